@@ -5,6 +5,7 @@ import { Logger } from "pino";
 import path from "path";
 import fs from "fs";
 import { projectStoreInstance } from "../services/projectStore";
+import { getConnector } from "../services/connectorRegistry";
 
 export class MigrationHandlers {
     constructor(
@@ -500,4 +501,35 @@ export class MigrationHandlers {
             this.rpc.sendError(id, { code: "IO_ERROR", message: String(e) });
         }
     }
+
+    /**
+     * Local and applied migrations of a connection, without the baseline
+     * that query.connectToDatabase creates on first connect
+     */
+    async handleMigrationStatus(params: any, id: number | string) {
+        try {
+            const { dbId } = params || {};
+
+            if (!dbId) {
+                return this.rpc.sendError(id, {
+                    code: "BAD_REQUEST",
+                    message: "Missing dbId",
+                });
+            }
+
+            const { conn, dbType } = await this.dbService.getDatabaseConnection(dbId);
+            const result = await (getConnector(dbType) as any).connectToDatabase(conn, dbId, { readOnly: true });
+
+            this.rpc.sendResponse(id, {
+                ok: true,
+                result: {
+                    baselined: result.baselined,
+                    migrations: result.migrations,
+                },
+            });
+        } catch (e: any) {
+            this.logger?.error({ e }, "migration.status failed");
+            this.rpc.sendError(id, { code: "IO_ERROR", message: String(e) });
+        }
+    }
 }
@@ -183,6 +183,9 @@ export function registerDbHandlers(
   rpcRegister(rpc, "migration.getSQL", (p, id) =>
     migrationHandlers.handleGetMigrationSQL(p, id)
   );
+  rpcRegister(rpc, "migration.status", (p, id) =>
+    migrationHandlers.handleMigrationStatus(p, id)
+  );
 
   // ==========================================
   // STATISTICS HANDLERS
@@ -1,3 +1,45 @@
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
 fn main() {
+    write_bundle_manifest();
     tauri_build::build()
 }
+
+/// Embed the SHA-256 of the bundled bridge binary and native binding, so the shell can skip tampered or stale copies.
+/// Files that are missing (e.g. before `bridge:package`) are left out, with a warning in release builds;
+/// the shell never runs an unlisted bundled file, so such a build falls back to the bridge scripts.
+fn write_bundle_manifest() {
+    let release = std::env::var("PROFILE").is_ok_and(|profile| profile == "release");
+    let target = std::env::var("TARGET").unwrap();
+    let ext = if target.contains("windows") { ".exe" } else { "" };
+    let files = [
+        ("bridge", PathBuf::from(format!("resources/bridge-{}{}", target, ext))),
+        ("better_sqlite3.node", PathBuf::from("resources/better_sqlite3.node")),
+    ];
+
+    let mut manifest = String::from("&[\n");
+    for (name, path) in &files {
+        println!("cargo:rerun-if-changed={}", path.display());
+        match sha256(path) {
+            Some(hash) => manifest.push_str(&format!("    ({:?}, {:?}),\n", name, hash)),
+            None if release => println!(
+                "cargo:warning={} is missing or unreadable and is left out of the integrity manifest; \
+                 run `npm run bridge:package` before a release build",
+                path.display()
+            ),
+            None => {}
+        }
+    }
+    manifest.push_str("]\n");
+
+    let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("bundle_manifest.rs");
+    std::fs::write(out, manifest).expect("failed to write bundle manifest");
+}
+
+fn sha256(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,413 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{broadcast, oneshot};
+
+/// How long a call waits for its response unless changed with `with_timeout`
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Notifications buffered per subscriber before the oldest are dropped
+const NOTIFICATION_BUFFER: usize = 1024;
+
+/// An error object the bridge answered with
+#[derive(Debug, Clone)]
+pub struct RpcError {
+    /// The bridge's string code (e.g. `BAD_REQUEST`), or the numeric JSON-RPC code if there is none
+    pub code: String,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn from_value(error: &Value) -> Self {
+        // Frames normalised to JSON-RPC 2.0 keep the bridge's code in `data.code`
+        let code = error
+            .get("data")
+            .and_then(|d| d.get("code"))
+            .or_else(|| error.get("code"))
+            .map(|c| match c {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .unwrap_or_default();
+        Self {
+            code,
+            message: error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            data: error.get("data").cloned(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// Writing to the bridge failed
+    Io(std::io::Error),
+    /// The bridge closed its stdout before answering
+    Closed,
+    /// No response within the client's timeout
+    Timeout { method: String, after: Duration },
+    /// The bridge answered with an error
+    Rpc(RpcError),
+    /// The handler answered `{ ok: false }`
+    Failed { method: String, message: String },
+    /// The response didn't have the expected shape
+    Decode { method: String, source: serde_json::Error },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to write to bridge: {}", e),
+            Self::Closed => write!(f, "bridge closed the connection"),
+            Self::Timeout { method, after } => write!(f, "{} timed out after {} ms", method, after.as_millis()),
+            Self::Rpc(e) => write!(f, "{}: {}", e.code, e.message),
+            Self::Failed { method, message } => write!(f, "{} failed: {}", method, message),
+            Self::Decode { method, source } => write!(f, "unexpected response to {}: {}", method, source),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// A notification the bridge sent without being asked, e.g. `query.result`
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub method: String,
+    pub params: Value,
+}
+
+/// State shared with the task reading bridge stdout
+struct Shared {
+    pending: Mutex<HashMap<u64, oneshot::Sender<Value>>>,
+    notifications: broadcast::Sender<Notification>,
+    closed: AtomicBool,
+}
+
+impl Shared {
+    fn dispatch(&self, frame: Value) {
+        if let Value::Array(frames) = frame {
+            for frame in frames {
+                self.dispatch(frame);
+            }
+            return;
+        }
+        match (frame.get("method").and_then(Value::as_str), frame.get("id")) {
+            (Some(method), None) => {
+                let notification = Notification {
+                    method: method.to_string(),
+                    params: frame.get("params").cloned().unwrap_or(Value::Null),
+                };
+                // Nobody listening is fine
+                let _ = self.notifications.send(notification);
+            }
+            (None, Some(id)) => {
+                let reply = id.as_u64().and_then(|id| self.pending.lock().unwrap().remove(&id));
+                if let Some(reply) = reply {
+                    let _ = reply.send(frame);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        // Dropping the senders wakes every waiting call with `Closed`
+        self.pending.lock().unwrap().clear();
+    }
+}
+
+async fn read_frames<R: AsyncRead + Unpin>(reader: R, shared: Arc<Shared>) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        // The bridge logs to stderr, so anything that isn't JSON here is noise
+        if let Ok(frame) = serde_json::from_str::<Value>(&line) {
+            shared.dispatch(frame);
+        }
+    }
+    shared.close();
+}
+
+/// Async JSON-RPC client for the bridge.
+/// Requests can be made concurrently; responses are matched by id and notifications broadcast to subscribers.
+/// Must be created inside a Tokio runtime, which drives the task reading bridge output.
+pub struct BridgeClient {
+    writer: tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
+    shared: Arc<Shared>,
+    next_id: AtomicU64,
+    timeout: Option<Duration>,
+    child: Mutex<Option<Child>>,
+}
+
+impl BridgeClient {
+    /// Speak the bridge protocol over any reader/writer pair, e.g. the stdio of a bridge started elsewhere
+    pub fn connect<R, W>(reader: R, writer: W) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (notifications, _) = broadcast::channel(NOTIFICATION_BUFFER);
+        let shared = Arc::new(Shared {
+            pending: Mutex::new(HashMap::new()),
+            notifications,
+            closed: AtomicBool::new(false),
+        });
+        tokio::spawn(read_frames(reader, shared.clone()));
+        Self {
+            writer: tokio::sync::Mutex::new(Box::new(writer)),
+            shared,
+            next_id: AtomicU64::new(1),
+            timeout: Some(DEFAULT_TIMEOUT),
+            child: Mutex::new(None),
+        }
+    }
+
+    /// Start a bridge process and talk to it over its stdin/stdout.
+    /// The process is killed when the client is dropped.
+    pub fn spawn(mut command: Command) -> std::io::Result<Self> {
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = command.spawn()?;
+        let missing = || std::io::Error::new(std::io::ErrorKind::BrokenPipe, "bridge stdio not captured");
+        let stdin = child.stdin.take().ok_or_else(missing)?;
+        let stdout = child.stdout.take().ok_or_else(missing)?;
+
+        let client = Self::connect(stdout, stdin);
+        *client.child.lock().unwrap() = Some(child);
+        Ok(client)
+    }
+
+    /// Give up on calls after `timeout`; `None` waits forever
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether the bridge has closed its output
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Relaxed)
+    }
+
+    /// Receive notifications sent from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.shared.notifications.subscribe()
+    }
+
+    async fn write_frame(&self, frame: &Value) -> Result<(), ClientError> {
+        let mut line = frame.to_string();
+        line.push('\n');
+        let mut writer = self.writer.lock().await;
+        writer.write_all(line.as_bytes()).await.map_err(ClientError::Io)?;
+        writer.flush().await.map_err(ClientError::Io)
+    }
+
+    /// Send a notification, which the bridge doesn't answer
+    pub async fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<(), ClientError> {
+        self.write_frame(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+    }
+
+    /// Call any bridge method and decode its whole `result`
+    pub async fn call<P: Serialize, T: DeserializeOwned>(&self, method: &str, params: P) -> Result<T, ClientError> {
+        let result = self.request(method, params).await?;
+        decode(method, result)
+    }
+
+    async fn request<P: Serialize>(&self, method: &str, params: P) -> Result<Value, ClientError> {
+        if self.is_closed() {
+            return Err(ClientError::Closed);
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (reply, response) = oneshot::channel();
+        self.shared.pending.lock().unwrap().insert(id, reply);
+        let forget = || self.shared.pending.lock().unwrap().remove(&id);
+
+        let frame = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(e) = self.write_frame(&frame).await {
+            forget();
+            return Err(e);
+        }
+
+        let response = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, response).await {
+                Ok(response) => response,
+                Err(_) => {
+                    forget();
+                    return Err(ClientError::Timeout {
+                        method: method.to_string(),
+                        after: timeout,
+                    });
+                }
+            },
+            None => response.await,
+        };
+        let mut response = response.map_err(|_| ClientError::Closed)?;
+
+        if let Some(error) = response.get("error") {
+            return Err(ClientError::Rpc(RpcError::from_value(error)));
+        }
+        Ok(response.get_mut("result").map(Value::take).unwrap_or(Value::Null))
+    }
+
+    /// Call a method answering `{ ok, <field> }` and decode `field`
+    pub(crate) async fn call_field<P: Serialize, T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+        field: &str,
+    ) -> Result<T, ClientError> {
+        let mut result = self.request(method, params).await?;
+        check_ok(method, &result)?;
+        let value = result.get_mut(field).map(Value::take).unwrap_or(Value::Null);
+        decode(method, value)
+    }
+
+    /// Call a method answering `{ ok, data }` and decode `data`
+    pub(crate) async fn call_data<P: Serialize, T: DeserializeOwned>(&self, method: &str, params: P) -> Result<T, ClientError> {
+        self.call_field(method, params, "data").await
+    }
+
+    /// Call a method answering a bare `{ ok }`
+    pub(crate) async fn call_ok<P: Serialize>(&self, method: &str, params: P) -> Result<(), ClientError> {
+        let result = self.request(method, params).await?;
+        check_ok(method, &result)
+    }
+
+    /// Close the bridge's stdin and wait for it to exit, killing it if it hasn't within `grace`
+    pub async fn shutdown(self, grace: Duration) -> std::io::Result<()> {
+        let _ = self.writer.lock().await.shutdown().await;
+        let Some(mut child) = self.child.lock().unwrap().take() else {
+            return Ok(());
+        };
+        if tokio::time::timeout(grace, child.wait()).await.is_err() {
+            child.kill().await?;
+        }
+        Ok(())
+    }
+}
+
+fn decode<T: DeserializeOwned>(method: &str, value: Value) -> Result<T, ClientError> {
+    serde_json::from_value(value).map_err(|source| ClientError::Decode {
+        method: method.to_string(),
+        source,
+    })
+}
+
+fn check_ok(method: &str, result: &Value) -> Result<(), ClientError> {
+    if result.get("ok") != Some(&Value::Bool(false)) {
+        return Ok(());
+    }
+    let message = result
+        .get("message")
+        .or_else(|| result.get("error"))
+        .map(|m| match m {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .unwrap_or_else(|| "unknown error".into());
+    Err(ClientError::Failed {
+        method: method.to_string(),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::query::QueryEvent;
+
+    fn run<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// A client whose bridge writes whatever `answer` returns for each frame, and the frames it was sent
+    fn client(answer: impl Fn(&Value) -> Vec<Value> + Send + 'static) -> (BridgeClient, Arc<Mutex<Vec<Value>>>) {
+        let (ours, theirs) = tokio::io::duplex(64 * 1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let (bridge_reader, mut bridge_writer) = tokio::io::split(theirs);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let seen = received.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(bridge_reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let frame: Value = serde_json::from_str(&line).unwrap();
+                seen.lock().unwrap().push(frame.clone());
+                for reply in answer(&frame) {
+                    let _ = bridge_writer.write_all(format!("{}\n", reply).as_bytes()).await;
+                }
+            }
+        });
+        (BridgeClient::connect(reader, writer), received)
+    }
+
+    #[test]
+    fn calls_send_json_rpc_and_unwrap_the_data() {
+        run(async {
+            let (client, received) = client(|frame| {
+                let id = frame["id"].clone();
+                vec![
+                    json!({ "method": "query.done", "params": { "sessionId": "s1", "rows": 0, "timeMs": 1, "status": "success" } }),
+                    json!({ "jsonrpc": "2.0", "id": id, "result": { "ok": true, "data": ["public", "audit"] } }),
+                ]
+            });
+            let mut notifications = client.subscribe();
+
+            let schemas = client.db().list_schemas("db1").await.unwrap();
+            assert_eq!(schemas, vec!["public", "audit"]);
+            assert_eq!(
+                received.lock().unwrap()[0],
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "db.listSchemas", "params": { "id": "db1" } })
+            );
+            let event = notifications.recv().await.unwrap().query_event();
+            assert!(matches!(event, Some(QueryEvent::Done(done)) if done.session_id == "s1"));
+        });
+    }
+
+    #[test]
+    fn failures_keep_the_bridge_code() {
+        run(async {
+            let (client, _) = client(|frame| {
+                let id = frame["id"].clone();
+                let reply = match frame["method"].as_str() {
+                    Some("db.get") => json!({
+                        "id": id,
+                        "error": { "code": -32003, "message": "no such database", "data": { "code": "NOT_FOUND" } },
+                    }),
+                    Some("db.delete") => json!({ "id": id, "result": { "ok": false, "message": "in use" } }),
+                    Some("db.getStats") => json!({ "id": id, "result": { "ok": true, "data": { "tables": "many" } } }),
+                    _ => return Vec::new(),
+                };
+                vec![reply]
+            });
+            let client = client.with_timeout(Some(Duration::from_millis(50)));
+
+            match client.db().get("x").await {
+                Err(ClientError::Rpc(e)) => {
+                    assert_eq!((e.code.as_str(), e.message.as_str()), ("NOT_FOUND", "no such database"))
+                }
+                other => panic!("{:?}", other.map(|_| ())),
+            }
+            assert!(matches!(client.db().delete("x").await, Err(ClientError::Failed { message, .. }) if message == "in use"));
+            assert!(matches!(client.db().get_stats("x").await, Err(ClientError::Decode { .. })));
+            assert!(matches!(client.db().touch("x").await, Err(ClientError::Timeout { .. })));
+        });
+    }
+}
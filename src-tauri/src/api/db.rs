@@ -0,0 +1,363 @@
+//! `db.*`: saved connections and their schema
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::client::{BridgeClient, ClientError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DbType {
+    Postgres,
+    Mysql,
+    Mariadb,
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SshAuthMethod {
+    Password,
+    PrivateKey,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth_method: SshAuthMethod,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Path to a key file or the PEM itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+}
+
+/// A saved connection as `db.list` and `db.get` return it; credentials stay in the bridge's keyring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub port: u16,
+    #[serde(default)]
+    pub user: String,
+    pub database: String,
+    #[serde(rename = "type", default)]
+    pub db_type: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub ssl: Option<bool>,
+    #[serde(default)]
+    pub sslmode: Option<String>,
+    #[serde(default)]
+    pub ssh: Option<SshConfig>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub last_accessed_at: Option<String>,
+}
+
+/// Parameters of `db.add`; SQLite only needs `name`, `database` (the file path) and `db_type`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewDatabase {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub db_type: DbType,
+    pub database: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssl: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sslmode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub schema: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub table_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbStats {
+    pub tables: u64,
+    pub rows: u64,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrimaryKeyInfo {
+    pub column_name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForeignKeyInfo {
+    pub constraint_name: String,
+    pub source_schema: String,
+    pub source_table: String,
+    pub source_column: String,
+    pub target_schema: String,
+    pub target_table: String,
+    pub target_column: String,
+    pub update_rule: String,
+    pub delete_rule: String,
+    pub ordinal_position: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexInfo {
+    pub table_name: String,
+    pub index_name: String,
+    pub column_name: String,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    pub index_type: String,
+    pub predicate: Option<String>,
+    pub ordinal_position: Option<u32>,
+    /// MySQL only
+    pub seq_in_index: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UniqueConstraintInfo {
+    pub constraint_name: String,
+    pub table_schema: String,
+    pub table_name: String,
+    pub column_name: String,
+    pub ordinal_position: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckConstraintInfo {
+    pub constraint_name: String,
+    pub table_schema: String,
+    pub table_name: String,
+    pub definition: Option<String>,
+    /// MySQL only
+    pub check_clause: Option<String>,
+}
+
+/// A column as `db.getSchema` describes it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ColumnSchema {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: String,
+    pub nullable: bool,
+    pub is_primary_key: bool,
+    pub is_foreign_key: bool,
+    pub default_value: Option<String>,
+    pub is_unique: bool,
+    pub is_serial: bool,
+    pub check_constraint: Option<String>,
+    pub comment: Option<String>,
+    pub max_length: Option<u64>,
+    pub ordinal_position: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableSchema {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub table_type: String,
+    #[serde(default)]
+    pub columns: Vec<ColumnSchema>,
+    #[serde(default)]
+    pub primary_keys: Vec<PrimaryKeyInfo>,
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKeyInfo>,
+    #[serde(default)]
+    pub indexes: Vec<IndexInfo>,
+    #[serde(default)]
+    pub unique_constraints: Vec<UniqueConstraintInfo>,
+    #[serde(default)]
+    pub check_constraints: Vec<CheckConstraintInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDetail {
+    pub name: String,
+    pub tables: Vec<TableSchema>,
+    /// Engine-specific extras such as `enumTypes` or `sequences`
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// What `db.getSchema` returns; schemas the bridge failed to read are left out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseSchema {
+    /// Database name, or the file path for SQLite
+    pub name: Option<String>,
+    pub schemas: Vec<SchemaDetail>,
+}
+
+/// Connection target of `db.connectTest`: a saved connection or one that hasn't been saved yet
+#[derive(Debug, Clone)]
+pub enum ConnectionTarget<'a> {
+    Saved(&'a str),
+    Unsaved(&'a NewDatabase),
+}
+
+pub struct Db<'a> {
+    client: &'a BridgeClient,
+}
+
+impl BridgeClient {
+    pub fn db(&self) -> Db<'_> {
+        Db { client: self }
+    }
+}
+
+impl Db<'_> {
+    pub async fn list(&self) -> Result<Vec<DatabaseInfo>, ClientError> {
+        self.client.call_data("db.list", json!({})).await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<DatabaseInfo, ClientError> {
+        self.client.call_data("db.get", json!({ "id": id })).await
+    }
+
+    /// Save a connection; returns its id
+    pub async fn add(&self, database: &NewDatabase) -> Result<String, ClientError> {
+        #[derive(Deserialize)]
+        struct Added {
+            id: String,
+        }
+        let added: Added = self.client.call_data("db.add", database).await?;
+        Ok(added.id)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), ClientError> {
+        self.client.call_ok("db.delete", json!({ "id": id })).await
+    }
+
+    /// Mark a connection as just used
+    pub async fn touch(&self, id: &str) -> Result<(), ClientError> {
+        self.client.call_ok("db.touch", json!({ "id": id })).await
+    }
+
+    /// Try to connect; the result is whatever the engine's connector reports
+    pub async fn test_connection(&self, target: ConnectionTarget<'_>) -> Result<Value, ClientError> {
+        let params = match target {
+            ConnectionTarget::Saved(id) => json!({ "id": id }),
+            ConnectionTarget::Unsaved(connection) => json!({ "connection": connection }),
+        };
+        self.client.call_data("db.connectTest", params).await
+    }
+
+    pub async fn list_tables(&self, id: &str, schema: Option<&str>) -> Result<Vec<TableInfo>, ClientError> {
+        self.client
+            .call_data("db.listTables", json!({ "id": id, "schema": schema }))
+            .await
+    }
+
+    pub async fn list_schemas(&self, id: &str) -> Result<Vec<String>, ClientError> {
+        self.client.call_data("db.listSchemas", json!({ "id": id })).await
+    }
+
+    /// Every schema with its tables, columns, keys and constraints
+    pub async fn get_schema(&self, id: &str) -> Result<DatabaseSchema, ClientError> {
+        self.client.call_data("db.getSchema", json!({ "id": id })).await
+    }
+
+    pub async fn get_stats(&self, id: &str) -> Result<DbStats, ClientError> {
+        self.client.call_data("db.getStats", json!({ "id": id })).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_connections_leave_out_unset_fields() {
+        let sqlite = NewDatabase {
+            name: "local".into(),
+            db_type: DbType::Sqlite,
+            database: "/data/app.db".into(),
+            host: None,
+            port: None,
+            user: None,
+            password: None,
+            ssl: None,
+            sslmode: None,
+            notes: None,
+            tags: Vec::new(),
+            ssh: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&sqlite).unwrap(),
+            json!({ "name": "local", "type": "sqlite", "database": "/data/app.db" })
+        );
+    }
+
+    #[test]
+    fn saved_connections_and_schemas_decode_from_bridge_output() {
+        let info: DatabaseInfo = serde_json::from_value(json!({
+            "id": "db1",
+            "name": "prod",
+            "database": "app",
+            "type": "postgres",
+            "ssh": { "host": "bastion", "port": 22, "username": "ops", "authMethod": "privateKey" },
+            "createdAt": "2026-01-01T00:00:00Z",
+            "updatedAt": "2026-01-02T00:00:00Z"
+        }))
+        .unwrap();
+        assert_eq!(info.db_type.as_deref(), Some("postgres"));
+        assert_eq!(info.ssh.unwrap().auth_method, SshAuthMethod::PrivateKey);
+        assert!(info.tags.is_empty() && info.last_accessed_at.is_none());
+
+        let schema: DatabaseSchema = serde_json::from_value(json!({
+            "name": "app",
+            "schemas": [{
+                "name": "public",
+                "tables": [{
+                    "name": "users",
+                    "type": "BASE TABLE",
+                    "columns": [{ "name": "id", "type": "integer", "isPrimaryKey": true, "ordinalPosition": 1 }],
+                    "primaryKeys": [{ "column_name": "id" }]
+                }],
+                "enumTypes": []
+            }]
+        }))
+        .unwrap();
+        let public = &schema.schemas[0];
+        assert!(public.extra.contains_key("enumTypes"));
+        let column = &public.tables[0].columns[0];
+        assert_eq!((column.column_type.as_str(), column.is_primary_key, column.nullable), ("integer", true, false));
+        assert_eq!(public.tables[0].primary_keys[0].column_name, "id");
+    }
+}
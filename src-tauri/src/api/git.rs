@@ -0,0 +1,248 @@
+//! `git.*`: version control of project directories
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::client::{BridgeClient, ClientError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    pub is_git_repo: bool,
+    pub branch: Option<String>,
+    /// Short hash of HEAD
+    pub head_commit: Option<String>,
+    /// Whether there are staged or unstaged changes
+    pub is_dirty: bool,
+    pub staged_count: u32,
+    pub unstaged_count: u32,
+    pub untracked_count: u32,
+    /// Commits ahead of upstream, `None` without one
+    pub ahead: Option<u32>,
+    pub behind: Option<u32>,
+    /// e.g. `origin/main`
+    pub upstream: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitFileChange {
+    /// Relative to the repository root
+    pub path: String,
+    /// `M`, `A`, `D`, `?` or `R`
+    pub status: String,
+    pub staged: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLogEntry {
+    pub hash: String,
+    pub full_hash: String,
+    #[serde(default)]
+    pub parents: Vec<String>,
+    pub author: String,
+    /// ISO 8601
+    pub date: String,
+    /// First line of the message
+    pub subject: String,
+    /// Branches and tags pointing at the commit
+    #[serde(default)]
+    pub refs: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitBranchInfo {
+    pub name: String,
+    pub current: bool,
+    pub upstream: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRemote {
+    pub name: String,
+    pub fetch_url: String,
+    pub push_url: String,
+}
+
+/// Options of `git.push`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PushOptions {
+    /// `origin` if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub force: bool,
+    pub set_upstream: bool,
+}
+
+#[derive(Deserialize)]
+struct Output {
+    output: String,
+}
+
+pub struct Git<'a> {
+    client: &'a BridgeClient,
+}
+
+impl BridgeClient {
+    pub fn git(&self) -> Git<'_> {
+        Git { client: self }
+    }
+}
+
+impl Git<'_> {
+    async fn call_in<T: serde::de::DeserializeOwned>(&self, method: &str, dir: &str, mut params: Value) -> Result<T, ClientError> {
+        params["dir"] = json!(dir);
+        self.client.call_data(method, params).await
+    }
+
+    pub async fn status(&self, dir: &str) -> Result<GitStatus, ClientError> {
+        self.call_in("git.status", dir, json!({})).await
+    }
+
+    /// Create a repository; `main` is used if no default branch is given
+    pub async fn init(&self, dir: &str, default_branch: Option<&str>) -> Result<GitStatus, ClientError> {
+        self.call_in("git.init", dir, json!({ "defaultBranch": default_branch })).await
+    }
+
+    pub async fn changes(&self, dir: &str) -> Result<Vec<GitFileChange>, ClientError> {
+        self.call_in("git.changes", dir, json!({})).await
+    }
+
+    pub async fn stage(&self, dir: &str, files: &[&str]) -> Result<(), ClientError> {
+        self.call_in("git.stage", dir, json!({ "files": files })).await
+    }
+
+    pub async fn stage_all(&self, dir: &str) -> Result<(), ClientError> {
+        self.call_in("git.stageAll", dir, json!({})).await
+    }
+
+    pub async fn unstage(&self, dir: &str, files: &[&str]) -> Result<(), ClientError> {
+        self.call_in("git.unstage", dir, json!({ "files": files })).await
+    }
+
+    /// Commit what is staged; returns the new commit's hash
+    pub async fn commit(&self, dir: &str, message: &str) -> Result<String, ClientError> {
+        #[derive(Deserialize)]
+        struct Commit {
+            hash: String,
+        }
+        let commit: Commit = self.call_in("git.commit", dir, json!({ "message": message })).await?;
+        Ok(commit.hash)
+    }
+
+    /// The most recent `count` commits, 20 if unset
+    pub async fn log(&self, dir: &str, count: Option<u32>) -> Result<Vec<GitLogEntry>, ClientError> {
+        self.call_in("git.log", dir, json!({ "count": count })).await
+    }
+
+    pub async fn branches(&self, dir: &str) -> Result<Vec<GitBranchInfo>, ClientError> {
+        self.call_in("git.branches", dir, json!({})).await
+    }
+
+    pub async fn create_branch(&self, dir: &str, name: &str) -> Result<(), ClientError> {
+        self.call_in::<Value>("git.createBranch", dir, json!({ "name": name })).await?;
+        Ok(())
+    }
+
+    pub async fn checkout(&self, dir: &str, name: &str) -> Result<(), ClientError> {
+        self.call_in::<Value>("git.checkout", dir, json!({ "name": name })).await?;
+        Ok(())
+    }
+
+    /// Throw away working tree changes to `files`
+    pub async fn discard(&self, dir: &str, files: &[&str]) -> Result<(), ClientError> {
+        self.call_in("git.discard", dir, json!({ "files": files })).await
+    }
+
+    /// Diff of one file, or the whole tree if `file` is unset
+    pub async fn diff(&self, dir: &str, file: Option<&str>, staged: bool) -> Result<String, ClientError> {
+        #[derive(Deserialize)]
+        struct Diff {
+            diff: String,
+        }
+        let diff: Diff = self
+            .call_in("git.diff", dir, json!({ "file": file, "staged": staged }))
+            .await?;
+        Ok(diff.diff)
+    }
+
+    pub async fn remote_list(&self, dir: &str) -> Result<Vec<GitRemote>, ClientError> {
+        self.call_in("git.remoteList", dir, json!({})).await
+    }
+
+    /// Push; returns git's output
+    pub async fn push(&self, dir: &str, options: &PushOptions) -> Result<String, ClientError> {
+        let output: Output = self.call_in("git.push", dir, json!(options)).await?;
+        Ok(output.output)
+    }
+
+    /// Pull from `origin` unless another remote is given; returns git's output
+    pub async fn pull(&self, dir: &str, remote: Option<&str>, branch: Option<&str>, rebase: bool) -> Result<String, ClientError> {
+        let output: Output = self
+            .call_in("git.pull", dir, json!({ "remote": remote, "branch": branch, "rebase": rebase }))
+            .await?;
+        Ok(output.output)
+    }
+
+    /// Fetch; returns git's output
+    pub async fn fetch(&self, dir: &str, remote: Option<&str>, prune: bool) -> Result<String, ClientError> {
+        let output: Output = self
+            .call_in("git.fetch", dir, json!({ "remote": remote, "prune": prune }))
+            .await?;
+        Ok(output.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_options_are_camel_case() {
+        assert_eq!(
+            serde_json::to_value(PushOptions::default()).unwrap(),
+            json!({ "force": false, "setUpstream": false })
+        );
+        let options = PushOptions {
+            remote: Some("origin".into()),
+            set_upstream: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({ "remote": "origin", "force": false, "setUpstream": true })
+        );
+    }
+
+    #[test]
+    fn status_and_log_decode_from_bridge_output() {
+        let status: GitStatus = serde_json::from_value(json!({
+            "isGitRepo": true,
+            "branch": "main",
+            "headCommit": "abc1234",
+            "isDirty": false,
+            "stagedCount": 0,
+            "unstagedCount": 1,
+            "untrackedCount": 2,
+            "ahead": null,
+            "behind": null,
+            "upstream": null
+        }))
+        .unwrap();
+        assert_eq!((status.unstaged_count, status.untracked_count, status.ahead), (1, 2, None));
+
+        let entry: GitLogEntry = serde_json::from_value(json!({
+            "hash": "abc1234",
+            "fullHash": "abc1234def",
+            "author": "dev",
+            "date": "2026-01-01T00:00:00Z",
+            "subject": "Initial"
+        }))
+        .unwrap();
+        assert!(entry.parents.is_empty() && entry.refs.is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+//! `migration.*`: generating, applying and rolling back migration files
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::client::{BridgeClient, ClientError};
+
+/// A column of a generated `CREATE TABLE` or `ADD COLUMN`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: String,
+    #[serde(default)]
+    pub not_nullable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<String>,
+    #[serde(default)]
+    pub is_primary_key: bool,
+}
+
+/// A foreign key of a generated `CREATE TABLE`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKeyDefinition {
+    pub constraint_name: String,
+    pub source_column: String,
+    pub target_schema: String,
+    pub target_table: String,
+    pub target_column: String,
+    /// `NO ACTION` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_delete: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_update: Option<String>,
+}
+
+/// One change of a generated `ALTER TABLE` migration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AlterOperation {
+    AddColumn { column: ColumnDefinition },
+    DropColumn { column_name: String },
+    RenameColumn { from: String, to: String },
+    AlterType { column_name: String, new_type: String },
+    SetNotNull { column_name: String },
+    DropNotNull { column_name: String },
+    SetDefault { column_name: String, default_value: String },
+    DropDefault { column_name: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DropMode {
+    Restrict,
+    /// Drop the foreign keys pointing at the table first
+    DetachFks,
+    Cascade,
+}
+
+/// Parameters of `migration.generateCreate`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTableMigration {
+    pub db_id: String,
+    pub schema_name: String,
+    pub table_name: String,
+    pub columns: Vec<ColumnDefinition>,
+    pub foreign_keys: Vec<ForeignKeyDefinition>,
+}
+
+/// Parameters of `migration.generateAlter`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlterTableMigration {
+    pub db_id: String,
+    pub schema_name: String,
+    pub table_name: String,
+    pub operations: Vec<AlterOperation>,
+}
+
+/// A migration file written to the project's migrations dir
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedMigration {
+    /// `YYYYMMDDHHmmss` timestamp the file name starts with
+    pub version: String,
+    pub filename: String,
+    pub filepath: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationSql {
+    pub up: String,
+    pub down: String,
+}
+
+/// The database `migration.applyMigrations` and `migration.applySnapshot` work on
+#[derive(Debug, Clone, Copy)]
+pub enum MigrationTarget<'a> {
+    Database(&'a str),
+    /// The database linked to a project
+    Project(&'a str),
+}
+
+impl MigrationTarget<'_> {
+    fn params(self) -> serde_json::Value {
+        match self {
+            Self::Database(id) => json!({ "dbId": id }),
+            Self::Project(id) => json!({ "projectId": id }),
+        }
+    }
+}
+
+pub struct Migration<'a> {
+    client: &'a BridgeClient,
+}
+
+impl BridgeClient {
+    pub fn migration(&self) -> Migration<'_> {
+        Migration { client: self }
+    }
+}
+
+impl Migration<'_> {
+    pub async fn generate_create(&self, params: &CreateTableMigration) -> Result<GeneratedMigration, ClientError> {
+        self.client.call_data("migration.generateCreate", params).await
+    }
+
+    pub async fn generate_alter(&self, params: &AlterTableMigration) -> Result<GeneratedMigration, ClientError> {
+        self.client.call_data("migration.generateAlter", params).await
+    }
+
+    pub async fn generate_drop(
+        &self,
+        db_id: &str,
+        schema_name: &str,
+        table_name: &str,
+        mode: DropMode,
+    ) -> Result<GeneratedMigration, ClientError> {
+        self.client
+            .call_data(
+                "migration.generateDrop",
+                json!({ "dbId": db_id, "schemaName": schema_name, "tableName": table_name, "mode": mode }),
+            )
+            .await
+    }
+
+    /// Apply one migration by version
+    pub async fn apply(&self, db_id: &str, version: &str) -> Result<(), ClientError> {
+        self.client
+            .call_ok("migration.apply", json!({ "dbId": db_id, "version": version }))
+            .await
+    }
+
+    /// Apply every pending migration; returns how many were applied
+    pub async fn apply_all(&self, target: MigrationTarget<'_>) -> Result<u64, ClientError> {
+        self.client
+            .call_field("migration.applyMigrations", target.params(), "count")
+            .await
+    }
+
+    /// Bring the database in line with the project's schema snapshot
+    pub async fn apply_snapshot(&self, target: MigrationTarget<'_>) -> Result<(), ClientError> {
+        self.client.call_ok("migration.applySnapshot", target.params()).await
+    }
+
+    pub async fn rollback(&self, db_id: &str, version: &str) -> Result<(), ClientError> {
+        self.client
+            .call_ok("migration.rollback", json!({ "dbId": db_id, "version": version }))
+            .await
+    }
+
+    /// Delete a migration file from the migrations dir
+    pub async fn delete(&self, db_id: &str, version: &str) -> Result<(), ClientError> {
+        self.client
+            .call_ok("migration.delete", json!({ "dbId": db_id, "version": version }))
+            .await
+    }
+
+    pub async fn get_sql(&self, db_id: &str, version: &str) -> Result<MigrationSql, ClientError> {
+        self.client
+            .call_data("migration.getSQL", json!({ "dbId": db_id, "version": version }))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alter_operations_match_the_bridge_shape() {
+        let operations = vec![
+            AlterOperation::AddColumn {
+                column: ColumnDefinition {
+                    name: "email".into(),
+                    column_type: "text".into(),
+                    not_nullable: true,
+                    default_value: None,
+                    is_primary_key: false,
+                },
+            },
+            AlterOperation::RenameColumn {
+                from: "mail".into(),
+                to: "email".into(),
+            },
+            AlterOperation::SetDefault {
+                column_name: "email".into(),
+                default_value: "''".into(),
+            },
+        ];
+        assert_eq!(
+            serde_json::to_value(&operations).unwrap(),
+            json!([
+                { "type": "ADD_COLUMN", "column": { "name": "email", "type": "text", "not_nullable": true, "is_primary_key": false } },
+                { "type": "RENAME_COLUMN", "from": "mail", "to": "email" },
+                { "type": "SET_DEFAULT", "column_name": "email", "default_value": "''" },
+            ])
+        );
+        assert_eq!(serde_json::to_value(DropMode::DetachFks).unwrap(), json!("DETACH_FKS"));
+    }
+
+    #[test]
+    fn targets_name_a_database_or_a_project() {
+        assert_eq!(MigrationTarget::Database("db1").params(), json!({ "dbId": "db1" }));
+        assert_eq!(MigrationTarget::Project("p1").params(), json!({ "projectId": "p1" }));
+    }
+}
@@ -0,0 +1,13 @@
+//! Typed async client for the bridge.
+//!
+//! Talks to a bridge process (or anything speaking its newline-delimited JSON-RPC) directly over stdio,
+//! without the desktop shell. Each method family has its own request/response types mirroring `bridge/src/types`.
+
+mod client;
+pub mod db;
+pub mod git;
+pub mod migration;
+pub mod project;
+pub mod query;
+
+pub use client::{BridgeClient, ClientError, Notification, RpcError};
@@ -0,0 +1,297 @@
+//! `project.*`: projects linked to a connection, and their saved queries and local config
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::client::{BridgeClient, ClientError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectStatus {
+    Active,
+    /// The connection it was linked to has been removed
+    Unlinked,
+}
+
+/// A project as listed by `project.list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSummary {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub engine: Option<String>,
+    pub database_id: Option<String>,
+    /// For imported projects, the repo they were cloned from
+    #[serde(default)]
+    pub source_path: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub status: Option<ProjectStatus>,
+}
+
+/// The full `relwave.json` of a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectMetadata {
+    pub version: u32,
+    pub id: String,
+    pub database_id: Option<String>,
+    #[serde(default)]
+    pub status: Option<ProjectStatus>,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub engine: Option<String>,
+    #[serde(default)]
+    pub default_schema: Option<String>,
+    #[serde(default)]
+    pub source_path: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Parameters of `project.create`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewProject {
+    pub database_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_schema: Option<String>,
+}
+
+/// Fields `project.update` can change; unset fields are left alone
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectUpdate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_schema: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedQuery {
+    pub id: String,
+    pub name: String,
+    pub sql: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Fields `project.updateQuery` can change; unset fields are left alone
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SavedQueryUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Per-developer settings kept out of git
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_url: Option<String>,
+    /// e.g. dev, staging or prod
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Local overrides of imported projects' metadata, so the tracked file is never modified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<ProjectUpdate>,
+}
+
+pub struct Project<'a> {
+    client: &'a BridgeClient,
+}
+
+impl BridgeClient {
+    pub fn project(&self) -> Project<'_> {
+        Project { client: self }
+    }
+}
+
+impl Project<'_> {
+    pub async fn list(&self) -> Result<Vec<ProjectSummary>, ClientError> {
+        self.client.call_data("project.list", json!({})).await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<ProjectMetadata, ClientError> {
+        self.client.call_data("project.get", json!({ "id": id })).await
+    }
+
+    /// The project linked to a connection, if there is one
+    pub async fn get_by_database_id(&self, database_id: &str) -> Result<Option<ProjectMetadata>, ClientError> {
+        self.client
+            .call_data("project.getByDatabaseId", json!({ "databaseId": database_id }))
+            .await
+    }
+
+    pub async fn create(&self, project: &NewProject) -> Result<ProjectMetadata, ClientError> {
+        self.client.call_data("project.create", project).await
+    }
+
+    pub async fn update(&self, id: &str, update: &ProjectUpdate) -> Result<ProjectMetadata, ClientError> {
+        let mut params = json!(update);
+        params["id"] = json!(id);
+        self.client.call_data("project.update", params).await
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), ClientError> {
+        self.client.call_ok("project.delete", json!({ "id": id })).await
+    }
+
+    /// Directory the project's files live in
+    pub async fn get_dir(&self, project_id: &str) -> Result<String, ClientError> {
+        #[derive(Deserialize)]
+        struct Dir {
+            dir: String,
+        }
+        let data: Dir = self
+            .client
+            .call_data("project.getDir", json!({ "projectId": project_id }))
+            .await?;
+        Ok(data.dir)
+    }
+
+    /// Whether the migration lock still matches the project's schema snapshot
+    pub async fn verify_lock(&self, project_id: &str) -> Result<bool, ClientError> {
+        self.client
+            .call_field("project.verifyLock", json!({ "projectId": project_id }), "isValid")
+            .await
+    }
+
+    /// SQL recreating the project's schema snapshot
+    pub async fn generate_sql(&self, project_id: &str) -> Result<String, ClientError> {
+        #[derive(Deserialize)]
+        struct Sql {
+            sql: String,
+        }
+        let data: Sql = self
+            .client
+            .call_data("project.generateSQL", json!({ "projectId": project_id }))
+            .await?;
+        Ok(data.sql)
+    }
+
+    pub async fn get_queries(&self, project_id: &str) -> Result<Vec<SavedQuery>, ClientError> {
+        #[derive(Deserialize)]
+        struct QueriesFile {
+            queries: Vec<SavedQuery>,
+        }
+        let file: Option<QueriesFile> = self
+            .client
+            .call_data("project.getQueries", json!({ "projectId": project_id }))
+            .await?;
+        Ok(file.map(|f| f.queries).unwrap_or_default())
+    }
+
+    pub async fn add_query(
+        &self,
+        project_id: &str,
+        name: &str,
+        sql: &str,
+        description: Option<&str>,
+    ) -> Result<SavedQuery, ClientError> {
+        self.client
+            .call_data(
+                "project.addQuery",
+                json!({ "projectId": project_id, "name": name, "sql": sql, "description": description }),
+            )
+            .await
+    }
+
+    pub async fn update_query(
+        &self,
+        project_id: &str,
+        query_id: &str,
+        update: &SavedQueryUpdate,
+    ) -> Result<SavedQuery, ClientError> {
+        let mut params = json!(update);
+        params["projectId"] = json!(project_id);
+        params["queryId"] = json!(query_id);
+        self.client.call_data("project.updateQuery", params).await
+    }
+
+    pub async fn delete_query(&self, project_id: &str, query_id: &str) -> Result<(), ClientError> {
+        self.client
+            .call_ok("project.deleteQuery", json!({ "projectId": project_id, "queryId": query_id }))
+            .await
+    }
+
+    /// The project's local config; empty if it has none yet
+    pub async fn get_local_config(&self, project_id: &str) -> Result<LocalConfig, ClientError> {
+        let config: Option<LocalConfig> = self
+            .client
+            .call_data("project.getLocalConfig", json!({ "projectId": project_id }))
+            .await?;
+        Ok(config.unwrap_or_default())
+    }
+
+    pub async fn save_local_config(&self, project_id: &str, config: &LocalConfig) -> Result<LocalConfig, ClientError> {
+        self.client
+            .call_data("project.saveLocalConfig", json!({ "projectId": project_id, "config": config }))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_only_carry_what_changes() {
+        assert_eq!(serde_json::to_value(ProjectUpdate::default()).unwrap(), json!({}));
+        let update = SavedQueryUpdate {
+            sql: Some("select 2".into()),
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_value(&update).unwrap(), json!({ "sql": "select 2" }));
+        let config = LocalConfig {
+            environment: Some("dev".into()),
+            overrides: Some(ProjectUpdate {
+                default_schema: Some("app".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            json!({ "environment": "dev", "overrides": { "defaultSchema": "app" } })
+        );
+    }
+
+    #[test]
+    fn projects_decode_from_bridge_output() {
+        let summary: ProjectSummary = serde_json::from_value(json!({
+            "id": "p1",
+            "name": "Shop",
+            "databaseId": null,
+            "createdAt": "2026-01-01T00:00:00Z",
+            "updatedAt": "2026-01-01T00:00:00Z",
+            "status": "unlinked"
+        }))
+        .unwrap();
+        assert_eq!(summary.status, Some(ProjectStatus::Unlinked));
+        assert!(summary.database_id.is_none() && summary.source_path.is_none());
+    }
+}
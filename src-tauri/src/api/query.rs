@@ -0,0 +1,299 @@
+//! `query.*`: query sessions, streamed results and table data
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::client::{BridgeClient, ClientError, Notification};
+use super::db::{DbType, PrimaryKeyInfo};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedSession {
+    pub session_id: String,
+    /// Milliseconds since the epoch
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub session_id: String,
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    pub created_at: u64,
+    /// Whether a query is running that can be cancelled
+    pub has_cancel: bool,
+}
+
+/// Parameters of `query.run`; rows stream back as `query.result` notifications
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunQuery {
+    pub session_id: String,
+    pub db_id: String,
+    pub sql: String,
+    /// Rows per `query.result` batch; the bridge uses 200 if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<u32>,
+}
+
+/// Parameters of `query.fetchTableData`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchTableData {
+    pub db_id: String,
+    pub schema_name: String,
+    pub table_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+}
+
+/// One page of a table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TablePage {
+    pub rows: Vec<Map<String, Value>>,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryInfo {
+    pub sql_preview: String,
+    pub db_id: String,
+    pub db_type: DbType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStarted {
+    pub session_id: String,
+    pub info: QueryInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub session_id: String,
+    pub batch_index: u64,
+    pub rows: Vec<Value>,
+    pub columns: Vec<Value>,
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryProgress {
+    pub session_id: String,
+    pub rows_so_far: u64,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryDone {
+    pub session_id: String,
+    pub rows: u64,
+    pub time_ms: u64,
+    /// `success` or `cancelled`
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryFailure {
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryError {
+    pub session_id: String,
+    pub error: QueryFailure,
+}
+
+/// A `query.*` notification about a running query
+#[derive(Debug, Clone)]
+pub enum QueryEvent {
+    Started(QueryStarted),
+    Result(QueryResult),
+    Progress(QueryProgress),
+    Done(QueryDone),
+    Error(QueryError),
+}
+
+impl QueryEvent {
+    pub fn session_id(&self) -> &str {
+        match self {
+            Self::Started(e) => &e.session_id,
+            Self::Result(e) => &e.session_id,
+            Self::Progress(e) => &e.session_id,
+            Self::Done(e) => &e.session_id,
+            Self::Error(e) => &e.session_id,
+        }
+    }
+
+    /// Whether this is the last event of the query
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Done(_) | Self::Error(_))
+    }
+}
+
+impl Notification {
+    /// Decode a `query.*` notification; `None` for other methods or unexpected params
+    pub fn query_event(&self) -> Option<QueryEvent> {
+        let params = self.params.clone();
+        match self.method.as_str() {
+            "query.started" => serde_json::from_value(params).ok().map(QueryEvent::Started),
+            "query.result" => serde_json::from_value(params).ok().map(QueryEvent::Result),
+            "query.progress" => serde_json::from_value(params).ok().map(QueryEvent::Progress),
+            "query.done" => serde_json::from_value(params).ok().map(QueryEvent::Done),
+            "query.error" => serde_json::from_value(params).ok().map(QueryEvent::Error),
+            _ => None,
+        }
+    }
+}
+
+pub struct Query<'a> {
+    client: &'a BridgeClient,
+}
+
+impl BridgeClient {
+    pub fn query(&self) -> Query<'_> {
+        Query { client: self }
+    }
+}
+
+impl Query<'_> {
+    pub async fn create_session(&self, connection_id: Option<&str>) -> Result<CreatedSession, ClientError> {
+        self.client
+            .call_data("query.createSession", json!({ "connectionId": connection_id }))
+            .await
+    }
+
+    /// Cancel the query running in a session; returns whether there was one
+    pub async fn cancel(&self, session_id: &str) -> Result<bool, ClientError> {
+        #[derive(Deserialize)]
+        struct Cancelled {
+            cancelled: bool,
+        }
+        let data: Cancelled = self
+            .client
+            .call_data("query.cancel", json!({ "sessionId": session_id }))
+            .await?;
+        Ok(data.cancelled)
+    }
+
+    pub async fn get_session(&self, session_id: &str) -> Result<SessionInfo, ClientError> {
+        self.client
+            .call_data("query.getSession", json!({ "sessionId": session_id }))
+            .await
+    }
+
+    pub async fn list_sessions(&self) -> Result<Vec<SessionInfo>, ClientError> {
+        #[derive(Deserialize)]
+        struct Sessions {
+            sessions: Vec<SessionInfo>,
+        }
+        let data: Sessions = self.client.call_data("query.listSessions", json!({})).await?;
+        Ok(data.sessions)
+    }
+
+    /// Forget a finished session; returns whether it existed
+    pub async fn destroy_session(&self, session_id: &str) -> Result<bool, ClientError> {
+        #[derive(Deserialize)]
+        struct Removed {
+            removed: bool,
+        }
+        let data: Removed = self
+            .client
+            .call_data("query.destroySession", json!({ "sessionId": session_id }))
+            .await?;
+        Ok(data.removed)
+    }
+
+    /// Start a query. Returns once it is running; subscribe before calling to see its `QueryEvent`s.
+    pub async fn run(&self, query: &RunQuery) -> Result<(), ClientError> {
+        self.client.call_ok("query.run", query).await
+    }
+
+    pub async fn fetch_table_data(&self, params: &FetchTableData) -> Result<TablePage, ClientError> {
+        self.client.call_data("query.fetchTableData", params).await
+    }
+
+    pub async fn list_primary_keys(
+        &self,
+        db_id: &str,
+        schema_name: &str,
+        table_name: &str,
+    ) -> Result<Vec<PrimaryKeyInfo>, ClientError> {
+        self.client
+            .call_field(
+                "query.listPrimaryKeys",
+                json!({ "dbId": db_id, "schemaName": schema_name, "tableName": table_name }),
+                "primaryKeys",
+            )
+            .await
+    }
+
+    pub async fn drop_table(&self, db_id: &str, schema_name: &str, table_name: &str) -> Result<(), ClientError> {
+        self.client
+            .call_ok(
+                "query.dropTable",
+                json!({ "dbId": db_id, "schemaName": schema_name, "tableName": table_name }),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(method: &str, params: Value) -> Option<QueryEvent> {
+        Notification {
+            method: method.into(),
+            params,
+        }
+        .query_event()
+    }
+
+    #[test]
+    fn run_parameters_are_camel_case() {
+        let run = RunQuery {
+            session_id: "s1".into(),
+            db_id: "db1".into(),
+            sql: "select 1".into(),
+            batch_size: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&run).unwrap(),
+            json!({ "sessionId": "s1", "dbId": "db1", "sql": "select 1" })
+        );
+    }
+
+    #[test]
+    fn query_notifications_decode_to_events() {
+        let started = event(
+            "query.started",
+            json!({ "sessionId": "s1", "info": { "sqlPreview": "select 1", "dbId": "db1", "dbType": "sqlite" } }),
+        )
+        .unwrap();
+        assert!(matches!(&started, QueryEvent::Started(e) if e.info.db_type == DbType::Sqlite));
+        assert!(!started.is_final());
+
+        let result = event(
+            "query.result",
+            json!({ "sessionId": "s1", "batchIndex": 0, "rows": [[1]], "columns": ["a"], "complete": true }),
+        )
+        .unwrap();
+        assert_eq!(result.session_id(), "s1");
+        let done = event("query.done", json!({ "sessionId": "s1", "rows": 1, "timeMs": 3, "status": "success" })).unwrap();
+        assert!(done.is_final());
+        let error = event("query.error", json!({ "sessionId": "s1", "error": { "message": "syntax" } })).unwrap();
+        assert!(matches!(&error, QueryEvent::Error(e) if e.error.message == "syntax"));
+
+        assert!(event("query.progress", json!({ "sessionId": "s1" })).is_none());
+        assert!(event("bridge.ready", json!({ "sessionId": "s1" })).is_none());
+    }
+}
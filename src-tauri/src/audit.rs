@@ -0,0 +1,518 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+
+use crate::bridge::{mutating_statements, Redactor, RpcRequest, SafetyGuard, BACKGROUND_WINDOW};
+
+/// Hash that the first entry of a new log chains from
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Results larger than this (serialized) are not copied into the log
+const MAX_RESULT_BYTES: usize = 4096;
+
+/// Bridge methods that are always audited
+const AUDITED_METHODS: &[&str] = &[
+    "query.dropTable",
+    "query.alterTable",
+    "query.deleteRow",
+    "migration.apply",
+    "migration.applyMigrations",
+    "migration.applySnapshot",
+    "migration.rollback",
+    "git.push",
+    "db.delete",
+];
+
+/// The hashed part of an audit entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: String,
+    pub user: String,
+    pub window: String,
+    pub method: String,
+    pub connection_id: Option<String>,
+    pub sql: Option<String>,
+    pub params: Value,
+    pub outcome: String,
+    pub error: Option<Value>,
+    pub result: Option<Value>,
+    pub duration_ms: u64,
+    pub prev_hash: String,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    #[serde(flatten)]
+    pub record: AuditRecord,
+    pub hash: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+    pub method: Option<String>,
+    pub connection_id: Option<String>,
+    /// RFC 3339 timestamp; only entries at or after it are returned
+    pub since: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries: u64,
+    /// Sequence number (or line number, if unparseable) of the first broken entry
+    pub broken_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// What happened to an audited request, as recorded in its entry
+pub enum Outcome<'a> {
+    /// Written to the bridge; how it ended is recorded in a later entry
+    Sent,
+    /// The bridge's response
+    Response(&'a Value),
+    /// The bridge's response to a request the shell had already given up on
+    Late(&'a Value),
+    /// The shell gave up on the request, e.g. `timeout`, `cancelled` or `abandoned`, with the error it reported
+    Aborted(&'static str, Value),
+}
+
+/// Last link of the chain, also stored outside the log so truncating or deleting the log shows up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ChainHead {
+    seq: u64,
+    hash: String,
+}
+
+/// Append-only, hash-chained log of destructive bridge operations
+pub struct AuditLog {
+    path: PathBuf,
+    head_path: PathBuf,
+    head: Mutex<ChainHead>,
+}
+
+impl AuditLog {
+    /// Open the log at `path`, resuming the chain from its last entry or from the head stored at `head_path`,
+    /// whichever is further along
+    pub fn open(path: PathBuf, head_path: PathBuf) -> Self {
+        let mut head = read_head(&head_path).unwrap_or(ChainHead {
+            seq: 0,
+            hash: GENESIS_HASH.to_string(),
+        });
+        if let Some(last) = read_entries(&path).ok().and_then(|e| e.into_iter().last()) {
+            if last.record.seq >= head.seq {
+                head = ChainHead {
+                    seq: last.record.seq,
+                    hash: last.hash,
+                };
+            }
+        }
+
+        Self {
+            path,
+            head_path,
+            head: Mutex::new(head),
+        }
+    }
+
+    /// Chain and append a record; `seq` and `prev_hash` are filled in here
+    pub fn append(&self, mut record: AuditRecord) -> Result<AuditEntry, String> {
+        let mut head = self.head.lock().unwrap();
+        record.seq = head.seq + 1;
+        record.prev_hash = head.hash.clone();
+        let entry = AuditEntry {
+            hash: hash_record(&record)?,
+            record,
+        };
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create audit dir: {}", e))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("failed to open audit log: {}", e))?;
+        let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| format!("failed to write audit log: {}", e))?;
+        file.sync_data().map_err(|e| format!("failed to sync audit log: {}", e))?;
+
+        *head = ChainHead {
+            seq: entry.record.seq,
+            hash: entry.hash.clone(),
+        };
+        let json = serde_json::to_string(&*head).map_err(|e| e.to_string())?;
+        if let Some(dir) = self.head_path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create audit dir: {}", e))?;
+        }
+        std::fs::write(&self.head_path, json).map_err(|e| format!("failed to save audit chain head: {}", e))?;
+        Ok(entry)
+    }
+
+    /// Entries matching `filter`, newest first
+    pub fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+        let _head = self.head.lock().unwrap();
+        let since = filter
+            .since
+            .as_deref()
+            .map(chrono::DateTime::parse_from_rfc3339)
+            .transpose()
+            .map_err(|e| format!("invalid since timestamp: {}", e))?;
+
+        let entries = read_entries(&self.path)?
+            .into_iter()
+            .rev()
+            .filter(|e| filter.method.as_ref().is_none_or(|m| &e.record.method == m))
+            .filter(|e| {
+                filter
+                    .connection_id
+                    .as_ref()
+                    .is_none_or(|c| e.record.connection_id.as_ref() == Some(c))
+            })
+            .filter(|e| {
+                since.is_none_or(|since| {
+                    chrono::DateTime::parse_from_rfc3339(&e.record.timestamp)
+                        .is_ok_and(|ts| ts >= since)
+                })
+            })
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(entries)
+    }
+
+    /// Walk the whole chain and check every link and hash, and that it reaches the stored head
+    pub fn verify(&self) -> AuditVerification {
+        let _head = self.head.lock().unwrap();
+        let stored_head = read_head(&self.head_path);
+        let broken = |at: u64, count: u64, error: String| AuditVerification {
+            valid: false,
+            entries: count,
+            broken_at: Some(at),
+            error: Some(error),
+        };
+        let lines = match File::open(&self.path) {
+            Ok(f) => BufReader::new(f).lines().collect::<Vec<_>>(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return AuditVerification {
+                    valid: false,
+                    entries: 0,
+                    broken_at: None,
+                    error: Some(format!("failed to open audit log: {}", e)),
+                }
+            }
+        };
+
+        let mut expected_seq = 1;
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for (index, line) in lines.into_iter().enumerate() {
+            let line_no = index as u64 + 1;
+            let count = expected_seq - 1;
+            let line = match line {
+                Ok(l) => l,
+                Err(e) => return broken(line_no, count, format!("unreadable line: {}", e)),
+            };
+            let entry: AuditEntry = match serde_json::from_str(&line) {
+                Ok(e) => e,
+                Err(e) => return broken(line_no, count, format!("unparseable entry: {}", e)),
+            };
+            let seq = entry.record.seq;
+            if seq != expected_seq {
+                return broken(seq, count, format!("expected sequence {}, found {}", expected_seq, seq));
+            }
+            if entry.record.prev_hash != prev_hash {
+                return broken(seq, count, "previous hash does not match".into());
+            }
+            match hash_record(&entry.record) {
+                Ok(hash) if hash == entry.hash => {}
+                _ => return broken(seq, count, "entry hash does not match its contents".into()),
+            }
+            if stored_head.as_ref().is_some_and(|h| h.seq == seq && h.hash != entry.hash) {
+                return broken(seq, count, "entry does not match the stored chain head".into());
+            }
+            prev_hash = entry.hash;
+            expected_seq += 1;
+        }
+
+        let entries = expected_seq - 1;
+        if let Some(head) = stored_head.filter(|h| h.seq > entries) {
+            return broken(
+                entries + 1,
+                entries,
+                format!("log ends at entry {} but the chain head is entry {}: entries were removed", entries, head.seq),
+            );
+        }
+        AuditVerification {
+            valid: true,
+            entries,
+            broken_at: None,
+            error: None,
+        }
+    }
+}
+
+fn read_head(path: &Path) -> Option<ChainHead> {
+    let json = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn hash_record(record: &AuditRecord) -> Result<String, String> {
+    let canonical = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    Ok(format!("{:x}", Sha256::digest(canonical.as_bytes())))
+}
+
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>, String> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to open audit log: {}", e)),
+    };
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+/// Name of the OS user running the app
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".into())
+}
+
+/// Whether requests like this one are audited
+fn is_audited(request: &RpcRequest) -> bool {
+    AUDITED_METHODS.contains(&request.method.as_str())
+        || (request.method == "query.run"
+            && request.param_str("sql").is_some_and(|s| !mutating_statements(s).is_empty()))
+}
+
+/// Build the audit record for a request, or `None` if the request is not audited.
+/// `started_at` is when the request was sent; `safety` resolves a `projectId` to its connection.
+pub fn record_for(
+    request: &RpcRequest,
+    window: &str,
+    started_at: Instant,
+    outcome: Outcome,
+    redactor: Option<&Redactor>,
+    safety: Option<&SafetyGuard>,
+) -> Option<AuditRecord> {
+    if window == BACKGROUND_WINDOW || !is_audited(request) {
+        return None;
+    }
+
+    let mut params = request.params.clone();
+    let mut sql = request.param_str("sql").map(str::to_string);
+    if let Some(redactor) = redactor {
+        redactor.redact_json(&mut params);
+        sql = sql.map(|s| redactor.redact(&s));
+    }
+
+    let answered = |response: &Value, success: &'static str, failure: &'static str| {
+        let error = response.get("error").cloned();
+        let result = response
+            .get("result")
+            .filter(|r| r.to_string().len() <= MAX_RESULT_BYTES)
+            .cloned();
+        (if error.is_some() { failure } else { success }, error, result)
+    };
+    let (outcome, error, result) = match outcome {
+        Outcome::Sent => ("sent", None, None),
+        Outcome::Response(response) => answered(response, "success", "error"),
+        Outcome::Late(response) => answered(response, "late success", "late error"),
+        Outcome::Aborted(label, error) => (label, Some(error), None),
+    };
+    Some(AuditRecord {
+        seq: 0,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        user: current_user(),
+        window: window.to_string(),
+        method: request.method.clone(),
+        connection_id: match safety {
+            Some(safety) => safety.connection_id(request),
+            None => request.param_str("dbId").map(str::to_string),
+        }
+        .or_else(|| request.param_str("id").map(str::to_string)),
+        sql,
+        params,
+        outcome: outcome.into(),
+        error,
+        result,
+        duration_ms: started_at.elapsed().as_millis() as u64,
+        prev_hash: String::new(),
+    })
+}
+
+/// Record what happened to a request if it is one we audit.
+/// Responses are already redacted when they get here.
+pub fn observe(app_handle: &AppHandle, request: &RpcRequest, window: &str, started_at: Instant, outcome: Outcome) {
+    let Some(log) = app_handle.try_state::<AuditLog>() else {
+        return;
+    };
+    let redactor = app_handle.try_state::<Redactor>();
+    let safety = app_handle.try_state::<SafetyGuard>();
+    let Some(record) =
+        record_for(request, window, started_at, outcome, redactor.as_deref(), safety.as_deref())
+    else {
+        return;
+    };
+
+    if let Err(e) = log.append(record) {
+        crate::diagnostics::shell_log(app_handle, format!("Failed to append audit entry: {}", e));
+    }
+}
+
+/// Query the audit log, newest entries first
+#[tauri::command]
+pub fn audit_query(
+    filter: Option<AuditFilter>,
+    log: State<'_, AuditLog>,
+) -> Result<Vec<AuditEntry>, String> {
+    log.query(&filter.unwrap_or_default())
+}
+
+/// Verify the hash chain of the audit log
+#[tauri::command]
+pub fn audit_verify(log: State<'_, AuditLog>) -> AuditVerification {
+    log.verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::RequestId;
+    use serde_json::json;
+
+    fn request(method: &str, params: Value) -> RpcRequest {
+        RpcRequest {
+            id: RequestId::Number(1),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    fn open(dir: &tempfile::TempDir) -> AuditLog {
+        AuditLog::open(dir.path().join("audit.log"), dir.path().join("audit-head.json"))
+    }
+
+    fn append(log: &AuditLog, outcome: Outcome) -> AuditEntry {
+        let drop = request("query.dropTable", json!({ "dbId": "db1", "table": "users" }));
+        log.append(record_for(&drop, "main", Instant::now(), outcome, None, None).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn only_destructive_requests_are_audited() {
+        let select = request("query.run", json!({ "dbId": "db1", "sql": "SELECT 1" }));
+        assert!(record_for(&select, "main", Instant::now(), Outcome::Sent, None, None).is_none());
+
+        let delete = request("query.run", json!({ "dbId": "db1", "sql": "DELETE FROM users" }));
+        let record = record_for(&delete, "main", Instant::now(), Outcome::Sent, None, None).unwrap();
+        assert_eq!(record.outcome, "sent");
+        assert_eq!(record.connection_id.as_deref(), Some("db1"));
+        assert_eq!(record.sql.as_deref(), Some("DELETE FROM users"));
+    }
+
+    #[test]
+    fn project_migrations_are_logged_against_the_linked_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = dir.path().join("index.json");
+        std::fs::write(&index, json!({ "projects": [{ "id": "p1", "databaseId": "db1" }] }).to_string()).unwrap();
+        let safety = SafetyGuard::load(None, Some(index));
+
+        let rollback = request("migration.rollback", json!({ "projectId": "p1", "version": "002" }));
+        let record = record_for(&rollback, "main", Instant::now(), Outcome::Sent, None, Some(&safety)).unwrap();
+        assert_eq!(record.connection_id.as_deref(), Some("db1"));
+
+        let delete = request("db.delete", json!({ "id": "db2" }));
+        let record = record_for(&delete, "main", Instant::now(), Outcome::Sent, None, Some(&safety)).unwrap();
+        assert_eq!(record.connection_id.as_deref(), Some("db2"));
+    }
+
+    #[test]
+    fn outcomes_are_labelled() {
+        let drop = request("query.dropTable", json!({ "dbId": "db1" }));
+        let label = |outcome: Outcome| record_for(&drop, "main", Instant::now(), outcome, None, None).unwrap().outcome;
+        let success = json!({ "id": 1, "result": true });
+        let failure = json!({ "id": 1, "error": {} });
+
+        assert_eq!(label(Outcome::Response(&success)), "success");
+        assert_eq!(label(Outcome::Response(&failure)), "error");
+        assert_eq!(label(Outcome::Late(&success)), "late success");
+        assert_eq!(label(Outcome::Aborted("timeout", json!({ "code": -32001 }))), "timeout");
+
+        let big = json!({ "id": 1, "result": "x".repeat(MAX_RESULT_BYTES) });
+        let record = record_for(&drop, "main", Instant::now(), Outcome::Response(&big), None, None).unwrap();
+        assert!(record.result.is_none());
+    }
+
+    #[test]
+    fn the_chain_verifies_and_resumes_after_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = open(&dir);
+        let first = append(&log, Outcome::Sent);
+        append(&log, Outcome::Response(&json!({ "id": 1, "result": true })));
+        assert_eq!(first.record.prev_hash, GENESIS_HASH);
+
+        let reopened = open(&dir);
+        let third = append(&reopened, Outcome::Sent);
+        assert_eq!(third.record.seq, 3);
+        let verification = reopened.verify();
+        assert!(verification.valid, "{:?}", verification);
+        assert_eq!(verification.entries, 3);
+        assert_eq!(reopened.query(&AuditFilter::default()).unwrap()[0].record.seq, 3);
+    }
+
+    #[test]
+    fn edited_entries_break_the_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = open(&dir);
+        for _ in 0..3 {
+            append(&log, Outcome::Sent);
+        }
+        let path = dir.path().join("audit.log");
+        let mut lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(str::to_string).collect();
+        lines[1] = lines[1].replace("\"users\"", "\"orders\"");
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        let verification = log.verify();
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at, Some(2));
+        assert_eq!(verification.entries, 1);
+    }
+
+    #[test]
+    fn truncated_or_deleted_logs_are_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = open(&dir);
+        for _ in 0..3 {
+            append(&log, Outcome::Sent);
+        }
+        let path = dir.path().join("audit.log");
+        let text = std::fs::read_to_string(&path).unwrap();
+        let kept: Vec<&str> = text.lines().take(2).collect();
+        std::fs::write(&path, kept.join("\n") + "\n").unwrap();
+
+        let verification = log.verify();
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at, Some(3));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(!log.verify().valid);
+        // New entries keep chaining from the stored head, so the gap stays visible
+        assert_eq!(open(&dir).verify().entries, 0);
+        assert_eq!(append(&open(&dir), Outcome::Sent).record.seq, 4);
+    }
+}
@@ -0,0 +1,103 @@
+use serde_json::Value;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use super::pending::PendingRequests;
+use super::policy::{self, MAIN_WINDOW};
+use super::process::BridgeProcess;
+use super::protocol::{self, RequestId, RpcRequest};
+use super::redact::Redactor;
+use super::safety::SafetyGuard;
+use super::timeouts::RequestTimeouts;
+use crate::audit::{self, Outcome};
+
+/// How long to wait for a reply when the request timeouts are not available
+const SHELL_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Extra wait beyond the method's timeout, so the watchdog's timeout error arrives first
+const REPLY_GRACE: Duration = Duration::from_secs(5);
+
+/// Recorded as the window of requests the shell makes on its own behalf
+pub const SHELL_WINDOW: &str = "shell";
+
+/// Window of the shell's own housekeeping requests, such as the tray's connection list.
+/// They are left out of the traffic inspector, metrics, recordings and the audit log.
+pub const BACKGROUND_WINDOW: &str = "shell-background";
+
+/// Send a request on behalf of the shell and wait for its `result`.
+/// Goes through the same policy and safety checks as the main window. Blocks, so call it off the main thread.
+pub fn request(app_handle: &AppHandle, method: &str, params: Value) -> Result<Value, String> {
+    send(app_handle, method, params, SHELL_WINDOW)
+}
+
+/// `request` for housekeeping that should not show up in the user's traffic (see `BACKGROUND_WINDOW`)
+pub fn background_request(app_handle: &AppHandle, method: &str, params: Value) -> Result<Value, String> {
+    send(app_handle, method, params, BACKGROUND_WINDOW)
+}
+
+fn send(app_handle: &AppHandle, method: &str, params: Value, window: &str) -> Result<Value, String> {
+    let request = RpcRequest {
+        id: RequestId::String(format!("shell-{}", uuid::Uuid::new_v4())),
+        method: method.to_string(),
+        params,
+    };
+    policy::check(&request, MAIN_WINDOW)?;
+    if let Some(safety) = app_handle.try_state::<SafetyGuard>() {
+        safety.check(&request, None)?;
+    }
+    if let Some(redactor) = app_handle.try_state::<Redactor>() {
+        redactor.register_from_json(&request.params);
+    }
+
+    let process = app_handle
+        .try_state::<BridgeProcess>()
+        .ok_or("bridge not available")?;
+    let pending = app_handle
+        .try_state::<PendingRequests>()
+        .ok_or("bridge not available")?;
+    let data = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+    let id = request.id.clone();
+
+    if window != BACKGROUND_WINDOW {
+        super::inspector::record_request(app_handle, &request, window, &data);
+        super::recording::record_out(app_handle, &data);
+        super::metrics::observe_request(app_handle, &request, &data);
+    }
+    let (reply, response) = mpsc::channel();
+    let sent_at = Instant::now();
+    audit::observe(app_handle, &request, window, sent_at, Outcome::Sent);
+    let audited = request.clone();
+    process
+        .send(&pending, request, id.clone(), &data, window, Some(reply))
+        .inspect_err(|e| {
+            let error = protocol::error_object("BRIDGE_UNAVAILABLE", e.as_str());
+            audit::observe(app_handle, &audited, window, sent_at, Outcome::Aborted("unsent", error));
+        })?;
+
+    let timeout = app_handle
+        .try_state::<RequestTimeouts>()
+        .map_or(SHELL_REQUEST_TIMEOUT, |t| t.timeout_for(method) + REPLY_GRACE);
+    let frame = match response.recv_timeout(timeout) {
+        Ok(frame) => frame,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            let message = format!("bridge did not answer {} in time", method);
+            if let Some(pending) = pending.complete(&id) {
+                let error = protocol::error_object("TIMEOUT", message.as_str());
+                audit::observe(app_handle, &pending.request, window, sent_at, Outcome::Aborted("timeout", error));
+            }
+            return Err(message);
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            return Err(format!("bridge stopped before answering {}", method));
+        }
+    };
+    if let Some(error) = frame.get("error") {
+        return Err(error
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string()));
+    }
+    Ok(frame.get("result").cloned().unwrap_or(Value::Null))
+}
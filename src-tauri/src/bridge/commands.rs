@@ -1,82 +1,362 @@
-use std::io::Write;
-use tauri::State;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 
-use super::process::{spawn_bridge, BridgeProcess};
+use super::history::BridgeHistory;
+use super::inspector;
+use super::metrics::{self, BridgeMetrics, MetricsSnapshot};
+use super::pending::{self, PendingRequests};
+use super::policy::{self, MethodPolicy};
+use super::process::BridgeProcess;
+use super::protocol::{self, parse_request, RequestId, RpcRequest};
+use super::recording::{self, BridgeRecorder, Recording, RecordingStatus};
+use super::redact::Redactor;
+use super::router::{self, BridgeRouter, STDOUT_EVENT};
+use super::safety::{ConnectionMode, SafetyGuard, Verdict};
+use super::timeouts::{self, AbortReason, LateResponse, RequestTimeouts, TimeoutSettings};
+use crate::audit::{self, Outcome};
 
-/// Write data to the bridge process stdin
+/// Write data to the bridge process stdin.
+/// The frame must be a well-formed request, with an id, for a method the policy allows from the calling window.
+/// Mutating requests against a production connection need a token from `safety_confirm`.
+/// The response and any session notifications are delivered to the calling window only, as JSON-RPC 2.0.
+/// A JSON array is a batch: its members are sent one by one and answered with a single array.
+/// `timeout_ms` overrides the method's timeout; returns the timeout in milliseconds that applies.
 #[tauri::command]
-pub fn bridge_write(data: String, state: State<'_, BridgeProcess>) -> Result<(), String> {
-    let mut guard = state.0.lock().unwrap();
-    
-    let child = guard.as_mut().ok_or("bridge not available")?;
+pub fn bridge_write(
+    data: String,
+    confirmation: Option<String>,
+    timeout_ms: Option<u64>,
+    window: WebviewWindow,
+    router: State<'_, BridgeRouter>,
+) -> Result<u64, String> {
+    if let Some(items) = protocol::parse_batch(&data) {
+        return write_batch(&window, items?, timeout_ms);
+    }
+
+    let mut request = parse_request(&data)?;
+    check_request(&window, &request, confirmation.as_deref())?;
+    let (data, client_id) = router.assign(&mut request, &data, window.label())?;
+    let timeout = send_request(&window, request, client_id, &data, timeout_ms)?;
+    Ok(timeout.as_millis() as u64)
+}
+
+/// Policy and safety checks for a request from `window`
+fn check_request(window: &WebviewWindow, request: &RpcRequest, confirmation: Option<&str>) -> Result<(), String> {
+    policy::check(request, window.label())?;
+    window.state::<SafetyGuard>().check(request, confirmation)?;
 
-    // Check if the process is still alive
-    match child.try_wait() {
-        Ok(Some(status)) => {
-            return Err(format!("bridge process exited with status: {:?}", status));
+    // Remember any credentials in the request so they can be masked in bridge output
+    window.state::<Redactor>().register_from_json(&request.params);
+    Ok(())
+}
+
+/// Record and write a request that already has its wire id; returns the timeout that applies to it
+fn send_request(
+    window: &WebviewWindow,
+    request: RpcRequest,
+    client_id: RequestId,
+    data: &str,
+    timeout_ms: Option<u64>,
+) -> Result<Duration, String> {
+    let app_handle = window.app_handle();
+    inspector::record_request(app_handle, &request, window.label(), data);
+    recording::record_out(app_handle, data);
+    metrics::observe_request(app_handle, &request, data);
+
+    let timeouts = window.state::<RequestTimeouts>();
+    let timeout = timeout_ms.filter(|ms| *ms > 0).map(Duration::from_millis);
+    let pending = window.state::<PendingRequests>();
+    let sent_at = Instant::now();
+    audit::observe(app_handle, &request, window.label(), sent_at, Outcome::Sent);
+    let audited = request.clone();
+    window
+        .state::<BridgeProcess>()
+        .send(&pending, request, client_id, data, window.label(), None)
+        .inspect_err(|e| {
+            let error = protocol::error_object("BRIDGE_UNAVAILABLE", e.as_str());
+            audit::observe(app_handle, &audited, window.label(), sent_at, Outcome::Aborted("unsent", error));
+        })?;
+    // Only once the request is tracked, or the watchdog would prune the override as finished
+    match timeout {
+        Some(timeout) => {
+            timeouts.set_timeout(&pending, &audited.id, timeout);
+            Ok(timeout)
         }
-        Ok(None) => {} // Process is still running
-        Err(e) => {
-            return Err(format!("failed to check bridge status: {}", e));
+        None => Ok(timeouts.timeout_for(&audited.method)),
+    }
+}
+
+/// Send the members of a batch and answer them with one array once the bridge has answered them all.
+/// Members that are malformed or refused get their error in the array; production changes can't be confirmed in a batch.
+fn write_batch(window: &WebviewWindow, items: Vec<Value>, timeout_ms: Option<u64>) -> Result<u64, String> {
+    let router = window.state::<BridgeRouter>();
+    let mut responses = Vec::new();
+    let mut accepted = Vec::new();
+    for item in items {
+        let id = item.get("id").cloned().unwrap_or(Value::Null);
+        let data = item.to_string();
+        let mut request = match parse_request(&data) {
+            Ok(request) => request,
+            Err(e) => {
+                responses.push(protocol::error_response(id, protocol::error_object("INVALID_REQUEST", e)));
+                continue;
+            }
+        };
+        if let Err(e) = check_request(window, &request, None) {
+            responses.push(protocol::error_response(id, protocol::error_object("DENIED", e)));
+            continue;
+        }
+        match router.assign(&mut request, &data, window.label()) {
+            Ok((data, client_id)) => accepted.push((request, client_id, data)),
+            Err(e) => responses.push(protocol::error_response(id, protocol::error_object("INVALID_REQUEST", e))),
+        }
+    }
+
+    let members: Vec<RequestId> = accepted.iter().map(|(request, _, _)| request.id.clone()).collect();
+    if let Some(responses) = router.open_batch(window.label(), &members, responses) {
+        let _ = window
+            .app_handle()
+            .emit_to(window.label(), STDOUT_EVENT, Value::Array(responses).to_string());
+        return Ok(0);
+    }
+
+    let mut longest = Duration::ZERO;
+    for (request, client_id, data) in accepted {
+        let id = request.id.clone();
+        match send_request(window, request, client_id.clone(), &data, timeout_ms) {
+            Ok(timeout) => longest = longest.max(timeout),
+            Err(e) => {
+                let frame = json!({ "id": client_id, "error": protocol::error_object("BRIDGE_UNAVAILABLE", e) });
+                router::deliver(window.app_handle(), &id, window.label(), frame);
+            }
         }
     }
+    Ok(longest.as_millis() as u64)
+}
 
-    let stdin = child.stdin.as_mut().ok_or("bridge stdin missing")?;
-    
-    stdin.write_all(data.as_bytes()).map_err(|e| e.to_string())?;
-    stdin.write_all(b"\n").map_err(|e| e.to_string())?;
-    stdin.flush().map_err(|e| e.to_string())?;
-    
+/// Give up on a request the calling window sent, by the id the window gave it.
+/// Query sessions are cancelled in the bridge with `query.cancel`; other methods keep running there and their result is dropped.
+/// The window gets a `CANCELLED` error response either way.
+#[tauri::command]
+pub fn bridge_cancel(
+    request_id: RequestId,
+    window: WebviewWindow,
+    pending: State<'_, PendingRequests>,
+) -> Result<(), String> {
+    let cancelled = pending.take_where(|p| p.window == window.label() && p.client_id == request_id);
+    if cancelled.is_empty() {
+        return Err(format!("no request {} in flight", request_id));
+    }
+    for request in cancelled {
+        timeouts::abort(window.app_handle(), request, AbortReason::Cancelled);
+    }
     Ok(())
 }
 
+/// Current request timeouts
+#[tauri::command]
+pub fn bridge_timeouts_get(timeouts: State<'_, RequestTimeouts>) -> TimeoutSettings {
+    timeouts.settings()
+}
+
+/// Change and persist the request timeouts
+#[tauri::command]
+pub fn bridge_timeouts_set(settings: TimeoutSettings, timeouts: State<'_, RequestTimeouts>) -> Result<(), String> {
+    timeouts.set_settings(settings)
+}
+
+/// Responses that arrived after their request had timed out or been cancelled, oldest first
+#[tauri::command]
+pub fn bridge_late_responses(timeouts: State<'_, RequestTimeouts>) -> Vec<LateResponse> {
+    timeouts.late_responses()
+}
+
 /// Kill the bridge process and wait for it to fully exit.
 /// Called before applying an update so the bridge releases file handles on
 /// bundled resources (e.g. better_sqlite3.node) before the installer overwrites them.
 #[tauri::command]
-pub fn bridge_kill(state: State<'_, BridgeProcess>) -> Result<(), String> {
-    let mut guard = state.0.lock().unwrap();
-    if let Some(mut child) = guard.take() {
-        child.kill().map_err(|e| format!("failed to kill bridge: {}", e))?;
-        // wait() is essential — it ensures the OS fully closes all file handles
-        // before we return, giving the installer a clean shot at the files.
-        child.wait().map_err(|e| format!("failed to wait for bridge exit: {}", e))?;
-    }
-    Ok(())
+pub fn bridge_kill(
+    app_handle: AppHandle,
+    state: State<'_, BridgeProcess>,
+    pending: State<'_, PendingRequests>,
+    router: State<'_, BridgeRouter>,
+    history: State<'_, BridgeHistory>,
+) -> Result<(), String> {
+    pending::abandon(&app_handle, pending.clear(), "bridge killed");
+    router.clear();
+    history.record_status("killed");
+    state.shutdown()
 }
 
 /// Restart the bridge process
 #[tauri::command]
-pub fn bridge_restart(
-    app_handle: tauri::AppHandle,
-    state: State<'_, BridgeProcess>,
-) -> Result<String, String> {
-    let mut guard = state.0.lock().unwrap();
+pub fn bridge_restart(app_handle: AppHandle) -> Result<String, String> {
+    super::restart(&app_handle)?;
+    Ok("Bridge restarted successfully".into())
+}
+
+/// Start writing every request, response and notification with its timing to `path`
+#[tauri::command]
+pub fn bridge_record_start(path: String, app_handle: AppHandle, recorder: State<'_, BridgeRecorder>) -> Result<(), String> {
+    recorder.start(PathBuf::from(path), &app_handle.package_info().version.to_string())
+}
+
+/// Stop recording; returns the file and the number of frames written
+#[tauri::command]
+pub fn bridge_record_stop(recorder: State<'_, BridgeRecorder>) -> RecordingStatus {
+    recorder.stop()
+}
 
-    // Kill existing process if any
-    if let Some(mut child) = guard.take() {
-        let _ = child.kill();
-        let _ = child.wait();
+/// Whether traffic is being recorded or replayed, and from or to which file
+#[tauri::command]
+pub fn bridge_recording_status(recorder: State<'_, BridgeRecorder>) -> RecordingStatus {
+    recorder.status()
+}
+
+/// Replace the bridge with one that answers from the recording at `path`
+#[tauri::command]
+pub fn bridge_replay_start(path: String, app_handle: AppHandle, recorder: State<'_, BridgeRecorder>) -> Result<(), String> {
+    let path = PathBuf::from(path);
+    // Fail here rather than leave the app without a bridge
+    Recording::load(&path)?;
+    recorder.set_replay_source(Some(path));
+    super::restart(&app_handle)
+}
+
+/// Go back to the real bridge
+#[tauri::command]
+pub fn bridge_replay_stop(app_handle: AppHandle, recorder: State<'_, BridgeRecorder>) -> Result<(), String> {
+    if recorder.replay_source().is_none() {
+        return Ok(());
     }
+    recorder.set_replay_source(None);
+    super::restart(&app_handle)
+}
 
-    // Spawn new bridge process
-    let child = spawn_bridge(app_handle)?;
-    *guard = Some(child);
-    
-    Ok("Bridge restarted successfully".into())
+/// Per-method counts, errors, latency percentiles and bytes, plus `query.result` throughput per session
+#[tauri::command]
+pub fn bridge_metrics(reset: Option<bool>, metrics: State<'_, BridgeMetrics>) -> MetricsSnapshot {
+    let snapshot = metrics.snapshot();
+    if reset.unwrap_or(false) {
+        metrics.reset();
+    }
+    snapshot
+}
+
+/// Emit a `bridge-metrics` event with the current metrics every `interval_secs`; `None` or 0 turns it off
+#[tauri::command]
+pub fn bridge_metrics_report(interval_secs: Option<u64>, app_handle: AppHandle, metrics: State<'_, BridgeMetrics>) {
+    metrics.report_every(&app_handle, interval_secs.map(Duration::from_secs));
 }
 
 /// Check if bridge process is alive
 #[tauri::command]
-pub fn bridge_status(state: State<'_, BridgeProcess>) -> Result<String, String> {
+pub fn bridge_status(
+    state: State<'_, BridgeProcess>,
+    history: State<'_, BridgeHistory>,
+) -> Result<String, String> {
     let mut guard = state.0.lock().unwrap();
-    
-    match guard.as_mut() {
+
+    let status = match guard.as_mut() {
         Some(child) => match child.try_wait() {
-            Ok(Some(status)) => Ok(format!("exited:{:?}", status)),
-            Ok(None) => Ok("running".into()),
-            Err(e) => Err(format!("error checking status: {}", e)),
+            Ok(Some(status)) => format!("exited:{}", status),
+            Ok(None) => "running".into(),
+            Err(e) => return Err(format!("error checking status: {}", e)),
         },
-        None => Ok("not_started".into()),
+        None => "not_started".into(),
+    };
+    history.record_status(&status);
+    Ok(status)
+}
+
+/// Toggle unredacted bridge output (development builds only)
+#[tauri::command]
+pub fn bridge_set_raw_output(enabled: bool, redactor: State<'_, Redactor>) -> Result<(), String> {
+    redactor.set_raw_output(enabled)
+}
+
+/// List bridge methods with their risk level and window restrictions
+#[tauri::command]
+pub fn bridge_policy() -> Vec<MethodPolicy> {
+    policy::describe()
+}
+
+/// List the connections that have a read-only or production flag
+#[tauri::command]
+pub fn safety_list_modes(safety: State<'_, SafetyGuard>) -> HashMap<String, ConnectionMode> {
+    safety.modes()
+}
+
+/// Set the safety mode of a connection.
+/// Lifting a restriction is only possible from the main window, after the user confirms it in a native dialog.
+#[tauri::command]
+pub async fn safety_set_mode(
+    connection_id: String,
+    mode: ConnectionMode,
+    window: WebviewWindow,
+    safety: State<'_, SafetyGuard>,
+) -> Result<(), String> {
+    let current = safety.mode(&connection_id);
+    if current.is_relaxed_by(mode) {
+        if window.label() != policy::MAIN_WINDOW {
+            return Err(format!(
+                "only the main window can lift the safety mode of connection '{}'",
+                connection_id
+            ));
+        }
+        let dialog = window
+            .dialog()
+            .message(format!(
+                "Change connection '{}' from {:?} to {:?}?\n\nChanges it currently blocks or asks to confirm may go through.",
+                connection_id, current, mode
+            ))
+            .title("Lift connection safety mode")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom("Change".into(), "Cancel".into()))
+            .parent(&window);
+        let confirmed = tauri::async_runtime::spawn_blocking(move || dialog.blocking_show())
+            .await
+            .map_err(|e| e.to_string())?;
+        if !confirmed {
+            return Err("safety mode change was not confirmed".into());
+        }
+    }
+    safety.set_mode(&connection_id, mode)
+}
+
+/// Ask the user to confirm a mutating request with a native dialog.
+/// Returns a single-use token to pass as `confirmation` to `bridge_write`.
+#[tauri::command]
+pub async fn safety_confirm(
+    data: String,
+    window: WebviewWindow,
+    safety: State<'_, SafetyGuard>,
+) -> Result<String, String> {
+    let request = parse_request(&data)?;
+    let description = match safety.evaluate(&request) {
+        Verdict::Confirm(description) => description,
+        Verdict::Deny(reason) => return Err(reason),
+        Verdict::Allow => return Err("request does not need confirmation".into()),
+    };
+
+    let dialog = window
+        .dialog()
+        .message(format!("Run {}?\n\nThis may change or delete data.", description))
+        .title("Confirm production change")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Run".into(), "Cancel".into()))
+        .parent(&window);
+    let confirmed = tauri::async_runtime::spawn_blocking(move || dialog.blocking_show())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if confirmed {
+        Ok(safety.issue_confirmation(&request))
+    } else {
+        Err("change was not confirmed".into())
     }
 }
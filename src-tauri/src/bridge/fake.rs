@@ -0,0 +1,240 @@
+//! An in-process bridge with scripted behaviour, so the shell's supervision, routing, timeouts
+//! and shutdown can be tested without Node or a bundled bridge.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{PipeWriter, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::history::SpawnReport;
+use super::launcher::{BridgeLauncher, LaunchedBridge};
+use super::process::{read_lines, BridgeChild, InProcessBridge};
+
+/// Granularity of interruptible sleeps, so `kill` never waits for a whole step
+const TICK: Duration = Duration::from_millis(5);
+
+/// One thing the fake bridge does in answer to a frame
+pub enum Step {
+    /// Answer with `{"id", "result"}`
+    Respond(Value),
+    /// Answer with a bridge-style error
+    Fail { code: &'static str, message: &'static str },
+    Notify { method: &'static str, params: Value },
+    /// Write a line that is not JSON
+    Garbage(&'static str),
+    Sleep(Duration),
+    /// Answer with `result`, writing the frame in pieces with a pause between each
+    RespondSlowly(Value, Duration),
+    /// Close stdout and stop, as if the process died
+    Crash,
+}
+
+type Handler = Box<dyn Fn(&Value) -> Vec<Step> + Send + Sync>;
+
+/// What the fake bridge does for each method. Methods without a handler get `UNKNOWN_METHOD`.
+#[derive(Default)]
+pub struct Script(HashMap<String, Handler>);
+
+impl Script {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `handler` with the params of every `method` frame
+    pub fn on(mut self, method: &str, handler: impl Fn(&Value) -> Vec<Step> + Send + Sync + 'static) -> Self {
+        self.0.insert(method.to_string(), Box::new(handler));
+        self
+    }
+
+    fn steps(&self, frame: &Value) -> Vec<Step> {
+        let method = frame.get("method").and_then(Value::as_str).unwrap_or_default();
+        let params = frame.get("params").cloned().unwrap_or(Value::Null);
+        match self.0.get(method) {
+            Some(handler) => handler(&params),
+            None => vec![Step::Fail {
+                code: "UNKNOWN_METHOD",
+                message: "Unknown method",
+            }],
+        }
+    }
+}
+
+/// A running fake bridge; frames written to it are answered by its script on a worker thread
+pub struct FakeBridge {
+    requests: Option<Sender<String>>,
+    running: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl FakeBridge {
+    fn start(script: Arc<Script>, mut stdout: PipeWriter, received: Arc<Mutex<Vec<Value>>>) -> Self {
+        let (requests, rx) = mpsc::channel::<String>();
+        let running = Arc::new(AtomicBool::new(true));
+        let alive = running.clone();
+        let worker = std::thread::spawn(move || {
+            for line in rx {
+                let Ok(frame) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                received.lock().unwrap().push(frame.clone());
+                let id = frame.get("id").cloned();
+                for step in script.steps(&frame) {
+                    if !alive.load(Ordering::Relaxed) || !run_step(step, id.as_ref(), &mut stdout, &alive) {
+                        alive.store(false, Ordering::Relaxed);
+                        return;
+                    }
+                }
+            }
+            alive.store(false, Ordering::Relaxed);
+        });
+        Self {
+            requests: Some(requests),
+            running,
+            worker: Some(worker),
+        }
+    }
+}
+
+/// Carry out one step; false once the bridge has stopped
+fn run_step(step: Step, id: Option<&Value>, stdout: &mut PipeWriter, running: &AtomicBool) -> bool {
+    let written = match step {
+        Step::Respond(result) => write_frame(stdout, &json!({ "id": id, "result": result })),
+        Step::Fail { code, message } => write_frame(
+            stdout,
+            &json!({ "id": id, "error": { "code": code, "message": message } }),
+        ),
+        Step::Notify { method, params } => write_frame(stdout, &json!({ "method": method, "params": params })),
+        Step::Garbage(line) => writeln!(stdout, "{}", line),
+        Step::Sleep(duration) => return sleep(duration, running),
+        Step::RespondSlowly(result, pause) => {
+            let line = format!("{}\n", json!({ "id": id, "result": result }));
+            for piece in line.as_bytes().chunks(line.len() / 3 + 1) {
+                if stdout.write_all(piece).and_then(|_| stdout.flush()).is_err() || !sleep(pause, running) {
+                    return false;
+                }
+            }
+            Ok(())
+        }
+        Step::Crash => return false,
+    };
+    written.is_ok()
+}
+
+fn write_frame(stdout: &mut PipeWriter, frame: &Value) -> std::io::Result<()> {
+    writeln!(stdout, "{}", frame)
+}
+
+/// Sleep for `duration` unless the bridge is stopped first; false if it was
+fn sleep(duration: Duration, running: &AtomicBool) -> bool {
+    let until = Instant::now() + duration;
+    while Instant::now() < until {
+        if !running.load(Ordering::Relaxed) {
+            return false;
+        }
+        std::thread::sleep(TICK.min(until - Instant::now()));
+    }
+    running.load(Ordering::Relaxed)
+}
+
+impl InProcessBridge for FakeBridge {
+    fn name(&self) -> &str {
+        "fake bridge"
+    }
+
+    fn write_line(&mut self, data: &str) -> std::io::Result<()> {
+        if !self.is_running() {
+            return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "fake bridge stopped"));
+        }
+        self.requests
+            .as_ref()
+            .and_then(|r| r.send(data.to_string()).ok())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "fake bridge stopped"))
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    fn kill(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        self.requests = None;
+    }
+
+    fn wait(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Launches fake bridges running one script, optionally failing the first few launches
+pub struct FakeLauncher {
+    script: Arc<Script>,
+    failures: usize,
+    launches: AtomicUsize,
+    received: Arc<Mutex<Vec<Value>>>,
+}
+
+impl FakeLauncher {
+    pub fn new(script: Script) -> Self {
+        Self {
+            script: Arc::new(script),
+            failures: 0,
+            launches: AtomicUsize::new(0),
+            received: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Make the first `failures` launches fail, as if the bridge could not be found
+    pub fn failing(mut self, failures: usize) -> Self {
+        self.failures = failures;
+        self
+    }
+
+    pub fn launches(&self) -> usize {
+        self.launches.load(Ordering::Relaxed)
+    }
+
+    /// Every frame written to any of the launched bridges, in order
+    pub fn received(&self) -> Vec<Value> {
+        self.received.lock().unwrap().clone()
+    }
+
+    /// Launch a bridge and collect its stdout lines, as the shell's reader thread would
+    pub fn start(&self) -> Result<(BridgeChild, Receiver<String>), String> {
+        let launched = self.launch(&mut SpawnReport::new())?;
+        Ok((launched.child, stdout_lines(launched.stdout)))
+    }
+}
+
+impl BridgeLauncher for FakeLauncher {
+    fn launch(&self, report: &mut SpawnReport) -> Result<LaunchedBridge, String> {
+        let attempt = self.launches.fetch_add(1, Ordering::Relaxed);
+        if attempt < self.failures {
+            let error = "fake bridge refused to start".to_string();
+            report.record("fake", &[], Some(error.clone()));
+            return Err(error);
+        }
+        let (reader, writer) = std::io::pipe().map_err(|e| e.to_string())?;
+        let bridge = FakeBridge::start(self.script.clone(), writer, self.received.clone());
+        report.record("fake", &[], None);
+        Ok(LaunchedBridge {
+            child: BridgeChild::InProcess(Box::new(bridge)),
+            stdout: Some(Box::new(reader)),
+            stderr: None,
+        })
+    }
+}
+
+/// Read `stdout` on a thread; the receiver disconnects once it closes
+fn stdout_lines(stdout: Option<Box<dyn std::io::Read + Send>>) -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = stdout {
+        std::thread::spawn(move || read_lines(stdout, |line| drop(tx.send(line))));
+    }
+    rx
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::node::NodeSelection;
+
+/// Number of status transitions kept for diagnostics
+const STATUS_HISTORY_LEN: usize = 200;
+
+/// One attempt to start the bridge with a particular program
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpawnAttempt {
+    pub program: String,
+    pub args: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Every strategy tried while spawning the bridge, and which one succeeded
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpawnReport {
+    pub started_at: String,
+    pub attempts: Vec<SpawnAttempt>,
+    pub pid: Option<u32>,
+    pub error: Option<String>,
+    /// Node executables considered, if a strategy needed one
+    pub node: Option<NodeSelection>,
+    /// Bundled files skipped or used unverified
+    pub warnings: Vec<String>,
+}
+
+impl SpawnReport {
+    pub fn new() -> Self {
+        Self {
+            started_at: chrono::Utc::now().to_rfc3339(),
+            ..Default::default()
+        }
+    }
+
+    pub fn record(&mut self, program: &str, args: &[&str], error: Option<String>) {
+        self.attempts.push(SpawnAttempt {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            error,
+        });
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusEntry {
+    pub timestamp: String,
+    pub status: String,
+}
+
+/// Spawn reports and status transitions of the bridge process
+#[derive(Default)]
+pub struct BridgeHistory {
+    last_spawn: Mutex<Option<SpawnReport>>,
+    statuses: Mutex<VecDeque<StatusEntry>>,
+}
+
+impl BridgeHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_spawn_report(&self, report: SpawnReport) {
+        *self.last_spawn.lock().unwrap() = Some(report);
+    }
+
+    pub fn spawn_report(&self) -> Option<SpawnReport> {
+        self.last_spawn.lock().unwrap().clone()
+    }
+
+    /// Record a status, skipping repeats of the most recent one
+    pub fn record_status(&self, status: &str) {
+        let mut statuses = self.statuses.lock().unwrap();
+        if statuses.back().is_some_and(|s| s.status == status) {
+            return;
+        }
+        if statuses.len() == STATUS_HISTORY_LEN {
+            statuses.pop_front();
+        }
+        statuses.push_back(StatusEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            status: status.to_string(),
+        });
+    }
+
+    /// Most recently recorded status
+    pub fn last_status(&self) -> Option<String> {
+        self.statuses.lock().unwrap().back().map(|s| s.status.clone())
+    }
+
+    pub fn statuses(&self) -> Vec<StatusEntry> {
+        self.statuses.lock().unwrap().iter().cloned().collect()
+    }
+}
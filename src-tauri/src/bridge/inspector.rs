@@ -0,0 +1,499 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use super::pending::PendingRequest;
+use super::protocol::{RequestId, RpcRequest};
+use super::redact::Redactor;
+
+/// Number of frames kept by the inspector
+const TRAFFIC_BUFFER_LEN: usize = 1000;
+
+/// Frames larger than this are recorded without their payload even when capture is on
+const MAX_PAYLOAD_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Shell to bridge (stdin)
+    Out,
+    /// Bridge to shell (stdout)
+    In,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameKind {
+    Request,
+    Response,
+    Notification,
+    /// A stdout line that is not JSON
+    Raw,
+}
+
+/// One frame seen on the bridge pipes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficEntry {
+    pub seq: u64,
+    pub timestamp: String,
+    pub direction: Direction,
+    pub kind: FrameKind,
+    pub method: Option<String>,
+    /// Id on the wire (the shell-wide id, not the one the window used)
+    pub id: Option<RequestId>,
+    pub window: Option<String>,
+    pub session_id: Option<String>,
+    pub size: usize,
+    /// Time from request to response, on responses
+    pub latency_ms: Option<f64>,
+    /// `error.code` of failed responses
+    pub error_code: Option<Value>,
+    /// Redacted frame, only while payload capture is on
+    pub payload: Option<Value>,
+}
+
+/// Criteria for `devtools_traffic_query`; every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficFilter {
+    /// Exact method name, or a prefix ending in `.` such as `query.`
+    pub method: Option<String>,
+    pub session_id: Option<String>,
+    pub window: Option<String>,
+    pub direction: Option<Direction>,
+    #[serde(default)]
+    pub errors_only: bool,
+    /// Keep only the most recent entries
+    pub limit: Option<usize>,
+}
+
+impl TrafficFilter {
+    fn matches(&self, entry: &TrafficEntry) -> bool {
+        if let Some(method) = &self.method {
+            let Some(m) = &entry.method else {
+                return false;
+            };
+            let hit = if method.ends_with('.') {
+                m.starts_with(method.as_str())
+            } else {
+                m == method
+            };
+            if !hit {
+                return false;
+            }
+        }
+        if self.session_id.is_some() && entry.session_id != self.session_id {
+            return false;
+        }
+        if self.window.is_some() && entry.window != self.window {
+            return false;
+        }
+        if self.direction.is_some_and(|d| d != entry.direction) {
+            return false;
+        }
+        !self.errors_only || entry.error_code.is_some()
+    }
+}
+
+/// Ring buffer of recent bridge frames for the devtools traffic view
+pub struct TrafficInspector {
+    entries: Mutex<VecDeque<TrafficEntry>>,
+    next_seq: AtomicU64,
+    capture_payloads: AtomicBool,
+}
+
+impl Default for TrafficInspector {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(TRAFFIC_BUFFER_LEN)),
+            next_seq: AtomicU64::new(1),
+            capture_payloads: AtomicBool::new(false),
+        }
+    }
+}
+
+fn session_of(value: &Value) -> Option<String> {
+    value
+        .get("sessionId")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+impl TrafficInspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_capture_payloads(&self, enabled: bool) {
+        self.capture_payloads.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn capture_payloads(&self) -> bool {
+        self.capture_payloads.load(Ordering::Relaxed)
+    }
+
+    /// The frame with secrets masked, if payloads are being captured and it is small enough
+    fn payload(&self, frame: &Value, size: usize, redactor: Option<&Redactor>) -> Option<Value> {
+        if !self.capture_payloads() {
+            return None;
+        }
+        if size > MAX_PAYLOAD_BYTES {
+            return Some(json!({ "truncated": true, "size": size }));
+        }
+        let mut payload = frame.clone();
+        if let Some(redactor) = redactor {
+            redactor.redact_json(&mut payload);
+        }
+        Some(payload)
+    }
+
+    fn push(&self, mut entry: TrafficEntry) {
+        entry.seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == TRAFFIC_BUFFER_LEN {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    fn entry(direction: Direction, kind: FrameKind, size: usize) -> TrafficEntry {
+        TrafficEntry {
+            seq: 0,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            direction,
+            kind,
+            method: None,
+            id: None,
+            window: None,
+            session_id: None,
+            size,
+            latency_ms: None,
+            error_code: None,
+            payload: None,
+        }
+    }
+
+    /// Record a request written to the bridge
+    pub fn record_request(&self, request: &RpcRequest, window: &str, data: &str, redactor: Option<&Redactor>) {
+        let mut entry = Self::entry(Direction::Out, FrameKind::Request, data.len());
+        entry.method = Some(request.method.clone());
+        entry.id = Some(request.id.clone());
+        entry.window = Some(window.to_string());
+        entry.session_id = session_of(&request.params);
+        if self.capture_payloads() {
+            let frame = serde_json::from_str(data).unwrap_or(Value::Null);
+            entry.payload = self.payload(&frame, data.len(), redactor);
+        }
+        self.push(entry);
+    }
+
+    /// Record a stdout line; `pending` is the request a response answers, `window` the notification's owner
+    pub fn record_stdout(
+        &self,
+        line: &str,
+        frame: Option<&Value>,
+        pending: Option<&PendingRequest>,
+        window: Option<&str>,
+        redactor: Option<&Redactor>,
+    ) {
+        let Some(frame) = frame else {
+            let mut entry = Self::entry(Direction::In, FrameKind::Raw, line.len());
+            if self.capture_payloads() && line.len() <= MAX_PAYLOAD_BYTES {
+                entry.payload = Some(Value::String(redactor.map_or_else(|| line.to_string(), |r| r.redact(line))));
+            }
+            self.push(entry);
+            return;
+        };
+
+        let kind = if frame.get("method").is_some() {
+            FrameKind::Notification
+        } else {
+            FrameKind::Response
+        };
+        let mut entry = Self::entry(Direction::In, kind, line.len());
+        entry.payload = self.payload(frame, line.len(), redactor);
+        entry.window = window.map(str::to_string);
+        match kind {
+            FrameKind::Notification => {
+                entry.method = frame.get("method").and_then(Value::as_str).map(str::to_string);
+                entry.session_id = frame.get("params").and_then(session_of);
+            }
+            _ => {
+                entry.id = frame
+                    .get("id")
+                    .and_then(|id| serde_json::from_value(id.clone()).ok());
+                entry.error_code = frame
+                    .get("error")
+                    .map(|e| e.get("code").cloned().unwrap_or(Value::Null));
+                if let Some(pending) = pending {
+                    entry.method = Some(pending.request.method.clone());
+                    entry.window = Some(pending.window.clone());
+                    entry.session_id = session_of(&pending.request.params);
+                    entry.latency_ms = Some(pending.started_at.elapsed().as_secs_f64() * 1000.0);
+                }
+            }
+        }
+        self.push(entry);
+    }
+
+    pub fn query(&self, filter: &TrafficFilter) -> Vec<TrafficEntry> {
+        let entries = self.entries.lock().unwrap();
+        let matching: Vec<TrafficEntry> = entries.iter().filter(|e| filter.matches(e)).cloned().collect();
+        match filter.limit {
+            Some(limit) if matching.len() > limit => matching[matching.len() - limit..].to_vec(),
+            _ => matching,
+        }
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+fn har_content(payload: &Option<Value>, size: usize) -> Value {
+    json!({
+        "size": size,
+        "mimeType": "application/json",
+        "text": payload.as_ref().map(Value::to_string),
+    })
+}
+
+/// Entries as a HAR 1.2 style log: each request paired with its response, notifications as entries of their own
+pub fn to_har(app_handle: &AppHandle, entries: &[TrafficEntry]) -> Value {
+    let responses: HashMap<&RequestId, &TrafficEntry> = entries
+        .iter()
+        .filter(|e| e.kind == FrameKind::Response)
+        .filter_map(|e| e.id.as_ref().map(|id| (id, e)))
+        .collect();
+
+    let har_entries: Vec<Value> = entries
+        .iter()
+        .filter_map(|entry| match entry.kind {
+            FrameKind::Request => {
+                let response = entry.id.as_ref().and_then(|id| responses.get(id));
+                let status = match response {
+                    Some(r) if r.error_code.is_some() => 500,
+                    Some(_) => 200,
+                    None => 0,
+                };
+                Some(json!({
+                    "startedDateTime": entry.timestamp,
+                    "time": response.and_then(|r| r.latency_ms).unwrap_or(-1.0),
+                    "request": {
+                        "method": "RPC",
+                        "url": format!("bridge://{}", entry.method.as_deref().unwrap_or_default()),
+                        "httpVersion": "JSON-RPC",
+                        "headers": [],
+                        "queryString": [],
+                        "bodySize": entry.size,
+                        "postData": { "mimeType": "application/json", "text": entry.payload.as_ref().map(Value::to_string) },
+                    },
+                    "response": {
+                        "status": status,
+                        "statusText": if status == 0 { "no response" } else { "" },
+                        "httpVersion": "JSON-RPC",
+                        "headers": [],
+                        "bodySize": response.map_or(-1, |r| r.size as i64),
+                        "content": response.map_or(Value::Null, |r| har_content(&r.payload, r.size)),
+                    },
+                    "timings": { "send": 0, "wait": response.and_then(|r| r.latency_ms).unwrap_or(-1.0), "receive": 0 },
+                    "_id": entry.id,
+                    "_window": entry.window,
+                    "_sessionId": entry.session_id,
+                    "_errorCode": response.and_then(|r| r.error_code.clone()),
+                }))
+            }
+            FrameKind::Notification | FrameKind::Raw => Some(json!({
+                "startedDateTime": entry.timestamp,
+                "time": 0,
+                "request": {
+                    "method": "NOTIFY",
+                    "url": format!("bridge://{}", entry.method.as_deref().unwrap_or("stdout")),
+                    "httpVersion": "JSON-RPC",
+                    "headers": [],
+                    "queryString": [],
+                    "bodySize": 0,
+                },
+                "response": {
+                    "status": 200,
+                    "statusText": "",
+                    "httpVersion": "JSON-RPC",
+                    "headers": [],
+                    "bodySize": entry.size,
+                    "content": har_content(&entry.payload, entry.size),
+                },
+                "timings": { "send": 0, "wait": 0, "receive": 0 },
+                "_window": entry.window,
+                "_sessionId": entry.session_id,
+            })),
+            // Paired with their request above
+            FrameKind::Response => None,
+        })
+        .collect();
+
+    let package = app_handle.package_info();
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": package.name, "version": package.version.to_string() },
+            "entries": har_entries,
+        }
+    })
+}
+
+/// Record a request written to the bridge, if the inspector is running
+pub fn record_request(app_handle: &AppHandle, request: &RpcRequest, window: &str, data: &str) {
+    if let Some(inspector) = app_handle.try_state::<TrafficInspector>() {
+        let redactor = app_handle.try_state::<Redactor>();
+        inspector.record_request(request, window, data, redactor.as_deref());
+    }
+}
+
+/// Record a (redacted) stdout line, if the inspector is running
+pub fn record_stdout(
+    app_handle: &AppHandle,
+    line: &str,
+    frame: Option<&Value>,
+    pending: Option<&PendingRequest>,
+    window: Option<&str>,
+) {
+    if let Some(inspector) = app_handle.try_state::<TrafficInspector>() {
+        let redactor = app_handle.try_state::<Redactor>();
+        inspector.record_stdout(line, frame, pending, window, redactor.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::protocol;
+    use std::time::Instant;
+
+    fn request(inspector: &TrafficInspector, id: u64, method: &str, params: Value, window: &str) -> PendingRequest {
+        let data = json!({ "id": id, "method": method, "params": params }).to_string();
+        let request = protocol::parse_request(&data).unwrap();
+        inspector.record_request(&request, window, &data, Some(&Redactor::new()));
+        PendingRequest {
+            client_id: request.id.clone(),
+            request,
+            window: window.to_string(),
+            started_at: Instant::now(),
+            reply: None,
+        }
+    }
+
+    fn respond(inspector: &TrafficInspector, frame: Value, pending: Option<&PendingRequest>) {
+        inspector.record_stdout(&frame.to_string(), Some(&frame), pending, None, None);
+    }
+
+    #[test]
+    fn responses_are_labelled_with_their_request() {
+        let inspector = TrafficInspector::new();
+        let run = request(&inspector, 1, "query.run", json!({ "sessionId": "s1" }), "main");
+        inspector.record_stdout(
+            "{}",
+            Some(&json!({ "method": "query.progress", "params": { "sessionId": "s1" } })),
+            None,
+            Some("main"),
+            None,
+        );
+        respond(&inspector, json!({ "id": 1, "error": { "code": "SQL_ERROR" } }), Some(&run));
+        inspector.record_stdout("not json", None, None, None, None);
+
+        let entries = inspector.query(&TrafficFilter::default());
+        let kinds: Vec<FrameKind> = entries.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![FrameKind::Request, FrameKind::Notification, FrameKind::Response, FrameKind::Raw]
+        );
+        assert!(entries.windows(2).all(|w| w[0].seq < w[1].seq));
+        let response = &entries[2];
+        assert_eq!(response.method.as_deref(), Some("query.run"));
+        assert_eq!(response.window.as_deref(), Some("main"));
+        assert_eq!(response.session_id.as_deref(), Some("s1"));
+        assert_eq!(response.error_code, Some(json!("SQL_ERROR")));
+        assert!(response.latency_ms.is_some());
+        assert!(entries.iter().all(|e| e.payload.is_none()));
+    }
+
+    #[test]
+    fn filters_narrow_the_traffic() {
+        let inspector = TrafficInspector::new();
+        let run = request(&inspector, 1, "query.run", json!({ "sessionId": "s1" }), "main");
+        request(&inspector, 2, "query.cancel", json!({ "sessionId": "s2" }), "tool");
+        let list = request(&inspector, 3, "db.list", json!({}), "main");
+        respond(&inspector, json!({ "id": 1, "error": { "code": "SQL_ERROR" } }), Some(&run));
+        respond(&inspector, json!({ "id": 3, "result": [] }), Some(&list));
+
+        let count = |filter: TrafficFilter| inspector.query(&filter).len();
+        let method = |m: &str| TrafficFilter {
+            method: Some(m.into()),
+            ..Default::default()
+        };
+        assert_eq!(count(method("query.")), 3);
+        assert_eq!(count(method("query")), 0);
+        assert_eq!(count(method("db.list")), 2);
+        assert_eq!(
+            count(TrafficFilter {
+                session_id: Some("s1".into()),
+                ..Default::default()
+            }),
+            2
+        );
+        assert_eq!(
+            count(TrafficFilter {
+                window: Some("tool".into()),
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            count(TrafficFilter {
+                direction: Some(Direction::In),
+                ..Default::default()
+            }),
+            2
+        );
+        assert_eq!(
+            count(TrafficFilter {
+                errors_only: true,
+                ..Default::default()
+            }),
+            1
+        );
+        let latest = inspector.query(&TrafficFilter {
+            limit: Some(2),
+            ..Default::default()
+        });
+        let ids: Vec<Option<RequestId>> = latest.iter().map(|e| e.id.clone()).collect();
+        assert_eq!(ids, vec![Some(RequestId::Number(1)), Some(RequestId::Number(3))]);
+
+        inspector.clear();
+        assert_eq!(count(TrafficFilter::default()), 0);
+    }
+
+    #[test]
+    fn captured_payloads_are_redacted_and_bounded() {
+        let inspector = TrafficInspector::new();
+        inspector.set_capture_payloads(true);
+        request(&inspector, 1, "db.add", json!({ "password": "hunter2-secret" }), "main");
+        let big = "x".repeat(MAX_PAYLOAD_BYTES + 1);
+        respond(&inspector, json!({ "id": 1, "result": big }), None);
+
+        let entries = inspector.query(&TrafficFilter::default());
+        let payload = entries[0].payload.as_ref().unwrap();
+        assert_ne!(payload["params"]["password"], json!("hunter2-secret"));
+        assert_eq!(entries[1].payload.as_ref().unwrap()["truncated"], json!(true));
+
+        for id in 0..TRAFFIC_BUFFER_LEN as u64 {
+            respond(&inspector, json!({ "id": id, "result": null }), None);
+        }
+        let entries = inspector.query(&TrafficFilter::default());
+        assert_eq!(entries.len(), TRAFFIC_BUFFER_LEN);
+        assert_eq!(entries[0].id, Some(RequestId::Number(0)));
+    }
+}
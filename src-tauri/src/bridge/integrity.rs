@@ -0,0 +1,116 @@
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use super::history::SpawnReport;
+
+/// Name and SHA-256 of the bundled bridge binary and native binding, generated by build.rs
+const MANIFEST: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/bundle_manifest.rs"));
+
+/// A bundled file the shell verifies before using it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    Bridge,
+    SqliteBinding,
+}
+
+impl Artifact {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bridge => "bridge",
+            Self::SqliteBinding => "better_sqlite3.node",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Verdict {
+    Verified,
+    /// The build had no copy to hash
+    Unlisted,
+    Mismatch { expected: String, actual: String },
+    Unreadable(String),
+}
+
+fn check(path: &Path, artifact: Artifact, manifest: &[(&str, &str)]) -> Verdict {
+    let Some((_, expected)) = manifest.iter().find(|(name, _)| *name == artifact.name()) else {
+        return Verdict::Unlisted;
+    };
+    let actual = match sha256(path) {
+        Ok(actual) => actual,
+        Err(e) => return Verdict::Unreadable(e.to_string()),
+    };
+    if actual.eq_ignore_ascii_case(expected) {
+        Verdict::Verified
+    } else {
+        Verdict::Mismatch {
+            expected: expected.to_string(),
+            actual,
+        }
+    }
+}
+
+fn sha256(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Whether the bundled file at `path` may be used.
+/// Files that differ from the build, or that the build has no hash for, are skipped with a warning in the report
+/// so the caller can move on to its next candidate.
+pub fn verify(report: &mut SpawnReport, path: &Path, artifact: Artifact) -> bool {
+    let problem = match check(path, artifact, MANIFEST) {
+        Verdict::Verified => return true,
+        Verdict::Unlisted => format!("Skipped {}: not in the build's integrity manifest", path.display()),
+        Verdict::Mismatch { expected, actual } => format!(
+            "Skipped {}: SHA-256 {} does not match the bundled {} (tampered or stale)",
+            path.display(),
+            actual,
+            expected
+        ),
+        Verdict::Unreadable(e) => format!("Skipped {}: {}", path.display(), e),
+    };
+    report.warnings.push(problem);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(dir: &tempfile::TempDir, contents: &str) -> std::path::PathBuf {
+        let path = dir.path().join("artifact");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn files_are_checked_against_the_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(&dir, "bridge");
+        let hash = sha256(&path).unwrap();
+        let manifest = [("bridge", hash.as_str())];
+
+        assert_eq!(check(&path, Artifact::Bridge, &manifest), Verdict::Verified);
+        assert_eq!(check(&path, Artifact::SqliteBinding, &manifest), Verdict::Unlisted);
+        let stale = "0".repeat(64);
+        assert!(matches!(
+            check(&path, Artifact::Bridge, &[("bridge", stale.as_str())]),
+            Verdict::Mismatch { .. }
+        ));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(check(&path, Artifact::Bridge, &manifest), Verdict::Unreadable(_)));
+    }
+
+    #[test]
+    fn files_failing_the_check_are_skipped_and_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(&dir, "not the bundled bridge");
+        let mut report = SpawnReport::new();
+
+        assert!(!verify(&mut report, &path, Artifact::Bridge));
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].starts_with(&format!("Skipped {}", path.display())), "{:?}", report.warnings);
+    }
+}
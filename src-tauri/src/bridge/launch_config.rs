@@ -0,0 +1,263 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the optional launch configuration, looked up in the config dir and next to the executable
+pub const CONFIG_FILE: &str = "bridge.toml";
+
+/// How the shell talks to the bridge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// Newline-delimited JSON over stdin/stdout
+    #[default]
+    Stdio,
+}
+
+/// `bridge.toml`: how to start the bridge, tried before the built-in strategies.
+/// Relative paths are resolved against the directory the file is in.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "snake_case")]
+pub struct LaunchConfig {
+    /// Program to run, or a `.js`/`.cjs`/`.mjs` script to run with Node.
+    /// Without one only `node` and `sqlite_binding` apply, to the built-in strategies.
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub working_dir: Option<PathBuf>,
+    /// Node executable for bridge scripts, preferred over the ones found on the PATH and in version managers
+    pub node: Option<PathBuf>,
+    /// `better_sqlite3.node` handed to the bridge in `RELWAVE_SQLITE_NATIVE_BINDING`
+    pub sqlite_binding: Option<PathBuf>,
+    pub transport: Transport,
+    /// File the configuration was read from
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl LaunchConfig {
+    /// Look for `bridge.toml` in the config dir, then next to the executable
+    pub fn discover(config_dir: Option<PathBuf>) -> Option<Result<Self, String>> {
+        let exe_dir = std::env::current_exe()
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf));
+        let dirs: Vec<PathBuf> = config_dir.into_iter().chain(exe_dir).collect();
+        Self::find(&dirs)
+    }
+
+    /// Read and validate the first `bridge.toml` found in `dirs`.
+    /// `None` if there is none; an error names the file and everything wrong with it.
+    pub fn find(dirs: &[PathBuf]) -> Option<Result<Self, String>> {
+        let path = dirs.iter().map(|d| d.join(CONFIG_FILE)).find(|p| p.is_file())?;
+        Some(Self::load(&path))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let mut config: Self = toml::from_str(&text).map_err(|e| format!("invalid {}: {}", path.display(), e))?;
+        config.path = path.to_path_buf();
+        config.resolve_paths();
+        config
+            .validate()
+            .map_err(|problems| format!("invalid {}: {}", path.display(), problems.join("; ")))?;
+        Ok(config)
+    }
+
+    fn resolve_paths(&mut self) {
+        let Some(base) = self.path.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        let resolve = |p: &mut PathBuf| {
+            if p.is_relative() {
+                *p = base.join(&*p);
+            }
+        };
+        if let Some(dir) = self.working_dir.as_mut() {
+            resolve(dir);
+        }
+        if let Some(binding) = self.sqlite_binding.as_mut() {
+            resolve(binding);
+        }
+        // Bare program names are looked up on the PATH; only paths are resolved
+        if let Some(node) = self.node.as_mut().filter(|n| is_path(n)) {
+            resolve(node);
+        }
+        if let Some(command) = self.command.as_mut().filter(|c| is_path(Path::new(c.as_str()))) {
+            if Path::new(command.as_str()).is_relative() {
+                *command = base.join(command.as_str()).to_string_lossy().into_owned();
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        match self.command.as_deref() {
+            Some(command) if command.trim().is_empty() => problems.push("command is empty".to_string()),
+            Some(command) if is_path(Path::new(command)) && !Path::new(command).is_file() => {
+                problems.push(format!("command {} does not exist", command))
+            }
+            Some(_) => {}
+            None if !self.args.is_empty() || !self.env.is_empty() || self.working_dir.is_some() => {
+                problems.push("args, env and working_dir need a command".to_string())
+            }
+            None => {}
+        }
+        if let Some(dir) = self.working_dir.as_ref().filter(|d| !d.is_dir()) {
+            problems.push(format!("working_dir {} is not a directory", dir.display()));
+        }
+        if let Some(node) = self.node.as_ref().filter(|n| is_path(n) && !n.is_file()) {
+            problems.push(format!("node {} does not exist", node.display()));
+        }
+        if let Some(binding) = self.sqlite_binding.as_ref().filter(|b| !b.is_file()) {
+            problems.push(format!("sqlite_binding {} does not exist", binding.display()));
+        }
+        for key in self.env.keys().filter(|k| k.is_empty() || k.contains('=')) {
+            problems.push(format!("env name '{}' is invalid", key));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Program and arguments to run; scripts go through the Node `node` returns
+    pub fn program(&self, node: impl FnOnce() -> String) -> Option<(String, Vec<String>)> {
+        let command = self.command.as_ref()?;
+        if is_script(command) {
+            let mut args = vec![command.clone()];
+            args.extend(self.args.iter().cloned());
+            Some((node(), args))
+        } else {
+            Some((command.clone(), self.args.clone()))
+        }
+    }
+}
+
+fn is_path(program: &Path) -> bool {
+    program.components().count() > 1 || program.is_absolute()
+}
+
+fn is_script(command: &str) -> bool {
+    [".js", ".cjs", ".mjs"].iter().any(|ext| command.ends_with(ext))
+}
+
+/// Split a command line on whitespace, keeping "double" or 'single' quoted parts together.
+/// Backslashes are kept as they are, so Windows paths need no escaping.
+pub fn split_command_line(line: &str) -> Result<Vec<String>, String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_part = false;
+    let mut quote = None;
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_part = true;
+            }
+            None if c.is_whitespace() => {
+                if in_part {
+                    parts.push(std::mem::take(&mut current));
+                    in_part = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_part = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote".into());
+    }
+    if in_part {
+        parts.push(current);
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(dir: &Path, text: &str) -> PathBuf {
+        let path = dir.join(CONFIG_FILE);
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn command_lines_keep_quoted_paths_together() {
+        assert_eq!(
+            split_command_line(r#"node "C:\Program Files\relwave\index.cjs" --inspect"#).unwrap(),
+            vec!["node", r"C:\Program Files\relwave\index.cjs", "--inspect"]
+        );
+        assert_eq!(
+            split_command_line("  'my node'  a''b \"\" ").unwrap(),
+            vec!["my node", "ab", ""]
+        );
+        assert!(split_command_line("node \"unterminated").is_err());
+    }
+
+    #[test]
+    fn scripts_run_with_the_configured_node() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        std::fs::create_dir_all(dir.join("dist")).unwrap();
+        std::fs::write(dir.join("dist").join("index.cjs"), "").unwrap();
+        let path = write_config(
+            &dir,
+            r#"
+command = "dist/index.cjs"
+args = ["--verbose"]
+node = "node20"
+env = { RELWAVE_LOG = "debug" }
+working_dir = "."
+"#,
+        );
+
+        let config = LaunchConfig::load(&path).unwrap();
+        assert_eq!(config.node, Some(PathBuf::from("node20")));
+        let (program, args) = config.program(|| "node20".into()).unwrap();
+        assert_eq!(program, "node20");
+        assert_eq!(args, vec![dir.join("dist").join("index.cjs").to_string_lossy().into_owned(), "--verbose".into()]);
+        assert_eq!(config.working_dir, Some(dir.join(".")));
+        assert_eq!(config.transport, Transport::Stdio);
+    }
+
+    #[test]
+    fn invalid_files_list_every_problem() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        let path = write_config(
+            &dir,
+            r#"
+command = "./missing-bridge"
+working_dir = "nowhere"
+sqlite_binding = "better_sqlite3.node"
+"#,
+        );
+        let error = LaunchConfig::load(&path).unwrap_err();
+        assert!(error.contains("missing-bridge does not exist"), "{}", error);
+        assert!(error.contains("working_dir"), "{}", error);
+        assert!(error.contains("sqlite_binding"), "{}", error);
+
+        write_config(&dir, "transport = \"tcp\"\n");
+        assert!(LaunchConfig::find(std::slice::from_ref(&dir)).unwrap().is_err());
+        write_config(&dir, "commmand = \"node\"\n");
+        assert!(LaunchConfig::find(std::slice::from_ref(&dir)).unwrap().is_err());
+    }
+
+    #[test]
+    fn the_first_directory_with_a_file_wins() {
+        let (empty, found) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (empty, dir) = (empty.path().to_path_buf(), found.path().to_path_buf());
+        write_config(&dir, "command = \"bridge\"\n");
+
+        assert!(LaunchConfig::find(std::slice::from_ref(&empty)).is_none());
+        let config = LaunchConfig::find(&[empty.clone(), dir.clone()]).unwrap().unwrap();
+        assert_eq!(config.program(|| unreachable!()), Some(("bridge".to_string(), vec![])));
+    }
+}
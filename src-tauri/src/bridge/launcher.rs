@@ -0,0 +1,53 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::Child;
+
+use super::history::SpawnReport;
+use super::launch_config::LaunchConfig;
+use super::process::{spawn_bridge_process, BridgeChild};
+
+/// A started bridge with the streams the shell reads from it
+pub struct LaunchedBridge {
+    pub child: BridgeChild,
+    pub stdout: Option<Box<dyn Read + Send>>,
+    pub stderr: Option<Box<dyn Read + Send>>,
+}
+
+impl From<Child> for LaunchedBridge {
+    fn from(mut child: Child) -> Self {
+        let stdout = child.stdout.take().map(|s| Box::new(s) as Box<dyn Read + Send>);
+        let stderr = child.stderr.take().map(|s| Box::new(s) as Box<dyn Read + Send>);
+        Self {
+            child: BridgeChild::Process(child),
+            stdout,
+            stderr,
+        }
+    }
+}
+
+/// Starts the bridge. Every strategy tried goes into `report`, so a failed start can be diagnosed.
+pub trait BridgeLauncher: Send + Sync {
+    fn launch(&self, report: &mut SpawnReport) -> Result<LaunchedBridge, String>;
+}
+
+/// The launcher `spawn_bridge` uses when the app manages one; otherwise the bridge is spawned as a process
+pub struct Launcher(pub Box<dyn BridgeLauncher>);
+
+/// Spawns the bridge as a child process: the `bridge.toml` command if there is one,
+/// then the bundled and development locations in turn
+pub struct ProcessLauncher {
+    resource_dir: Option<PathBuf>,
+    config: Option<LaunchConfig>,
+}
+
+impl ProcessLauncher {
+    pub fn new(resource_dir: Option<PathBuf>, config: Option<LaunchConfig>) -> Self {
+        Self { resource_dir, config }
+    }
+}
+
+impl BridgeLauncher for ProcessLauncher {
+    fn launch(&self, report: &mut SpawnReport) -> Result<LaunchedBridge, String> {
+        spawn_bridge_process(self.resource_dir.as_deref(), self.config.as_ref(), report).map(LaunchedBridge::from)
+    }
+}
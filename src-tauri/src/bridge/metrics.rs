@@ -0,0 +1,399 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::pending::PendingRequest;
+use super::protocol::RpcRequest;
+
+/// Event carrying a `MetricsSnapshot` while periodic reporting is on
+pub const METRICS_EVENT: &str = "bridge-metrics";
+
+/// Most recent latencies kept per method for the percentiles
+const LATENCY_SAMPLES: usize = 1000;
+
+/// Finished query sessions whose throughput is still reported
+const FINISHED_SESSIONS: usize = 50;
+
+#[derive(Default)]
+struct MethodStats {
+    count: u64,
+    errors: u64,
+    latencies_ms: VecDeque<f64>,
+    bytes_out: u64,
+    bytes_in: u64,
+}
+
+impl MethodStats {
+    fn summary(&self, method: &str) -> MethodMetrics {
+        let mut sorted: Vec<f64> = self.latencies_ms.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        MethodMetrics {
+            method: method.to_string(),
+            count: self.count,
+            errors: self.errors,
+            p50_ms: percentile(&sorted, 50.0),
+            p95_ms: percentile(&sorted, 95.0),
+            p99_ms: percentile(&sorted, 99.0),
+            max_ms: sorted.last().copied(),
+            bytes_out: self.bytes_out,
+            bytes_in: self.bytes_in,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+/// Counters of one method; notifications count towards their own method name and have no latency
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodMetrics {
+    pub method: String,
+    pub count: u64,
+    /// Error responses, including `{ ok: false }` results
+    pub errors: u64,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    pub bytes_out: u64,
+    pub bytes_in: u64,
+}
+
+struct SessionStats {
+    started_at: Instant,
+    last_batch_at: Option<Instant>,
+    finished_at: Option<Instant>,
+    rows: u64,
+    batches: u64,
+    bytes: u64,
+}
+
+impl SessionStats {
+    fn new(started_at: Instant) -> Self {
+        Self {
+            started_at,
+            last_batch_at: None,
+            finished_at: None,
+            rows: 0,
+            batches: 0,
+            bytes: 0,
+        }
+    }
+}
+
+/// `query.result` throughput of one query session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionThroughput {
+    pub session_id: String,
+    pub rows: u64,
+    pub batches: u64,
+    pub bytes: u64,
+    /// From `query.started` to the last batch (or now, while still streaming)
+    pub elapsed_ms: f64,
+    pub rows_per_sec: f64,
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSnapshot {
+    /// When counting started (launch or the last reset)
+    pub since: String,
+    pub methods: Vec<MethodMetrics>,
+    pub sessions: Vec<SessionThroughput>,
+}
+
+/// Per-method latency and throughput of bridge traffic, measured in the shell
+pub struct BridgeMetrics {
+    since: Mutex<String>,
+    methods: Mutex<HashMap<String, MethodStats>>,
+    sessions: Mutex<HashMap<String, SessionStats>>,
+    /// Finished sessions, oldest first, so the map doesn't grow forever
+    finished: Mutex<VecDeque<String>>,
+    /// Bumped whenever the reporting interval changes, so the old reporter thread stops
+    reporter: AtomicU64,
+}
+
+impl Default for BridgeMetrics {
+    fn default() -> Self {
+        Self {
+            since: Mutex::new(chrono::Utc::now().to_rfc3339()),
+            methods: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+            finished: Mutex::new(VecDeque::new()),
+            reporter: AtomicU64::new(0),
+        }
+    }
+}
+
+fn is_error(frame: &Value) -> bool {
+    frame.get("error").is_some()
+        || frame
+            .get("result")
+            .and_then(|r| r.get("ok"))
+            .is_some_and(|ok| ok == &Value::Bool(false))
+}
+
+impl BridgeMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record_request(&self, request: &RpcRequest, size: usize) {
+        let mut methods = self.methods.lock().unwrap();
+        methods.entry(request.method.clone()).or_default().bytes_out += size as u64;
+    }
+
+    fn record_response(&self, pending: &PendingRequest, frame: &Value, size: usize) {
+        let mut methods = self.methods.lock().unwrap();
+        let stats = methods.entry(pending.request.method.clone()).or_default();
+        stats.count += 1;
+        stats.bytes_in += size as u64;
+        if is_error(frame) {
+            stats.errors += 1;
+        }
+        if stats.latencies_ms.len() == LATENCY_SAMPLES {
+            stats.latencies_ms.pop_front();
+        }
+        stats.latencies_ms.push_back(pending.started_at.elapsed().as_secs_f64() * 1000.0);
+    }
+
+    fn record_notification(&self, frame: &Value, size: usize) {
+        let Some(method) = frame.get("method").and_then(Value::as_str) else {
+            return;
+        };
+        {
+            let mut methods = self.methods.lock().unwrap();
+            let stats = methods.entry(method.to_string()).or_default();
+            stats.count += 1;
+            stats.bytes_in += size as u64;
+            if method == "query.error" {
+                stats.errors += 1;
+            }
+        }
+
+        let params = frame.get("params").unwrap_or(&Value::Null);
+        let Some(session) = params.get("sessionId").and_then(Value::as_str) else {
+            return;
+        };
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        match method {
+            "query.started" => {
+                // A session runs one query at a time; a new one starts its throughput over
+                self.finished.lock().unwrap().retain(|s| s != session);
+                sessions.insert(session.to_string(), SessionStats::new(now));
+            }
+            "query.result" => {
+                // Sessions that started before the shell was watching count from their first batch
+                let stats = sessions.entry(session.to_string()).or_insert_with(|| SessionStats::new(now));
+                stats.rows += params.get("rows").and_then(Value::as_array).map_or(0, Vec::len) as u64;
+                stats.batches += 1;
+                stats.bytes += size as u64;
+                stats.last_batch_at = Some(now);
+            }
+            "query.done" | "query.error" => {
+                if let Some(stats) = sessions.get_mut(session) {
+                    if stats.finished_at.is_none() {
+                        stats.finished_at = Some(now);
+                        let mut finished = self.finished.lock().unwrap();
+                        finished.push_back(session.to_string());
+                        if finished.len() > FINISHED_SESSIONS {
+                            if let Some(oldest) = finished.pop_front() {
+                                sessions.remove(&oldest);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let mut methods: Vec<MethodMetrics> = self
+            .methods
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(method, stats)| stats.summary(method))
+            .collect();
+        methods.sort_by(|a, b| a.method.cmp(&b.method));
+
+        let now = Instant::now();
+        let mut sessions: Vec<(Instant, SessionThroughput)> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, stats)| {
+                let end = match stats.finished_at {
+                    Some(_) => stats.last_batch_at.unwrap_or(stats.started_at),
+                    None => now,
+                };
+                let elapsed = end.duration_since(stats.started_at).as_secs_f64();
+                let throughput = SessionThroughput {
+                    session_id: id.clone(),
+                    rows: stats.rows,
+                    batches: stats.batches,
+                    bytes: stats.bytes,
+                    elapsed_ms: elapsed * 1000.0,
+                    rows_per_sec: if elapsed > 0.0 { stats.rows as f64 / elapsed } else { 0.0 },
+                    finished: stats.finished_at.is_some(),
+                };
+                (stats.started_at, throughput)
+            })
+            .collect();
+        sessions.sort_by_key(|(started_at, _)| *started_at);
+
+        MetricsSnapshot {
+            since: self.since.lock().unwrap().clone(),
+            methods,
+            sessions: sessions.into_iter().map(|(_, s)| s).collect(),
+        }
+    }
+
+    pub fn reset(&self) {
+        *self.since.lock().unwrap() = chrono::Utc::now().to_rfc3339();
+        self.methods.lock().unwrap().clear();
+        self.sessions.lock().unwrap().clear();
+        self.finished.lock().unwrap().clear();
+    }
+
+    /// Emit a snapshot to every window every `interval`; `None` stops reporting
+    pub fn report_every(&self, app_handle: &AppHandle, interval: Option<Duration>) {
+        let generation = self.reporter.fetch_add(1, Ordering::Relaxed) + 1;
+        let Some(interval) = interval.filter(|i| !i.is_zero()) else {
+            return;
+        };
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(metrics) = app_handle.try_state::<BridgeMetrics>() else {
+                return;
+            };
+            if metrics.reporter.load(Ordering::Relaxed) != generation {
+                return;
+            }
+            let _ = app_handle.emit(METRICS_EVENT, metrics.snapshot());
+        });
+    }
+}
+
+/// Count the bytes of a request written to the bridge
+pub fn observe_request(app_handle: &AppHandle, request: &RpcRequest, data: &str) {
+    if let Some(metrics) = app_handle.try_state::<BridgeMetrics>() {
+        metrics.record_request(request, data.len());
+    }
+}
+
+/// Count a response against the method of the request it answers
+pub fn observe_response(app_handle: &AppHandle, pending: &PendingRequest, frame: &Value, line: &str) {
+    if let Some(metrics) = app_handle.try_state::<BridgeMetrics>() {
+        metrics.record_response(pending, frame, line.len());
+    }
+}
+
+/// Count a notification, and follow `query.result` batches per session
+pub fn observe_notification(app_handle: &AppHandle, frame: &Value, line: &str) {
+    if let Some(metrics) = app_handle.try_state::<BridgeMetrics>() {
+        metrics.record_notification(frame, line.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::protocol;
+    use serde_json::json;
+
+    fn pending(method: &str, started_ms_ago: u64) -> PendingRequest {
+        let request = protocol::parse_request(&json!({ "id": 1, "method": method }).to_string()).unwrap();
+        PendingRequest {
+            client_id: request.id.clone(),
+            request,
+            window: "main".into(),
+            started_at: Instant::now() - Duration::from_millis(started_ms_ago),
+            reply: None,
+        }
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let samples: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentile(&samples, 50.0), Some(50.0));
+        assert_eq!(percentile(&samples, 95.0), Some(95.0));
+        assert_eq!(percentile(&samples, 99.0), Some(99.0));
+        assert_eq!(percentile(&samples, 100.0), Some(100.0));
+        assert_eq!(percentile(&samples, 0.0), Some(1.0));
+        assert_eq!(percentile(&[7.0], 99.0), Some(7.0));
+        assert_eq!(percentile(&[1.0, 2.0, 3.0], 50.0), Some(2.0));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[test]
+    fn responses_count_against_their_method() {
+        let metrics = BridgeMetrics::new();
+        let request = pending("db.list", 0).request;
+        metrics.record_request(&request, 40);
+        for (ago, frame) in [
+            (30, json!({ "id": 1, "result": [] })),
+            (10, json!({ "id": 1, "error": { "code": "X" } })),
+            (20, json!({ "id": 1, "result": { "ok": false } })),
+        ] {
+            metrics.record_response(&pending("db.list", ago), &frame, 10);
+        }
+
+        let snapshot = metrics.snapshot();
+        let stats = &snapshot.methods[0];
+        assert_eq!(stats.method, "db.list");
+        assert_eq!((stats.count, stats.errors, stats.bytes_out, stats.bytes_in), (3, 2, 40, 30));
+        let (p50, max) = (stats.p50_ms.unwrap(), stats.max_ms.unwrap());
+        assert!((20.0..30.0).contains(&p50), "{}", p50);
+        assert!(max >= 30.0 && stats.p99_ms == Some(max));
+
+        metrics.reset();
+        assert!(metrics.snapshot().methods.is_empty());
+    }
+
+    #[test]
+    fn query_sessions_report_rows_until_they_finish() {
+        let metrics = BridgeMetrics::new();
+        let notify = |method: &str, params: Value| {
+            metrics.record_notification(&json!({ "method": method, "params": params }), 100)
+        };
+        notify("query.started", json!({ "sessionId": "s1" }));
+        notify("query.result", json!({ "sessionId": "s1", "rows": [1, 2, 3] }));
+        notify("query.result", json!({ "sessionId": "s1", "rows": [4] }));
+        notify("query.result", json!({ "sessionId": "s2", "rows": [1] }));
+        notify("query.done", json!({ "sessionId": "s1" }));
+        notify("query.error", json!({ "sessionId": "s2" }));
+
+        let snapshot = metrics.snapshot();
+        let s1 = snapshot.sessions.iter().find(|s| s.session_id == "s1").unwrap();
+        assert_eq!((s1.rows, s1.batches, s1.bytes, s1.finished), (4, 2, 200, true));
+        let errors = snapshot.methods.iter().find(|m| m.method == "query.error").unwrap();
+        assert_eq!((errors.count, errors.errors, errors.p50_ms), (1, 1, None));
+
+        for n in 0..FINISHED_SESSIONS {
+            let session = format!("later-{}", n);
+            notify("query.started", json!({ "sessionId": session }));
+            notify("query.done", json!({ "sessionId": session }));
+        }
+        let sessions = metrics.snapshot().sessions;
+        assert_eq!(sessions.len(), FINISHED_SESSIONS);
+        assert!(sessions.iter().all(|s| s.session_id.starts_with("later-")));
+    }
+}
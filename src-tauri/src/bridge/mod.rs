@@ -1,19 +1,76 @@
 mod process;
+mod client;
 mod commands;
+#[cfg(test)]
+pub mod fake;
+mod history;
+mod inspector;
+#[cfg(any(not(debug_assertions), test))]
+mod integrity;
+mod launch_config;
+mod launcher;
+mod metrics;
+mod node;
+mod pending;
+pub mod policy;
+mod protocol;
+mod recording;
+mod redact;
+mod replay;
+mod router;
+pub mod safety;
+mod timeouts;
 
-pub use process::BridgeProcess;
-pub use commands::{bridge_write, bridge_kill, bridge_restart, bridge_status};
+pub use process::{BridgeChild, BridgeProcess};
+pub use client::{background_request, request, BACKGROUND_WINDOW};
+pub use commands::{bridge_write, bridge_cancel, bridge_kill, bridge_late_responses, bridge_timeouts_get, bridge_timeouts_set, bridge_metrics, bridge_metrics_report, bridge_restart, bridge_status, bridge_record_start, bridge_record_stop, bridge_recording_status, bridge_replay_start, bridge_replay_stop, bridge_set_raw_output, bridge_policy, safety_list_modes, safety_set_mode, safety_confirm};
+pub use history::{BridgeHistory, SpawnReport};
+pub use metrics::BridgeMetrics;
+pub use inspector::{to_har, TrafficEntry, TrafficFilter, TrafficInspector};
+pub use launch_config::LaunchConfig;
+pub use launcher::{BridgeLauncher, Launcher, ProcessLauncher};
+pub use pending::{PendingRequest, PendingRequests};
+pub use protocol::{RequestId, RpcRequest};
+pub use recording::BridgeRecorder;
+pub use redact::{Redactor, REDACTED};
+pub use router::{window_closed, BridgeRouter};
+pub use safety::{mutating_statements, SafetyGuard, Verdict};
+pub use timeouts::{start_watchdog, RequestTimeouts};
 
-use tauri::AppHandle;
-use std::process::Child;
+use tauri::{AppHandle, Manager};
 
 /// Initialize and spawn the bridge process
-pub fn init(app_handle: AppHandle) -> Option<Child> {
-    match process::spawn_bridge(app_handle) {
+pub fn init(app_handle: AppHandle) -> Option<BridgeChild> {
+    match process::spawn_bridge(app_handle.clone()) {
         Ok(child) => Some(child),
         Err(e) => {
-            eprintln!("Failed to spawn bridge: {}", e);
+            crate::diagnostics::shell_log(&app_handle, format!("Failed to spawn bridge: {}", e));
             None
         }
     }
 }
+
+/// Kill the running bridge, if any, and start a new one.
+/// Requests in flight and query sessions belong to the old process and are forgotten.
+pub fn restart(app_handle: &AppHandle) -> Result<(), String> {
+    if let Some(pending) = app_handle.try_state::<PendingRequests>() {
+        pending::abandon(app_handle, pending.clear(), "bridge restarted");
+    }
+    if let Some(router) = app_handle.try_state::<BridgeRouter>() {
+        router.clear();
+    }
+    if let Some(notifier) = app_handle.try_state::<crate::notifications::Notifier>() {
+        notifier.clear();
+    }
+    if let Some(history) = app_handle.try_state::<BridgeHistory>() {
+        history.record_status("restarting");
+    }
+    crate::tray::refresh(app_handle);
+
+    let result = match app_handle.try_state::<BridgeProcess>() {
+        Some(state) => state.replace(|| process::spawn_bridge(app_handle.clone())),
+        None => Err("bridge not available".into()),
+    };
+    crate::tray::refresh(app_handle);
+    result
+}
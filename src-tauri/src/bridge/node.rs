@@ -0,0 +1,326 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Oldest Node major version the bridge scripts run on
+pub const MIN_NODE_MAJOR: u32 = 22;
+
+#[cfg(target_os = "windows")]
+const NODE_EXE: &str = "node.exe";
+#[cfg(not(target_os = "windows"))]
+const NODE_EXE: &str = "node";
+
+/// Where a Node candidate was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeSource {
+    /// `node` in `bridge.toml`
+    Config,
+    /// A runtime shipped with the app's resources
+    Bundled,
+    Path,
+    Nvm,
+    Volta,
+    Fnm,
+}
+
+/// A Node executable that was found, and what `--version` said
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeCandidate {
+    pub path: PathBuf,
+    pub source: NodeSource,
+    /// `None` if it was not run because an earlier candidate was picked
+    pub version: Option<String>,
+    pub compatible: bool,
+    pub error: Option<String>,
+}
+
+/// The Node candidates considered for bridge scripts and the one picked
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSelection {
+    pub minimum_major: u32,
+    pub selected: Option<PathBuf>,
+    pub candidates: Vec<NodeCandidate>,
+}
+
+impl NodeSelection {
+    /// Program to run scripts with; plain `node` if nothing usable was found, so the spawn error says why
+    pub fn program(&self) -> String {
+        self.selected
+            .as_ref()
+            .map_or_else(|| "node".to_string(), |p| p.to_string_lossy().into_owned())
+    }
+
+    /// One line for the shell log
+    pub fn summary(&self) -> String {
+        let selected = self
+            .selected
+            .as_ref()
+            .and_then(|path| self.candidates.iter().find(|c| &c.path == path));
+        match selected {
+            Some(c) if c.compatible => format!(
+                "Using Node {} from {} ({:?})",
+                c.version.as_deref().unwrap_or("?"),
+                c.path.display(),
+                c.source
+            ),
+            Some(c) => format!(
+                "Using Node {} from {} ({:?}), older than the required {}",
+                c.version.as_deref().unwrap_or("?"),
+                c.path.display(),
+                c.source,
+                MIN_NODE_MAJOR
+            ),
+            None => format!(
+                "No working Node found ({} candidates); Node {} or newer is required",
+                self.candidates.len(),
+                MIN_NODE_MAJOR
+            ),
+        }
+    }
+}
+
+/// Find Node executables and pick one for bridge scripts.
+/// Candidates are tried in order (configured, bundled, PATH, nvm, volta, fnm; newest version first within
+/// a version manager) and the first compatible one wins. A configured Node is used even if it is too old;
+/// otherwise the first one that runs is the fallback.
+pub fn discover(configured: Option<&Path>, resource_dir: Option<&Path>) -> NodeSelection {
+    let mut found: Vec<(PathBuf, NodeSource)> = Vec::new();
+    let mut add = |path: PathBuf, source: NodeSource| {
+        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if !found.iter().any(|(p, _)| p.canonicalize().unwrap_or_else(|_| p.clone()) == key) {
+            found.push((path, source));
+        }
+    };
+    if let Some(node) = configured {
+        add(node.to_path_buf(), NodeSource::Config);
+    }
+    for path in bundled(resource_dir) {
+        add(path, NodeSource::Bundled);
+    }
+    for path in on_path() {
+        add(path, NodeSource::Path);
+    }
+    for (source, paths) in [
+        (NodeSource::Nvm, nvm()),
+        (NodeSource::Volta, volta()),
+        (NodeSource::Fnm, fnm()),
+    ] {
+        for path in paths {
+            add(path, source);
+        }
+    }
+
+    let mut candidates: Vec<NodeCandidate> = found
+        .into_iter()
+        .map(|(path, source)| NodeCandidate {
+            path,
+            source,
+            version: None,
+            compatible: false,
+            error: None,
+        })
+        .collect();
+    let mut selected = None;
+    let mut fallback = None;
+    for candidate in candidates.iter_mut() {
+        match node_version(&candidate.path) {
+            Ok(version) => {
+                candidate.compatible = parse_major(&version).is_some_and(|major| major >= MIN_NODE_MAJOR);
+                candidate.version = Some(version);
+                if candidate.compatible || candidate.source == NodeSource::Config {
+                    selected = Some(candidate.path.clone());
+                    break;
+                }
+                fallback.get_or_insert_with(|| candidate.path.clone());
+            }
+            Err(e) => candidate.error = Some(e),
+        }
+    }
+
+    NodeSelection {
+        minimum_major: MIN_NODE_MAJOR,
+        selected: selected.or(fallback),
+        candidates,
+    }
+}
+
+/// Output of `node --version`, e.g. `v22.11.0`
+fn node_version(path: &Path) -> Result<String, String> {
+    let mut cmd = Command::new(path);
+    cmd.arg("--version").stdin(Stdio::null()).stderr(Stdio::null());
+
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let output = cmd.output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("--version exited with {}", output.status));
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if parse_major(&version).is_none() {
+        return Err(format!("unexpected --version output '{}'", version));
+    }
+    Ok(version)
+}
+
+/// Major version of `v22.11.0` or `22.11.0`
+fn parse_major(version: &str) -> Option<u32> {
+    version.trim().trim_start_matches('v').split('.').next()?.parse().ok()
+}
+
+/// A Node runtime shipped next to the bridge resources
+fn bundled(resource_dir: Option<&Path>) -> Vec<PathBuf> {
+    let Some(dir) = resource_dir else {
+        return Vec::new();
+    };
+    [
+        dir.join(NODE_EXE),
+        dir.join("node").join(NODE_EXE),
+        dir.join("node").join("bin").join(NODE_EXE),
+        dir.join("_up_").join("node").join(NODE_EXE),
+        dir.join("_up_").join("node").join("bin").join(NODE_EXE),
+    ]
+    .into_iter()
+    .filter(|p| p.is_file())
+    .collect()
+}
+
+fn on_path() -> Vec<PathBuf> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(NODE_EXE))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+/// `<versions>/<version>/<bin>` for every installed version, newest first
+fn installed_versions(versions: &Path, bin: &[&str]) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(versions) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(Vec<u32>, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let version: Vec<u32> = name
+                .trim_start_matches('v')
+                .split('.')
+                .map_while(|part| part.parse().ok())
+                .collect();
+            let path = bin.iter().fold(entry.path(), |p, part| p.join(part));
+            (!version.is_empty() && path.is_file()).then_some((version, path))
+        })
+        .collect();
+    found.sort_by(|a, b| b.0.cmp(&a.0));
+    found.into_iter().map(|(_, path)| path).collect()
+}
+
+fn bin_layout() -> Vec<&'static str> {
+    if cfg!(target_os = "windows") {
+        vec![NODE_EXE]
+    } else {
+        vec!["bin", NODE_EXE]
+    }
+}
+
+fn nvm() -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        // nvm-windows keeps each version directly under NVM_HOME
+        let home = env_dir("NVM_HOME").or_else(|| dirs::data_dir().map(|d| d.join("nvm")));
+        return home.map_or_else(Vec::new, |home| installed_versions(&home, &[NODE_EXE]));
+    }
+    let home = env_dir("NVM_DIR").or_else(|| dirs::home_dir().map(|d| d.join(".nvm")));
+    home.map_or_else(Vec::new, |home| {
+        installed_versions(&home.join("versions").join("node"), &bin_layout())
+    })
+}
+
+fn volta() -> Vec<PathBuf> {
+    let home = env_dir("VOLTA_HOME").or_else(|| {
+        if cfg!(target_os = "windows") {
+            dirs::data_local_dir().map(|d| d.join("Volta"))
+        } else {
+            dirs::home_dir().map(|d| d.join(".volta"))
+        }
+    });
+    home.map_or_else(Vec::new, |home| {
+        installed_versions(&home.join("tools").join("image").join("node"), &bin_layout())
+    })
+}
+
+fn fnm() -> Vec<PathBuf> {
+    let homes = match env_dir("FNM_DIR") {
+        Some(dir) => vec![dir],
+        None => [
+            dirs::data_dir().map(|d| d.join("fnm")),
+            dirs::home_dir().map(|d| d.join(".fnm")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+    };
+    let mut bin = vec!["installation"];
+    bin.extend(bin_layout());
+    homes
+        .iter()
+        .flat_map(|home| installed_versions(&home.join("node-versions"), &bin))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn majors_are_read_with_or_without_the_v() {
+        assert_eq!(parse_major("v22.11.0\n"), Some(22));
+        assert_eq!(parse_major("18.0.0"), Some(18));
+        assert_eq!(parse_major("node"), None);
+    }
+
+    #[test]
+    fn installed_versions_are_listed_newest_first() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for version in ["v18.20.4", "v22.2.0", "v22.11.0", "not-a-version"] {
+            let bin = root.join(version).join("bin");
+            std::fs::create_dir_all(&bin).unwrap();
+            std::fs::write(bin.join(NODE_EXE), "").unwrap();
+        }
+        std::fs::create_dir_all(root.join("v20.0.0")).unwrap();
+
+        let found = installed_versions(root, &["bin", NODE_EXE]);
+        let names: Vec<String> = found
+            .iter()
+            .map(|p| p.parent().unwrap().parent().unwrap().file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["v22.11.0", "v22.2.0", "v18.20.4"]);
+    }
+
+    #[test]
+    fn a_missing_configured_node_is_reported_and_skipped() {
+        let temp = tempfile::tempdir().unwrap();
+        let missing = temp.path().join("no-such-node").join(NODE_EXE);
+        let selection = discover(Some(&missing), None);
+
+        let configured = &selection.candidates[0];
+        assert_eq!(configured.source, NodeSource::Config);
+        assert!(configured.error.is_some());
+        assert_ne!(selection.selected.as_ref(), Some(&missing));
+    }
+}
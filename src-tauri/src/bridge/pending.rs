@@ -0,0 +1,120 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+use super::protocol::{self, RequestId, RpcRequest};
+use crate::audit::{self, Outcome};
+
+/// A request that has been written to the bridge and is waiting for its response
+pub struct PendingRequest {
+    pub request: RpcRequest,
+    /// Id the window used; the shell rewrites ids so windows with their own counters never collide
+    pub client_id: RequestId,
+    pub window: String,
+    pub started_at: Instant,
+    /// Set for requests made by the shell itself; the response goes here instead of to a webview
+    pub reply: Option<Sender<Value>>,
+}
+
+/// Requests in flight, keyed by id, so responses can be matched to what was asked
+#[derive(Default)]
+pub struct PendingRequests(Mutex<HashMap<RequestId, PendingRequest>>);
+
+impl PendingRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track(
+        &self,
+        request: RpcRequest,
+        client_id: RequestId,
+        window: &str,
+        reply: Option<Sender<Value>>,
+    ) {
+        let id = request.id.clone();
+        self.0.lock().unwrap().insert(
+            id,
+            PendingRequest {
+                request,
+                client_id,
+                window: window.to_string(),
+                started_at: Instant::now(),
+                reply,
+            },
+        );
+    }
+
+    pub fn complete(&self, id: &RequestId) -> Option<PendingRequest> {
+        self.0.lock().unwrap().remove(id)
+    }
+
+    pub fn contains(&self, id: &RequestId) -> bool {
+        self.0.lock().unwrap().contains_key(id)
+    }
+
+    /// Stop tracking and return the requests `pick` selects
+    pub fn take_where(&self, mut pick: impl FnMut(&PendingRequest) -> bool) -> Vec<PendingRequest> {
+        let mut requests = self.0.lock().unwrap();
+        let ids: Vec<RequestId> = requests
+            .iter()
+            .filter(|(_, p)| pick(p))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.iter().filter_map(|id| requests.remove(id)).collect()
+    }
+
+    /// Forget everything in flight, e.g. when the bridge is restarted, and return what was forgotten
+    pub fn clear(&self) -> Vec<PendingRequest> {
+        self.0.lock().unwrap().drain().map(|(_, p)| p).collect()
+    }
+
+    /// Forget the requests of a window that has been closed, and return them
+    pub fn drop_window(&self, window: &str) -> Vec<PendingRequest> {
+        self.take_where(|p| p.window == window)
+    }
+}
+
+/// Complete the request a (redacted) response frame answers and hand it to the audit log.
+/// Returns `None` for notifications and responses nobody is waiting for.
+pub fn complete_response(app_handle: &AppHandle, frame: &Value) -> Option<PendingRequest> {
+    // Notifications carry a method and no id; only responses complete a request
+    if frame.get("method").is_some() {
+        return None;
+    }
+    let id = frame
+        .get("id")
+        .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok())?;
+    let pending = app_handle
+        .try_state::<PendingRequests>()
+        .and_then(|p| p.complete(&id))?;
+
+    audit::observe(
+        app_handle,
+        &pending.request,
+        &pending.window,
+        pending.started_at,
+        Outcome::Response(frame),
+    );
+    Some(pending)
+}
+
+/// Audit requests that will never be answered, because the bridge or the window that sent them went away
+pub fn abandon(app_handle: &AppHandle, requests: Vec<PendingRequest>, reason: &str) {
+    for pending in requests {
+        let error = protocol::error_object(
+            "ABANDONED",
+            format!("{} before answering {}", reason, pending.request.method),
+        );
+        audit::observe(
+            app_handle,
+            &pending.request,
+            &pending.window,
+            pending.started_at,
+            Outcome::Aborted("abandoned", error),
+        );
+    }
+}
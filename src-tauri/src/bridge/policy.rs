@@ -0,0 +1,277 @@
+use serde::Serialize;
+
+use super::protocol::RpcRequest;
+
+/// Label of the primary application window
+pub const MAIN_WINDOW: &str = "main";
+
+/// Risk level of a bridge method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    /// Reads data or metadata without side effects
+    Read,
+    /// Creates or modifies data, files or settings
+    Write,
+    /// Deletes data, drops objects or discards work
+    Destructive,
+    /// Talks to hosts other than the configured databases (git remotes, AI providers, discovery)
+    Network,
+}
+
+/// Every method the bridge exposes, grouped by risk level.
+/// Anything not listed here is rejected before it reaches the bridge.
+const METHODS: &[(&str, RiskLevel)] = &[
+    // Health
+    ("health.ping", RiskLevel::Read),
+    // Sessions and queries
+    ("query.createSession", RiskLevel::Write),
+    ("query.cancel", RiskLevel::Write),
+    ("query.getSession", RiskLevel::Read),
+    ("query.listSessions", RiskLevel::Read),
+    ("query.destroySession", RiskLevel::Write),
+    ("query.run", RiskLevel::Write),
+    ("query.fetchTableData", RiskLevel::Read),
+    ("query.listPrimaryKeys", RiskLevel::Read),
+    ("query.createTable", RiskLevel::Write),
+    ("query.createIndexes", RiskLevel::Write),
+    ("query.dropTable", RiskLevel::Destructive),
+    ("query.alterTable", RiskLevel::Destructive),
+    ("query.connectToDatabase", RiskLevel::Read),
+    ("query.insertRow", RiskLevel::Write),
+    ("query.updateRow", RiskLevel::Write),
+    ("query.deleteRow", RiskLevel::Destructive),
+    ("query.searchTable", RiskLevel::Read),
+    // Connections
+    ("db.list", RiskLevel::Read),
+    ("db.get", RiskLevel::Read),
+    ("db.add", RiskLevel::Write),
+    ("db.delete", RiskLevel::Destructive),
+    ("db.connectTest", RiskLevel::Read),
+    ("db.touch", RiskLevel::Write),
+    ("db.listTables", RiskLevel::Read),
+    ("db.getSchema", RiskLevel::Read),
+    ("db.listSchemas", RiskLevel::Read),
+    ("db.getStats", RiskLevel::Read),
+    ("db.getTotalStats", RiskLevel::Read),
+    ("db.monitoringSnapshot", RiskLevel::Read),
+    ("db.monitoringWsInfo", RiskLevel::Read),
+    ("db.discover", RiskLevel::Network),
+    // Migrations
+    ("migration.generateCreate", RiskLevel::Write),
+    ("migration.generateAlter", RiskLevel::Write),
+    ("migration.generateDrop", RiskLevel::Write),
+    ("migration.apply", RiskLevel::Write),
+    ("migration.applyMigrations", RiskLevel::Write),
+    ("migration.applySnapshot", RiskLevel::Write),
+    ("migration.rollback", RiskLevel::Destructive),
+    ("migration.delete", RiskLevel::Destructive),
+    ("migration.getSQL", RiskLevel::Read),
+    ("migration.status", RiskLevel::Read),
+    // Projects
+    ("project.list", RiskLevel::Read),
+    ("project.get", RiskLevel::Read),
+    ("project.getByDatabaseId", RiskLevel::Read),
+    ("project.create", RiskLevel::Write),
+    ("project.update", RiskLevel::Write),
+    ("project.delete", RiskLevel::Destructive),
+    ("project.getSchema", RiskLevel::Read),
+    ("project.saveSchema", RiskLevel::Write),
+    ("project.refreshSchemaCache", RiskLevel::Write),
+    ("project.getERDiagram", RiskLevel::Read),
+    ("project.saveERDiagram", RiskLevel::Write),
+    ("project.getAnnotations", RiskLevel::Read),
+    ("project.saveAnnotations", RiskLevel::Write),
+    ("project.analyzeImport", RiskLevel::Read),
+    ("project.verifyLock", RiskLevel::Read),
+    ("project.pushMigrations", RiskLevel::Write),
+    ("project.syncMigrations", RiskLevel::Write),
+    ("project.generateSQL", RiskLevel::Read),
+    ("project.getDrift", RiskLevel::Read),
+    ("project.getQueries", RiskLevel::Read),
+    ("project.addQuery", RiskLevel::Write),
+    ("project.updateQuery", RiskLevel::Write),
+    ("project.deleteQuery", RiskLevel::Write),
+    ("project.export", RiskLevel::Read),
+    ("project.getDir", RiskLevel::Read),
+    ("project.getLocalConfig", RiskLevel::Read),
+    ("project.saveLocalConfig", RiskLevel::Write),
+    ("project.ensureGitignore", RiskLevel::Write),
+    ("project.scanImport", RiskLevel::Read),
+    ("project.import", RiskLevel::Write),
+    ("project.linkDatabase", RiskLevel::Write),
+    ("project.unlinkFromConnection", RiskLevel::Write),
+    ("project.deleteWithConnection", RiskLevel::Destructive),
+    ("project.getGitRemote", RiskLevel::Read),
+    ("project.relinkToConnection", RiskLevel::Write),
+    // Git
+    ("git.status", RiskLevel::Read),
+    ("git.init", RiskLevel::Write),
+    ("git.changes", RiskLevel::Read),
+    ("git.stage", RiskLevel::Write),
+    ("git.stageAll", RiskLevel::Write),
+    ("git.unstage", RiskLevel::Write),
+    ("git.commit", RiskLevel::Write),
+    ("git.log", RiskLevel::Read),
+    ("git.branches", RiskLevel::Read),
+    ("git.createBranch", RiskLevel::Write),
+    ("git.checkout", RiskLevel::Write),
+    ("git.discard", RiskLevel::Destructive),
+    ("git.stash", RiskLevel::Write),
+    ("git.stashPop", RiskLevel::Write),
+    ("git.diff", RiskLevel::Read),
+    ("git.ensureIgnore", RiskLevel::Write),
+    ("git.logGraph", RiskLevel::Read),
+    ("git.remoteList", RiskLevel::Read),
+    ("git.remoteAdd", RiskLevel::Write),
+    ("git.remoteRemove", RiskLevel::Destructive),
+    ("git.remoteGetUrl", RiskLevel::Read),
+    ("git.remoteSetUrl", RiskLevel::Write),
+    ("git.push", RiskLevel::Network),
+    ("git.pull", RiskLevel::Network),
+    ("git.fetch", RiskLevel::Network),
+    ("git.revert", RiskLevel::Destructive),
+    // AI
+    ("ai.testConnection", RiskLevel::Network),
+    ("ai.analyzeSchema", RiskLevel::Network),
+    ("ai.explainQuery", RiskLevel::Network),
+    ("ai.recommendChart", RiskLevel::Network),
+    ("ai.getHistory", RiskLevel::Read),
+    ("ai.getHistoryById", RiskLevel::Read),
+    ("ai.deleteHistory", RiskLevel::Write),
+    ("ai.clearHistory", RiskLevel::Destructive),
+];
+
+/// Methods that manage connections and projects globally.
+/// Secondary windows may use a connection but not create or remove one.
+const MAIN_WINDOW_ONLY: &[&str] = &[
+    "db.add",
+    "db.delete",
+    "project.create",
+    "project.delete",
+    "project.import",
+    "project.deleteWithConnection",
+    "project.linkDatabase",
+    "project.unlinkFromConnection",
+    "project.relinkToConnection",
+];
+
+/// Look up the risk level of a method, or `None` if it is unknown
+pub fn risk_level(method: &str) -> Option<RiskLevel> {
+    METHODS
+        .iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, level)| *level)
+}
+
+/// Windows a method may be sent from, or `None` if any window may send it
+pub fn allowed_windows(method: &str) -> Option<&'static [&'static str]> {
+    if MAIN_WINDOW_ONLY.contains(&method) {
+        Some(&[MAIN_WINDOW])
+    } else {
+        None
+    }
+}
+
+/// Check a parsed request against the policy for the window that sent it
+pub fn check(request: &RpcRequest, window_label: &str) -> Result<RiskLevel, String> {
+    let level = risk_level(&request.method)
+        .ok_or_else(|| format!("method '{}' is not allowed", request.method))?;
+
+    if let Some(windows) = allowed_windows(&request.method) {
+        if !windows.contains(&window_label) {
+            return Err(format!(
+                "method '{}' is not allowed from window '{}'",
+                request.method, window_label
+            ));
+        }
+    }
+
+    Ok(level)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodPolicy {
+    pub method: &'static str,
+    pub risk: RiskLevel,
+    pub windows: Option<&'static [&'static str]>,
+}
+
+/// Snapshot of the whole policy table
+pub fn describe() -> Vec<MethodPolicy> {
+    METHODS
+        .iter()
+        .map(|(method, risk)| MethodPolicy {
+            method,
+            risk: *risk,
+            windows: allowed_windows(method),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn request(method: &str) -> RpcRequest {
+        RpcRequest {
+            id: super::super::protocol::RequestId::Number(1),
+            method: method.to_string(),
+            params: Value::Null,
+        }
+    }
+
+    /// Methods the bridge registers, read from its handler table, plus its built-in `health.ping`
+    fn bridge_methods() -> Vec<String> {
+        let source = include_str!("../../../bridge/src/jsonRpcHandler.ts");
+        let mut methods: Vec<String> = source
+            .split("rpcRegister(rpc, \"")
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .map(str::to_string)
+            .collect();
+        methods.push("health.ping".into());
+        methods.sort();
+        methods
+    }
+
+    #[test]
+    fn the_allowlist_matches_the_bridge() {
+        let mut allowed: Vec<String> = METHODS.iter().map(|(m, _)| m.to_string()).collect();
+        allowed.sort();
+        assert_eq!(allowed, bridge_methods());
+    }
+
+    #[test]
+    fn main_window_only_methods_are_on_the_allowlist() {
+        for method in MAIN_WINDOW_ONLY {
+            assert!(risk_level(method).is_some(), "{} is not allowed at all", method);
+        }
+    }
+
+    #[test]
+    fn methods_are_classified_by_risk() {
+        assert_eq!(risk_level("db.list"), Some(RiskLevel::Read));
+        assert_eq!(risk_level("query.run"), Some(RiskLevel::Write));
+        assert_eq!(risk_level("query.dropTable"), Some(RiskLevel::Destructive));
+        assert_eq!(risk_level("git.push"), Some(RiskLevel::Network));
+        assert_eq!(risk_level("ai.explainQuery"), Some(RiskLevel::Network));
+        assert_eq!(risk_level("shell.exec"), None);
+    }
+
+    #[test]
+    fn unknown_methods_are_rejected() {
+        let error = check(&request("fs.readFile"), MAIN_WINDOW).unwrap_err();
+        assert!(error.contains("not allowed"), "{}", error);
+    }
+
+    #[test]
+    fn connection_management_is_limited_to_the_main_window() {
+        assert_eq!(check(&request("db.add"), MAIN_WINDOW), Ok(RiskLevel::Write));
+        assert!(check(&request("db.add"), "db-1").is_err());
+        assert!(check(&request("project.delete"), "db-1").is_err());
+        assert_eq!(check(&request("query.run"), "db-1"), Ok(RiskLevel::Write));
+    }
+}
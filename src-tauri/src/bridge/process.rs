@@ -1,32 +1,188 @@
-use std::io::{BufRead, BufReader};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
-#[cfg(not(debug_assertions))]
-use tauri::path::BaseDirectory;
 use tauri::{AppHandle, Emitter, Manager};
 
+use super::history::{BridgeHistory, SpawnReport};
+use super::launch_config::{split_command_line, LaunchConfig};
+#[cfg(not(debug_assertions))]
+use super::integrity::{self, Artifact};
+use super::launcher::{BridgeLauncher, Launcher, ProcessLauncher};
+use super::node;
+use super::pending::PendingRequests;
+use super::recording::BridgeRecorder;
+use super::replay::ReplayBridge;
+use super::protocol::{RequestId, RpcRequest};
+use super::{redact, router};
+use crate::diagnostics::{self, LogSource};
+
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
+/// Environment variable telling the bridge where `better_sqlite3.node` is
+const SQLITE_BINDING_ENV: &str = "RELWAVE_SQLITE_NATIVE_BINDING";
+
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// A bridge that runs inside the shell instead of as a child process, such as the replay bridge
+pub trait InProcessBridge: Send {
+    /// Short name for status messages
+    fn name(&self) -> &str;
+    /// Hand it a frame, as if written to the bridge stdin
+    fn write_line(&mut self, data: &str) -> std::io::Result<()>;
+    fn is_running(&self) -> bool;
+    fn kill(&mut self);
+    /// Wait for it to finish after `kill`
+    fn wait(&mut self);
+}
+
+/// A running bridge: a spawned process, or one running inside the shell
+pub enum BridgeChild {
+    Process(Child),
+    InProcess(Box<dyn InProcessBridge>),
+}
+
+impl BridgeChild {
+    /// Process id; a bridge inside the shell has none
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            Self::Process(child) => Some(child.id()),
+            Self::InProcess(_) => None,
+        }
+    }
+
+    /// How the bridge exited, or `None` while it is running
+    pub fn try_wait(&mut self) -> std::io::Result<Option<String>> {
+        match self {
+            Self::Process(child) => Ok(child.try_wait()?.map(|status| format!("{:?}", status))),
+            Self::InProcess(bridge) => Ok((!bridge.is_running()).then(|| format!("{} stopped", bridge.name()))),
+        }
+    }
+
+    pub fn kill(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Process(child) => child.kill(),
+            Self::InProcess(bridge) => {
+                bridge.kill();
+                Ok(())
+            }
+        }
+    }
+
+    /// Wait for the bridge to exit after `kill`
+    pub fn wait(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Process(child) => child.wait().map(|_| ()),
+            Self::InProcess(bridge) => {
+                bridge.wait();
+                Ok(())
+            }
+        }
+    }
+
+    pub fn write_line(&mut self, data: &str) -> std::io::Result<()> {
+        match self {
+            Self::Process(child) => {
+                let stdin = child
+                    .stdin
+                    .as_mut()
+                    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "bridge stdin missing"))?;
+                stdin
+                    .write_all(data.as_bytes())
+                    .and_then(|_| stdin.write_all(b"\n"))
+                    .and_then(|_| stdin.flush())
+            }
+            Self::InProcess(bridge) => bridge.write_line(data),
+        }
+    }
+}
+
 /// Wrapper for the bridge child process
-pub struct BridgeProcess(pub Arc<Mutex<Option<Child>>>);
+pub struct BridgeProcess(pub Arc<Mutex<Option<BridgeChild>>>);
 
 impl BridgeProcess {
-    pub fn new(child: Option<Child>) -> Self {
+    pub fn new(child: Option<BridgeChild>) -> Self {
         Self(Arc::new(Mutex::new(child)))
     }
+
+    /// Track `request` and write its frame (`data`) to the bridge stdin
+    pub fn send(
+        &self,
+        pending: &PendingRequests,
+        request: RpcRequest,
+        client_id: RequestId,
+        data: &str,
+        window: &str,
+        reply: Option<Sender<Value>>,
+    ) -> Result<(), String> {
+        let mut guard = self.0.lock().unwrap();
+
+        let child = guard.as_mut().ok_or("bridge not available")?;
+
+        // Check if the process is still alive
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                return Err(format!("bridge process exited with status: {}", status));
+            }
+            Ok(None) => {} // Process is still running
+            Err(e) => {
+                return Err(format!("failed to check bridge status: {}", e));
+            }
+        }
+
+        // Track before writing: a fast response may be read before write_all returns
+        let id = request.id.clone();
+        pending.track(request, client_id, window, reply);
+
+        if let Err(e) = child.write_line(data) {
+            pending.complete(&id);
+            return Err(e.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Kill the bridge, if any, and wait for it to exit
+    pub fn shutdown(&self) -> Result<(), String> {
+        let mut guard = self.0.lock().unwrap();
+        if let Some(mut child) = guard.take() {
+            child.kill().map_err(|e| format!("failed to kill bridge: {}", e))?;
+            // wait() is essential — it ensures the OS fully closes all file handles
+            // before we return, giving the installer a clean shot at the files.
+            child.wait().map_err(|e| format!("failed to wait for bridge exit: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Kill the running bridge, if any, and put the one `launch` starts in its place
+    pub fn replace(&self, launch: impl FnOnce() -> Result<BridgeChild, String>) -> Result<(), String> {
+        let mut guard = self.0.lock().unwrap();
+        if let Some(mut child) = guard.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        *guard = Some(launch()?);
+        Ok(())
+    }
 }
 
-/// Try to spawn a program with args and return Child or an error message
-fn try_spawn(program: &str, args: &[&str]) -> Result<Child, String> {
+/// Try to spawn a program with args and return Child or an error message.
+/// `configure` can set the environment or working directory first.
+fn spawn_command(
+    report: &mut SpawnReport,
+    program: &str,
+    args: &[&str],
+    configure: impl FnOnce(&mut Command),
+) -> Result<Child, String> {
     let mut cmd = Command::new(program);
     for a in args {
         cmd.arg(a);
     }
+    configure(&mut cmd);
     cmd.stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -34,28 +190,85 @@ fn try_spawn(program: &str, args: &[&str]) -> Result<Child, String> {
     #[cfg(target_os = "windows")]
     cmd.creation_flags(CREATE_NO_WINDOW);
 
-    cmd.spawn()
-        .map_err(|e| format!("failed to spawn '{}': {}", program, e))
+    let result = cmd
+        .spawn()
+        .map_err(|e| format!("failed to spawn '{}': {}", program, e));
+    report.record(program, args, result.as_ref().err().cloned());
+    result
+}
+
+fn try_spawn(report: &mut SpawnReport, program: &str, args: &[&str]) -> Result<Child, String> {
+    spawn_command(report, program, args, |_| {})
 }
 
 #[cfg(not(debug_assertions))]
-fn try_spawn_with_env(program: &str, args: &[&str], envs: &[(&str, &str)]) -> Result<Child, String> {
-    let mut cmd = Command::new(program);
-    for a in args {
-        cmd.arg(a);
-    }
-    for (key, value) in envs {
-        cmd.env(key, value);
+fn try_spawn_with_env(
+    report: &mut SpawnReport,
+    program: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> Result<Child, String> {
+    spawn_command(report, program, args, |cmd| {
+        for (key, value) in envs {
+            cmd.env(key, value);
+        }
+    })
+}
+
+/// Start the command `bridge.toml` configures
+fn try_configured(report: &mut SpawnReport, node: &mut NodeResolver, config: &LaunchConfig) -> Option<Child> {
+    let (program, args) = config.program(|| node.program(report))?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if let Some(binding) = &config.sqlite_binding {
+        unverified_binding(report, binding);
     }
-    cmd.stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+    spawn_command(report, &program, &args, |cmd| {
+        if let Some(binding) = &config.sqlite_binding {
+            cmd.env(SQLITE_BINDING_ENV, binding);
+        }
+        cmd.envs(&config.env);
+        if let Some(dir) = &config.working_dir {
+            cmd.current_dir(dir);
+        }
+    })
+    .ok()
+}
 
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(CREATE_NO_WINDOW);
+/// Flag a `bridge.toml` native binding, which is used without checking it against the build's manifest
+fn unverified_binding(report: &mut SpawnReport, binding: &Path) {
+    report.warnings.push(format!(
+        "{} is configured in bridge.toml and was not verified against the build's integrity manifest",
+        binding.display()
+    ));
+}
+
+/// Picks the Node executable for bridge scripts the first time a strategy needs one,
+/// and puts what was considered into the spawn report
+struct NodeResolver<'a> {
+    configured: Option<&'a Path>,
+    resource_dir: Option<&'a Path>,
+    program: Option<String>,
+}
+
+impl<'a> NodeResolver<'a> {
+    fn new(configured: Option<&'a Path>, resource_dir: Option<&'a Path>) -> Self {
+        Self {
+            configured,
+            resource_dir,
+            program: None,
+        }
+    }
 
-    cmd.spawn()
-        .map_err(|e| format!("failed to spawn '{}': {}", program, e))
+    fn program(&mut self, report: &mut SpawnReport) -> String {
+        self.program
+            .get_or_insert_with(|| {
+                let selection = node::discover(self.configured, self.resource_dir);
+                let program = selection.program();
+                report.node = Some(selection);
+                program
+            })
+            .clone()
+    }
 }
 
 /// Get the resource directory path for bundled resources
@@ -71,17 +284,22 @@ fn get_exe_dir() -> Option<PathBuf> {
 }
 
 #[cfg(not(debug_assertions))]
-fn find_sqlite_native_binding(app_handle: &AppHandle, base_dir: &Path) -> Option<PathBuf> {
-    if let Ok(resolved) = app_handle
-        .path()
-        .resolve("better_sqlite3.node", BaseDirectory::Resource)
-    {
-        if resolved.exists() {
-            return Some(resolved);
-        }
+/// The `better_sqlite3.node` to hand to a bundled bridge: the one `bridge.toml` names (flagged as unverified),
+/// or the first one found that matches the build's hash
+fn find_sqlite_native_binding(
+    report: &mut SpawnReport,
+    config: &LaunchConfig,
+    resource_dir: Option<&Path>,
+    base_dir: &Path,
+) -> Option<PathBuf> {
+    if let Some(binding) = &config.sqlite_binding {
+        unverified_binding(report, binding);
+        return Some(binding.clone());
     }
+    let resolved = resource_dir.map(|d| d.join("better_sqlite3.node"));
 
-    let mut candidates = vec![
+    let mut candidates: Vec<PathBuf> = resolved.into_iter().collect();
+    candidates.extend([
         base_dir.join("better_sqlite3.node"),
         base_dir.join("bridge").join("better_sqlite3.node"),
         base_dir.join("resources").join("better_sqlite3.node"),
@@ -89,7 +307,7 @@ fn find_sqlite_native_binding(app_handle: &AppHandle, base_dir: &Path) -> Option
         base_dir.join("_up_").join("better_sqlite3.node"),
         base_dir.join("_up_").join("bridge").join("better_sqlite3.node"),
         base_dir.join("_up_").join("resources").join("better_sqlite3.node"),
-    ];
+    ]);
 
     if let Some(parent) = base_dir.parent() {
         candidates.extend([
@@ -101,23 +319,45 @@ fn find_sqlite_native_binding(app_handle: &AppHandle, base_dir: &Path) -> Option
         ]);
     }
 
-    candidates.into_iter().find(|p| p.exists())
+    candidates
+        .into_iter()
+        .find(|p| p.exists() && integrity::verify(report, p, Artifact::SqliteBinding))
 }
 
-/// Resolve candidate bridge paths and try spawn strategies in order
-fn spawn_bridge_process(app_handle: &AppHandle) -> Result<Child, String> {
+/// Resolve candidate bridge paths and try spawn strategies in order.
+/// Needs no app handle, so the headless CLI can start the bridge the same way as the desktop app.
+/// A `bridge.toml` command is tried after `BRIDGE_DEV_CMD`. Its `sqlite_binding` is also used for the bundled binaries.
+/// Scripts run with the Node `node::discover` picks, preferring the one `bridge.toml` names.
+/// Release builds check bundled binaries against the build's manifest; one that fails is skipped with a warning
+/// in the report and the next candidate is tried.
+pub fn spawn_bridge_process(
+    resource_dir: Option<&Path>,
+    config: Option<&LaunchConfig>,
+    report: &mut SpawnReport,
+) -> Result<Child, String> {
     // 1) BRIDGE_DEV_CMD override (for development/testing)
     if let Ok(cmdline) = std::env::var("BRIDGE_DEV_CMD") {
-        let parts: Vec<&str> = cmdline.split_whitespace().collect();
-        if !parts.is_empty() {
-            let prog = parts[0];
-            let args: Vec<&str> = parts.iter().skip(1).copied().collect();
-            if let Ok(c) = try_spawn(prog, &args) {
-                return Ok(c);
+        match split_command_line(&cmdline) {
+            Ok(parts) => {
+                if let Some((prog, args)) = parts.split_first() {
+                    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                    if let Ok(c) = try_spawn(report, prog, &args) {
+                        return Ok(c);
+                    }
+                }
             }
+            Err(e) => report.record("BRIDGE_DEV_CMD", &[&cmdline], Some(e)),
         }
     }
 
+    // 2) bridge.toml
+    let default_config = LaunchConfig::default();
+    let config = config.unwrap_or(&default_config);
+    let node = &mut NodeResolver::new(config.node.as_deref(), resource_dir);
+    if let Some(child) = try_configured(report, node, config) {
+        return Ok(child);
+    }
+
     // --- Development: prefer Node scripts over pkg binaries ---
     // In debug builds, Tauri copies the pkg-compiled bridge binary from
     // externalBin into target/debug/. That binary cannot load native modules
@@ -125,31 +365,31 @@ fn spawn_bridge_process(app_handle: &AppHandle) -> Result<Child, String> {
     #[cfg(debug_assertions)]
     {
         // Try local development path ./bridge/dist
-        if let Some(child) = try_local_dev_path() {
+        if let Some(child) = try_local_dev_path(report, node) {
             return Ok(child);
         }
 
         // Check ../../bridge/dist (two levels up from target/debug)
-        if let Some(child) = try_parent_dev_path() {
+        if let Some(child) = try_parent_dev_path(report, node) {
             return Ok(child);
         }
 
         // Try bundled scripts in resource directory
-        if let Some(resource_path) = get_resource_path(app_handle) {
-            if let Some(child) = try_bundled_scripts(&resource_path) {
+        if let Some(resource_path) = resource_dir {
+            if let Some(child) = try_bundled_scripts(report, node, resource_path) {
                 return Ok(child);
             }
         }
 
         // Try scripts in exe directory
         if let Some(exe_dir) = get_exe_dir() {
-            if let Some(child) = try_exe_dir_scripts(&exe_dir) {
+            if let Some(child) = try_exe_dir_scripts(report, node, &exe_dir) {
                 return Ok(child);
             }
         }
 
         // Try pnpm dev as last resort
-        if let Some(child) = try_pnpm_dev() {
+        if let Some(child) = try_pnpm_dev(report) {
             return Ok(child);
         }
     }
@@ -158,30 +398,30 @@ fn spawn_bridge_process(app_handle: &AppHandle) -> Result<Child, String> {
     #[cfg(not(debug_assertions))]
     {
         // Try bundled bridge executable (compiled with pkg)
-        if let Some(resource_path) = get_resource_path(app_handle) {
-            if let Some(child) = try_bundled_exe(app_handle, &resource_path) {
+        if let Some(resource_path) = resource_dir {
+            if let Some(child) = try_bundled_exe(report, config, resource_dir, resource_path) {
                 return Ok(child);
             }
-            if let Some(child) = try_bundled_scripts(&resource_path) {
+            if let Some(child) = try_bundled_scripts(report, node, resource_path) {
                 return Ok(child);
             }
         }
 
         // Try exe directory (works for deb/appimage on Linux)
         if let Some(exe_dir) = get_exe_dir() {
-            if let Some(child) = try_exe_dir_binary(app_handle, &exe_dir) {
+            if let Some(child) = try_exe_dir_binary(report, config, resource_dir, &exe_dir) {
                 return Ok(child);
             }
-            if let Some(child) = try_exe_dir_scripts(&exe_dir) {
+            if let Some(child) = try_exe_dir_scripts(report, node, &exe_dir) {
                 return Ok(child);
             }
         }
 
         // Fallback to local dev paths
-        if let Some(child) = try_local_dev_path() {
+        if let Some(child) = try_local_dev_path(report, node) {
             return Ok(child);
         }
-        if let Some(child) = try_parent_dev_path() {
+        if let Some(child) = try_parent_dev_path(report, node) {
             return Ok(child);
         }
     }
@@ -190,13 +430,40 @@ fn spawn_bridge_process(app_handle: &AppHandle) -> Result<Child, String> {
         "All bridge spawn attempts failed. \
         For production: ensure bridge files are bundled and Node.js is installed. \
         For development: ensure bridge/dist/index.cjs or index.js exists. \
-        Try setting BRIDGE_DEV_CMD environment variable."
+        Try setting BRIDGE_DEV_CMD environment variable or configuring bridge.toml."
             .into(),
     )
 }
 
+/// Spawn a bundled bridge binary with the native binding it should load, unless it fails the integrity check
+#[cfg(not(debug_assertions))]
+fn spawn_bundled(
+    report: &mut SpawnReport,
+    config: &LaunchConfig,
+    resource_dir: Option<&Path>,
+    exe: &Path,
+    binding_base: &Path,
+) -> Option<Child> {
+    if !integrity::verify(report, exe, Artifact::Bridge) {
+        return None;
+    }
+    let exe_str = exe.to_str()?;
+    let binding_path = find_sqlite_native_binding(report, config, resource_dir, binding_base);
+    let result = if let Some(binding) = binding_path.as_ref().and_then(|p| p.to_str()) {
+        try_spawn_with_env(report, exe_str, &[], &[(SQLITE_BINDING_ENV, binding)])
+    } else {
+        try_spawn(report, exe_str, &[])
+    };
+    result.ok()
+}
+
 #[cfg(not(debug_assertions))]
-fn try_bundled_exe(app_handle: &AppHandle, resource_path: &Path) -> Option<Child> {
+fn try_bundled_exe(
+    report: &mut SpawnReport,
+    config: &LaunchConfig,
+    resource_dir: Option<&Path>,
+    resource_path: &Path,
+) -> Option<Child> {
     // Try platform-specific binary names first
     #[cfg(target_os = "windows")]
     let bridge_candidates = vec![
@@ -215,16 +482,8 @@ fn try_bundled_exe(app_handle: &AppHandle, resource_path: &Path) -> Option<Child
 
     for bridge_exe in bridge_candidates {
         if bridge_exe.exists() {
-            if let Some(exe_str) = bridge_exe.to_str() {
-                let binding_path = find_sqlite_native_binding(app_handle, resource_path);
-                let result = if let Some(binding) = binding_path.as_ref().and_then(|p| p.to_str()) {
-                    try_spawn_with_env(exe_str, &[], &[("RELWAVE_SQLITE_NATIVE_BINDING", binding)])
-                } else {
-                    try_spawn(exe_str, &[])
-                };
-                if let Ok(c) = result {
-                    return Some(c);
-                }
+            if let Some(child) = spawn_bundled(report, config, resource_dir, &bridge_exe, resource_path) {
+                return Some(child);
             }
         }
     }
@@ -236,23 +495,13 @@ fn try_bundled_exe(app_handle: &AppHandle, resource_path: &Path) -> Option<Child
     let bridge_exe_up = resource_path.join("_up_").join("bridge");
 
     if bridge_exe_up.exists() {
-        if let Some(exe_str) = bridge_exe_up.to_str() {
-            let binding_path = find_sqlite_native_binding(app_handle, &resource_path.join("_up_"));
-            let result = if let Some(binding) = binding_path.as_ref().and_then(|p| p.to_str()) {
-                try_spawn_with_env(exe_str, &[], &[("RELWAVE_SQLITE_NATIVE_BINDING", binding)])
-            } else {
-                try_spawn(exe_str, &[])
-            };
-            if let Ok(c) = result {
-                return Some(c);
-            }
-        }
+        return spawn_bundled(report, config, resource_dir, &bridge_exe_up, &resource_path.join("_up_"));
     }
 
     None
 }
 
-fn try_bundled_scripts(resource_path: &Path) -> Option<Child> {
+fn try_bundled_scripts(report: &mut SpawnReport, node: &mut NodeResolver, resource_path: &Path) -> Option<Child> {
     let search_paths = [
         resource_path.join("bridge").join("dist"),
         resource_path.to_path_buf(),
@@ -264,7 +513,8 @@ fn try_bundled_scripts(resource_path: &Path) -> Option<Child> {
             let script = base_path.join(format!("index.{}", ext));
             if script.exists() {
                 if let Some(script_str) = script.to_str() {
-                    if let Ok(c) = try_spawn("node", &[script_str]) {
+                    let node = node.program(report);
+                    if let Ok(c) = try_spawn(report, &node, &[script_str]) {
                         return Some(c);
                     }
                 }
@@ -275,7 +525,7 @@ fn try_bundled_scripts(resource_path: &Path) -> Option<Child> {
     None
 }
 
-fn try_exe_dir_scripts(exe_dir: &Path) -> Option<Child> {
+fn try_exe_dir_scripts(report: &mut SpawnReport, node: &mut NodeResolver, exe_dir: &Path) -> Option<Child> {
     let search_paths = [
         exe_dir.join("bridge").join("dist"),
         exe_dir.join("_up_").join("bridge").join("dist"),
@@ -286,7 +536,8 @@ fn try_exe_dir_scripts(exe_dir: &Path) -> Option<Child> {
             let script = base_path.join(format!("index.{}", ext));
             if script.exists() {
                 if let Some(script_str) = script.to_str() {
-                    if let Ok(c) = try_spawn("node", &[script_str]) {
+                    let node = node.program(report);
+                    if let Ok(c) = try_spawn(report, &node, &[script_str]) {
                         return Some(c);
                     }
                 }
@@ -299,7 +550,12 @@ fn try_exe_dir_scripts(exe_dir: &Path) -> Option<Child> {
 
 /// Try to find and spawn bridge binary in the exe directory (for Linux deb/appimage and Windows)
 #[cfg(not(debug_assertions))]
-fn try_exe_dir_binary(app_handle: &AppHandle, exe_dir: &Path) -> Option<Child> {
+fn try_exe_dir_binary(
+    report: &mut SpawnReport,
+    config: &LaunchConfig,
+    resource_dir: Option<&Path>,
+    exe_dir: &Path,
+) -> Option<Child> {
     // Platform-specific binary names to search for
     #[cfg(target_os = "windows")]
     let binary_names = vec![
@@ -324,21 +580,13 @@ fn try_exe_dir_binary(app_handle: &AppHandle, exe_dir: &Path) -> Option<Child> {
     for name in binary_names {
         let exe_path = exe_dir.join(name);
         if exe_path.exists() {
-            if let Some(exe_str) = exe_path.to_str() {
-                let binding_base = if name.starts_with("_up_") {
-                    exe_dir.join("_up_")
-                } else {
-                    exe_dir.to_path_buf()
-                };
-                let binding_path = find_sqlite_native_binding(app_handle, &binding_base);
-                let result = if let Some(binding) = binding_path.as_ref().and_then(|p| p.to_str()) {
-                    try_spawn_with_env(exe_str, &[], &[("RELWAVE_SQLITE_NATIVE_BINDING", binding)])
-                } else {
-                    try_spawn(exe_str, &[])
-                };
-                if let Ok(c) = result {
-                    return Some(c);
-                }
+            let binding_base = if name.starts_with("_up_") {
+                exe_dir.join("_up_")
+            } else {
+                exe_dir.to_path_buf()
+            };
+            if let Some(child) = spawn_bundled(report, config, resource_dir, &exe_path, &binding_base) {
+                return Some(child);
             }
         }
     }
@@ -346,13 +594,14 @@ fn try_exe_dir_binary(app_handle: &AppHandle, exe_dir: &Path) -> Option<Child> {
     None
 }
 
-fn try_local_dev_path() -> Option<Child> {
+fn try_local_dev_path(report: &mut SpawnReport, node: &mut NodeResolver) -> Option<Child> {
     for ext in &["cjs", "js"] {
         let cand = Path::new("bridge").join("dist").join(format!("index.{}", ext));
         if cand.exists() {
             if let Ok(abs) = cand.canonicalize() {
                 if let Some(abs_str) = abs.to_str() {
-                    if let Ok(c) = try_spawn("node", &[abs_str]) {
+                    let node = node.program(report);
+                    if let Ok(c) = try_spawn(report, &node, &[abs_str]) {
                         return Some(c);
                     }
                 }
@@ -362,7 +611,7 @@ fn try_local_dev_path() -> Option<Child> {
     None
 }
 
-fn try_parent_dev_path() -> Option<Child> {
+fn try_parent_dev_path(report: &mut SpawnReport, node: &mut NodeResolver) -> Option<Child> {
     for ext in &["cjs", "js"] {
         let cand = Path::new("..")
             .join("..")
@@ -373,7 +622,8 @@ fn try_parent_dev_path() -> Option<Child> {
         if cand.exists() {
             if let Ok(abs) = cand.canonicalize() {
                 if let Some(abs_str) = abs.to_str() {
-                    if let Ok(c) = try_spawn("node", &[abs_str]) {
+                    let node = node.program(report);
+                    if let Ok(c) = try_spawn(report, &node, &[abs_str]) {
                         return Some(c);
                     }
                 }
@@ -384,42 +634,232 @@ fn try_parent_dev_path() -> Option<Child> {
 }
 
 #[cfg(debug_assertions)]
-fn try_pnpm_dev() -> Option<Child> {
+fn try_pnpm_dev(report: &mut SpawnReport) -> Option<Child> {
     #[cfg(target_os = "windows")]
     {
-        try_spawn("cmd", &["/C", "pnpm", "--prefix", "..\\bridge", "dev"]).ok()
+        try_spawn(report, "cmd", &["/C", "pnpm", "--prefix", "..\\bridge", "dev"]).ok()
     }
     #[cfg(not(target_os = "windows"))]
     {
-        try_spawn("pnpm", &["--prefix", "../bridge", "dev"]).ok()
+        try_spawn(report, "pnpm", &["--prefix", "../bridge", "dev"]).ok()
+    }
+}
+
+/// Call `on_line` with every line of `reader` until it closes
+pub fn read_lines(reader: impl Read, mut on_line: impl FnMut(String)) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        on_line(line);
     }
 }
 
-/// Spawn the bridge and connect stdout/stderr to Tauri events
-pub fn spawn_bridge(app_handle: AppHandle) -> Result<Child, String> {
-    let mut child = spawn_bridge_process(&app_handle)?;
+/// Start the bridge and connect stdout/stderr to Tauri events.
+/// Lines are passed through the redaction filter before they are emitted.
+/// When a replay has been selected, the recording answers requests instead of the launcher's bridge.
+pub fn spawn_bridge(app_handle: AppHandle) -> Result<BridgeChild, String> {
+    if let Some(path) = app_handle.try_state::<BridgeRecorder>().and_then(|r| r.replay_source()) {
+        return start_replay(&app_handle, &path);
+    }
+
+    let mut report = SpawnReport::new();
+    let result = match app_handle.try_state::<Launcher>() {
+        Some(launcher) => launcher.0.launch(&mut report),
+        None => ProcessLauncher::new(get_resource_path(&app_handle), None).launch(&mut report),
+    };
+    report.pid = result.as_ref().ok().and_then(|b| b.child.id());
+    report.error = result.as_ref().err().cloned();
+    if let Some(selection) = &report.node {
+        diagnostics::shell_log(&app_handle, selection.summary());
+    }
+    for warning in &report.warnings {
+        diagnostics::shell_log(&app_handle, warning);
+    }
+    if let Some(history) = app_handle.try_state::<BridgeHistory>() {
+        history.record_status(if result.is_ok() { "running" } else { "spawn_failed" });
+        history.set_spawn_report(report);
+    }
+    let launched = result?;
 
     // Forward stdout to "bridge-stdout" events
-    if let Some(stdout) = child.stdout.take() {
+    if let Some(stdout) = launched.stdout {
         let ah = app_handle.clone();
         std::thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines().flatten() {
-                let _ = ah.emit("bridge-stdout", line);
-            }
+            read_lines(stdout, |line| {
+                let line = redact::filter_stdout(&ah, line);
+                router::route_stdout(&ah, line);
+            });
         });
     }
 
     // Forward stderr to "bridge-stderr" events
-    if let Some(stderr) = child.stderr.take() {
+    if let Some(stderr) = launched.stderr {
         let ah = app_handle.clone();
         std::thread::spawn(move || {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines().flatten() {
+            read_lines(stderr, |line| {
+                let line = redact::filter_stderr(&ah, line);
+                diagnostics::record_log(&ah, LogSource::Bridge, &line);
                 let _ = ah.emit("bridge-stderr", line);
-            }
+            });
+        });
+    }
+
+    Ok(launched.child)
+}
+
+/// Start the replay bridge on the recording at `path`, reported like a spawn
+fn start_replay(app_handle: &AppHandle, path: &Path) -> Result<BridgeChild, String> {
+    let mut report = SpawnReport::new();
+    let result = ReplayBridge::start(app_handle, path);
+    report.record("replay", &[&path.display().to_string()], result.as_ref().err().cloned());
+    report.error = result.as_ref().err().cloned();
+    if let Some(history) = app_handle.try_state::<BridgeHistory>() {
+        history.record_status(if result.is_ok() { "running" } else { "spawn_failed" });
+        history.set_spawn_report(report);
+    }
+    result.map(|replay| BridgeChild::InProcess(Box::new(replay)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::fake::{FakeLauncher, Script, Step};
+    use crate::bridge::protocol::parse_request;
+    use serde_json::json;
+    use std::sync::mpsc::Receiver;
+    use std::time::{Duration, Instant};
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn ping_script() -> Script {
+        Script::new().on("ping", |_| vec![Step::Respond(json!({ "ok": true }))])
+    }
+
+    fn start(launcher: &FakeLauncher) -> (BridgeProcess, Receiver<String>) {
+        let (child, stdout) = launcher.start().unwrap();
+        (BridgeProcess::new(Some(child)), stdout)
+    }
+
+    fn send(process: &BridgeProcess, pending: &PendingRequests, data: &str) -> Result<(), String> {
+        let request = parse_request(data).unwrap();
+        let client_id = request.id.clone();
+        process.send(pending, request, client_id, data, "main", None)
+    }
+
+    fn next_frame(stdout: &Receiver<String>) -> Value {
+        serde_json::from_str(&stdout.recv_timeout(WAIT).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn send_tracks_the_request_until_answered() {
+        let launcher = FakeLauncher::new(ping_script());
+        let (process, stdout) = start(&launcher);
+        let pending = PendingRequests::new();
+
+        send(&process, &pending, r#"{"id":1,"method":"ping"}"#).unwrap();
+        let frame = next_frame(&stdout);
+        assert_eq!(frame["id"], 1);
+        assert_eq!(frame["result"]["ok"], true);
+        assert!(pending.complete(&RequestId::Number(1)).is_some());
+        assert_eq!(launcher.received()[0]["method"], "ping");
+    }
+
+    #[test]
+    fn send_fails_after_the_bridge_crashes() {
+        let script = ping_script().on("crash", |_| vec![Step::Crash]);
+        let launcher = FakeLauncher::new(script);
+        let (process, stdout) = start(&launcher);
+        let pending = PendingRequests::new();
+
+        send(&process, &pending, r#"{"id":1,"method":"crash"}"#).unwrap();
+        assert!(stdout.recv_timeout(WAIT).is_err(), "stdout should close when the bridge dies");
+
+        let error = send(&process, &pending, r#"{"id":2,"method":"ping"}"#).unwrap_err();
+        assert!(error.contains("exited"), "{}", error);
+        assert!(!pending.contains(&RequestId::Number(2)));
+    }
+
+    #[test]
+    fn replace_starts_a_new_bridge() {
+        let script = ping_script().on("crash", |_| vec![Step::Crash]);
+        let launcher = FakeLauncher::new(script);
+        let (process, _stdout) = start(&launcher);
+        let pending = PendingRequests::new();
+        send(&process, &pending, r#"{"id":1,"method":"crash"}"#).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        process
+            .replace(|| {
+                let (child, stdout) = launcher.start()?;
+                tx.send(stdout).unwrap();
+                Ok(child)
+            })
+            .unwrap();
+        let stdout = rx.recv().unwrap();
+
+        send(&process, &pending, r#"{"id":2,"method":"ping"}"#).unwrap();
+        assert_eq!(next_frame(&stdout)["id"], 2);
+        assert_eq!(launcher.launches(), 2);
+    }
+
+    #[test]
+    fn failed_launches_are_reported() {
+        let launcher = FakeLauncher::new(ping_script()).failing(1);
+        let mut report = SpawnReport::new();
+
+        assert!(launcher.launch(&mut report).is_err());
+        assert!(launcher.launch(&mut report).is_ok());
+        assert_eq!(report.attempts.len(), 2);
+        assert!(report.attempts[0].error.is_some());
+        assert!(report.attempts[1].error.is_none());
+    }
+
+    #[test]
+    fn shutdown_interrupts_a_slow_bridge() {
+        let script = Script::new().on("slow", |_| vec![Step::Sleep(Duration::from_secs(60)), Step::Respond(json!(null))]);
+        let launcher = FakeLauncher::new(script);
+        let (process, stdout) = start(&launcher);
+        let pending = PendingRequests::new();
+        send(&process, &pending, r#"{"id":1,"method":"slow"}"#).unwrap();
+
+        let started = Instant::now();
+        process.shutdown().unwrap();
+        assert!(started.elapsed() < WAIT);
+        assert!(stdout.recv_timeout(WAIT).is_err(), "stdout should close on shutdown");
+        assert!(send(&process, &pending, r#"{"id":2,"method":"ping"}"#).is_err());
+    }
+
+    #[test]
+    fn garbage_and_slow_writes_keep_lines_intact() {
+        let script = Script::new().on("noisy", |_| {
+            vec![
+                Step::Garbage("Debugger attached."),
+                Step::Notify {
+                    method: "query.progress",
+                    params: json!({ "sessionId": "s1", "rowsSoFar": 10 }),
+                },
+                Step::RespondSlowly(json!({ "rows": [1, 2, 3] }), Duration::from_millis(20)),
+            ]
         });
+        let launcher = FakeLauncher::new(script);
+        let (process, stdout) = start(&launcher);
+        let pending = PendingRequests::new();
+        send(&process, &pending, r#"{"id":"a","method":"noisy"}"#).unwrap();
+
+        assert_eq!(stdout.recv_timeout(WAIT).unwrap(), "Debugger attached.");
+        assert_eq!(next_frame(&stdout)["method"], "query.progress");
+        let response = next_frame(&stdout);
+        assert_eq!(response["id"], "a");
+        assert_eq!(response["result"]["rows"], json!([1, 2, 3]));
     }
 
-    Ok(child)
+    #[test]
+    fn unknown_methods_are_answered_with_an_error() {
+        let launcher = FakeLauncher::new(Script::new());
+        let (process, stdout) = start(&launcher);
+        let pending = PendingRequests::new();
+        send(&process, &pending, r#"{"id":7,"method":"nope"}"#).unwrap();
+
+        let frame = next_frame(&stdout);
+        assert_eq!(frame["id"], 7);
+        assert_eq!(frame["error"]["code"], "UNKNOWN_METHOD");
+    }
 }
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// Value of the `jsonrpc` member of every frame the shell delivers
+pub const JSONRPC_VERSION: &str = "2.0";
+
+// JSON-RPC 2.0 error codes. The bridge's own string codes are mapped onto these and kept in `error.data.code`.
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// Any other bridge error (`IO_ERROR`, `GIT_ERROR`, ...)
+pub const SERVER_ERROR: i64 = -32000;
+pub const REQUEST_TIMEOUT: i64 = -32001;
+/// Rejected by the method policy or a connection's safety mode
+pub const REQUEST_DENIED: i64 = -32002;
+pub const NOT_FOUND: i64 = -32003;
+/// Same code as the Language Server Protocol uses
+pub const REQUEST_CANCELLED: i64 = -32800;
+
+/// JSON-RPC request id as sent by the webview (numbers or strings)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(i64),
+    String(String),
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Number(n) => write!(f, "{}", n),
+            RequestId::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// An outgoing request frame, parsed from the line the webview hands to `bridge_write`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub id: RequestId,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub params: Value,
+}
+
+impl RpcRequest {
+    /// String parameter lookup, e.g. `dbId` or `sql`
+    pub fn param_str(&self, key: &str) -> Option<&str> {
+        self.params.get(key).and_then(Value::as_str)
+    }
+}
+
+/// Parse and validate a single outgoing request line.
+/// Notifications (frames without an id) are refused: every webview call is tracked, timed out and audited by its id,
+/// and the bridge ignores notifications anyway.
+pub fn parse_request(data: &str) -> Result<RpcRequest, String> {
+    let value: Value =
+        serde_json::from_str(data).map_err(|e| format!("malformed bridge frame: {}", e))?;
+
+    let obj = value
+        .as_object()
+        .ok_or("malformed bridge frame: expected a JSON object")?;
+    if !obj.get("method").is_some_and(Value::is_string) {
+        return Err("malformed bridge frame: missing method".into());
+    }
+    match obj.get("id") {
+        Some(Value::Number(n)) if n.is_i64() => {}
+        Some(Value::String(_)) => {}
+        None => return Err("malformed bridge frame: notifications are not accepted, the request needs an id".into()),
+        _ => return Err("malformed bridge frame: id must be an integer or string".into()),
+    }
+    match obj.get("params") {
+        None | Some(Value::Null) | Some(Value::Object(_)) | Some(Value::Array(_)) => {}
+        Some(_) => return Err("malformed bridge frame: params must be an object or array".into()),
+    }
+
+    serde_json::from_value(value).map_err(|e| format!("malformed bridge frame: {}", e))
+}
+
+/// Numeric JSON-RPC code for a bridge or shell error code
+pub fn error_code(code: &str) -> i64 {
+    match code {
+        "PARSE_ERROR" => PARSE_ERROR,
+        "INVALID_REQUEST" => INVALID_REQUEST,
+        "UNKNOWN_METHOD" => METHOD_NOT_FOUND,
+        "BAD_REQUEST" => INVALID_PARAMS,
+        "INTERNAL_ERROR" | "HANDLER_ERROR" => INTERNAL_ERROR,
+        "TIMEOUT" => REQUEST_TIMEOUT,
+        "DENIED" => REQUEST_DENIED,
+        "NOT_FOUND" => NOT_FOUND,
+        "CANCELLED" => REQUEST_CANCELLED,
+        _ => SERVER_ERROR,
+    }
+}
+
+/// A JSON-RPC 2.0 error object; `name` is the bridge-style code kept in `data.code`
+pub fn error_object(name: &str, message: impl Into<String>) -> Value {
+    json!({
+        "code": error_code(name),
+        "message": message.into(),
+        "data": { "code": name },
+    })
+}
+
+/// Rewrite a bridge error as a JSON-RPC 2.0 error object: numeric `code`, string `message`,
+/// and the bridge's string code and any other members (such as `details`) under `data`
+fn normalize_error(error: &Value) -> Value {
+    let Some(obj) = error.as_object() else {
+        return json!({ "code": INTERNAL_ERROR, "message": error.as_str().map_or_else(|| error.to_string(), str::to_string) });
+    };
+    // Already JSON-RPC 2.0
+    if obj.get("code").is_some_and(Value::is_i64) && obj.get("message").is_some_and(Value::is_string) {
+        return error.clone();
+    }
+
+    let name = obj.get("code").and_then(Value::as_str);
+    let message = obj
+        .get("message")
+        .and_then(Value::as_str)
+        .or(name)
+        .unwrap_or("Unknown error")
+        .to_string();
+    let mut data = Map::new();
+    if let Some(name) = name {
+        data.insert("code".into(), json!(name));
+    }
+    for (key, value) in obj {
+        match key.as_str() {
+            "code" | "message" => {}
+            "data" => match value {
+                Value::Object(extra) => data.extend(extra.clone()),
+                other => {
+                    data.insert("data".into(), other.clone());
+                }
+            },
+            _ => {
+                data.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    let mut normalized = json!({
+        "code": name.map_or(SERVER_ERROR, error_code),
+        "message": message,
+    });
+    if !data.is_empty() {
+        normalized["data"] = Value::Object(data);
+    }
+    normalized
+}
+
+/// Make a bridge frame JSON-RPC 2.0: add `jsonrpc`, normalise `error`, and give responses without either member a null `result`
+pub fn normalize_frame(frame: &mut Value) {
+    let Some(obj) = frame.as_object_mut() else {
+        return;
+    };
+    obj.insert("jsonrpc".into(), json!(JSONRPC_VERSION));
+    if let Some(error) = obj.get("error") {
+        let error = normalize_error(error);
+        obj.insert("error".into(), error);
+        obj.remove("result");
+    } else if obj.contains_key("id") && !obj.contains_key("method") && !obj.contains_key("result") {
+        obj.insert("result".into(), Value::Null);
+    }
+}
+
+/// A JSON-RPC 2.0 error response
+pub fn error_response(id: Value, error: Value) -> Value {
+    json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "error": error })
+}
+
+/// Split a batch frame into its members, or `None` if `data` is not a batch
+pub fn parse_batch(data: &str) -> Option<Result<Vec<Value>, String>> {
+    if !data.trim_start().starts_with('[') {
+        return None;
+    }
+    Some(match serde_json::from_str::<Vec<Value>>(data) {
+        Ok(items) if items.is_empty() => Err("malformed bridge frame: empty batch".into()),
+        Ok(items) => Ok(items),
+        Err(e) => Err(format!("malformed bridge frame: {}", e)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_need_a_method_and_an_id() {
+        let request = parse_request(r#"{"id":"a","method":"db.get","params":{"dbId":"x"}}"#).unwrap();
+        assert_eq!(request.id, RequestId::String("a".into()));
+        assert_eq!(request.param_str("dbId"), Some("x"));
+        assert_eq!(parse_request(r#"{"id":7,"method":"db.list"}"#).unwrap().params, Value::Null);
+
+        for (frame, problem) in [
+            ("not json", "malformed"),
+            ("[1]", "expected a JSON object"),
+            (r#"{"id":1}"#, "missing method"),
+            (r#"{"method":"db.list"}"#, "notifications are not accepted"),
+            (r#"{"id":1.5,"method":"db.list"}"#, "integer or string"),
+            (r#"{"id":null,"method":"db.list"}"#, "integer or string"),
+            (r#"{"id":1,"method":"db.list","params":"x"}"#, "object or array"),
+        ] {
+            let error = parse_request(frame).unwrap_err();
+            assert!(error.contains(problem), "{}: {}", frame, error);
+        }
+    }
+
+    #[test]
+    fn batches_are_arrays_with_members() {
+        assert!(parse_batch(r#"{"id":1}"#).is_none());
+        assert_eq!(parse_batch(r#" [{"id":1},{"id":2}]"#).unwrap().unwrap().len(), 2);
+        assert!(parse_batch("[]").unwrap().unwrap_err().contains("empty batch"));
+        assert!(parse_batch("[{").unwrap().is_err());
+    }
+
+    #[test]
+    fn bridge_errors_become_json_rpc_errors() {
+        let error = normalize_error(&json!({ "code": "NOT_FOUND", "message": "No such database", "details": { "id": "x" } }));
+        assert_eq!(
+            error,
+            json!({ "code": NOT_FOUND, "message": "No such database", "data": { "code": "NOT_FOUND", "details": { "id": "x" } } })
+        );
+        assert_eq!(
+            normalize_error(&json!({ "code": "GIT_ERROR", "data": { "exit": 1 } })),
+            json!({ "code": SERVER_ERROR, "message": "GIT_ERROR", "data": { "code": "GIT_ERROR", "exit": 1 } })
+        );
+        assert_eq!(normalize_error(&json!("boom")), json!({ "code": INTERNAL_ERROR, "message": "boom" }));
+        assert_eq!(
+            normalize_error(&json!({ "data": 3 })),
+            json!({ "code": SERVER_ERROR, "message": "Unknown error", "data": { "data": 3 } })
+        );
+        let standard = json!({ "code": INVALID_PARAMS, "message": "bad" });
+        assert_eq!(normalize_error(&standard), standard);
+    }
+
+    #[test]
+    fn frames_are_made_json_rpc() {
+        let mut response = json!({ "id": 1, "result": 2, "error": { "code": "TIMEOUT", "message": "late" } });
+        normalize_frame(&mut response);
+        assert_eq!(response["jsonrpc"], json!(JSONRPC_VERSION));
+        assert_eq!(response["error"]["code"], json!(REQUEST_TIMEOUT));
+        assert!(response.get("result").is_none());
+
+        let mut empty = json!({ "id": 1 });
+        normalize_frame(&mut empty);
+        assert_eq!(empty, json!({ "jsonrpc": JSONRPC_VERSION, "id": 1, "result": null }));
+        let mut notification = json!({ "method": "query.done" });
+        normalize_frame(&mut notification);
+        assert!(notification.get("result").is_none());
+    }
+}
@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+use super::inspector::Direction;
+use super::redact::Redactor;
+use crate::diagnostics;
+
+/// `format` of the header line that starts every recording
+const RECORDING_FORMAT: &str = "relwave-bridge-recording";
+const RECORDING_VERSION: u32 = 1;
+
+/// Record from the first frame into this file
+const RECORD_ENV: &str = "RELWAVE_BRIDGE_RECORD";
+/// Answer requests from this recording instead of spawning the bridge
+const REPLAY_ENV: &str = "RELWAVE_BRIDGE_REPLAY";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingHeader {
+    pub format: String,
+    pub version: u32,
+    pub started_at: String,
+    pub app_version: String,
+    pub os: String,
+}
+
+/// One line of a recording after the header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Milliseconds since the recording started
+    pub t: f64,
+    pub direction: Direction,
+    /// The parsed frame, or the raw line as a string if it was not JSON
+    pub frame: Value,
+}
+
+/// A recording read back from disk
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("failed to open recording: {}", e))?;
+        let mut lines = BufReader::new(file).lines();
+        let header: RecordingHeader = lines
+            .next()
+            .and_then(Result::ok)
+            .and_then(|l| serde_json::from_str(&l).ok())
+            .ok_or("not a bridge recording: missing header")?;
+        if header.format != RECORDING_FORMAT {
+            return Err(format!("not a bridge recording: format is '{}'", header.format));
+        }
+        if header.version > RECORDING_VERSION {
+            return Err(format!("recording version {} is newer than this build supports", header.version));
+        }
+
+        let mut frames = Vec::new();
+        for (n, line) in lines.enumerate() {
+            let line = line.map_err(|e| format!("failed to read recording: {}", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // The header is line 1
+            let frame = serde_json::from_str(&line).map_err(|e| format!("recording line {}: {}", n + 2, e))?;
+            frames.push(frame);
+        }
+        Ok(Self { header, frames })
+    }
+}
+
+struct ActiveRecording {
+    path: PathBuf,
+    file: LineWriter<File>,
+    started_at: Instant,
+    frames: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingStatus {
+    /// File being recorded to
+    pub recording: Option<String>,
+    pub frames: u64,
+    /// Recording the replay bridge answers from
+    pub replaying: Option<String>,
+}
+
+/// Writes bridge traffic with timings to a file, and remembers which recording to replay instead of spawning the bridge.
+/// Outgoing frames are redacted like logs; incoming frames are recorded as the webviews see them.
+#[derive(Default)]
+pub struct BridgeRecorder {
+    recording: Mutex<Option<ActiveRecording>>,
+    replay_source: Mutex<Option<PathBuf>>,
+}
+
+impl BridgeRecorder {
+    /// Start recording and/or replaying if `RELWAVE_BRIDGE_RECORD` / `RELWAVE_BRIDGE_REPLAY` are set
+    pub fn from_env(app_handle: &AppHandle, app_version: &str) -> Self {
+        let recorder = Self::default();
+        if let Some(path) = std::env::var_os(REPLAY_ENV) {
+            recorder.set_replay_source(Some(PathBuf::from(path)));
+        }
+        if let Some(path) = std::env::var_os(RECORD_ENV) {
+            if let Err(e) = recorder.start(PathBuf::from(path), app_version) {
+                diagnostics::shell_log(app_handle, e);
+            }
+        }
+        recorder
+    }
+
+    /// Start writing traffic to `path`, replacing any recording in progress
+    pub fn start(&self, path: PathBuf, app_version: &str) -> Result<(), String> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create recording dir: {}", e))?;
+        }
+        let mut file = LineWriter::new(File::create(&path).map_err(|e| format!("failed to create recording: {}", e))?);
+        let header = RecordingHeader {
+            format: RECORDING_FORMAT.into(),
+            version: RECORDING_VERSION,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            app_version: app_version.to_string(),
+            os: std::env::consts::OS.to_string(),
+        };
+        let json = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+        writeln!(file, "{}", json).map_err(|e| format!("failed to write recording: {}", e))?;
+
+        *self.recording.lock().unwrap() = Some(ActiveRecording {
+            path,
+            file,
+            started_at: Instant::now(),
+            frames: 0,
+        });
+        Ok(())
+    }
+
+    /// Stop recording; returns the status of the finished recording
+    pub fn stop(&self) -> RecordingStatus {
+        let finished = self.recording.lock().unwrap().take();
+        RecordingStatus {
+            recording: finished.as_ref().map(|r| r.path.display().to_string()),
+            frames: finished.map_or(0, |r| r.frames),
+            replaying: self.replay_source().map(|p| p.display().to_string()),
+        }
+    }
+
+    pub fn status(&self) -> RecordingStatus {
+        let recording = self.recording.lock().unwrap();
+        RecordingStatus {
+            recording: recording.as_ref().map(|r| r.path.display().to_string()),
+            frames: recording.as_ref().map_or(0, |r| r.frames),
+            replaying: self.replay_source().map(|p| p.display().to_string()),
+        }
+    }
+
+    pub fn replay_source(&self) -> Option<PathBuf> {
+        self.replay_source.lock().unwrap().clone()
+    }
+
+    /// Replay `path` the next time the bridge starts; `None` goes back to the real bridge
+    pub fn set_replay_source(&self, path: Option<PathBuf>) {
+        *self.replay_source.lock().unwrap() = path;
+    }
+
+    /// Append a frame; an error means the recording was given up
+    pub(super) fn write(&self, direction: Direction, frame: Value) -> Result<(), String> {
+        let mut guard = self.recording.lock().unwrap();
+        let Some(recording) = guard.as_mut() else {
+            return Ok(());
+        };
+        let entry = RecordedFrame {
+            t: recording.started_at.elapsed().as_secs_f64() * 1000.0,
+            direction,
+            frame,
+        };
+        let written = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|json| writeln!(recording.file, "{}", json).map_err(|e| e.to_string()));
+        match written {
+            Ok(()) => {
+                recording.frames += 1;
+                Ok(())
+            }
+            // A full disk shouldn't take the bridge down with it; give up on the recording instead
+            Err(e) => {
+                *guard = None;
+                Err(format!("Stopped recording bridge traffic: {}", e))
+            }
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.recording.lock().unwrap().is_some()
+    }
+}
+
+/// Record a frame written to the bridge, with credentials masked
+pub fn record_out(app_handle: &AppHandle, data: &str) {
+    let Some(recorder) = app_handle.try_state::<BridgeRecorder>() else {
+        return;
+    };
+    if !recorder.is_recording() {
+        return;
+    }
+    let mut frame = serde_json::from_str(data).unwrap_or_else(|_| Value::String(data.to_string()));
+    if let Some(redactor) = app_handle.try_state::<Redactor>() {
+        redactor.redact_json(&mut frame);
+    }
+    if let Err(e) = recorder.write(Direction::Out, frame) {
+        diagnostics::shell_log(app_handle, e);
+    }
+}
+
+/// Record a (redacted) stdout line
+pub fn record_in(app_handle: &AppHandle, line: &str) {
+    let Some(recorder) = app_handle.try_state::<BridgeRecorder>() else {
+        return;
+    };
+    if !recorder.is_recording() {
+        return;
+    }
+    let frame = serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_string()));
+    if let Err(e) = recorder.write(Direction::In, frame) {
+        diagnostics::shell_log(app_handle, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn recordings_read_back_as_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("traffic").join("session.jsonl");
+        let recorder = BridgeRecorder::default();
+        recorder.start(path.clone(), "1.2.3").unwrap();
+
+        recorder.write(Direction::Out, json!({"id": 1, "method": "db.list"})).unwrap();
+        recorder.write(Direction::In, Value::String("not json".into())).unwrap();
+        recorder.write(Direction::In, json!({"id": 1, "result": []})).unwrap();
+        assert_eq!(recorder.status().frames, 3);
+        let finished = recorder.stop();
+        assert_eq!(finished.recording, Some(path.display().to_string()));
+        assert_eq!(finished.frames, 3);
+        assert!(recorder.write(Direction::Out, json!({"id": 2})).is_ok());
+
+        let recording = Recording::load(&path).unwrap();
+        assert_eq!(recording.header.format, RECORDING_FORMAT);
+        assert_eq!(recording.header.app_version, "1.2.3");
+        let frames: Vec<(Direction, Value)> = recording.frames.iter().map(|f| (f.direction, f.frame.clone())).collect();
+        assert_eq!(
+            frames,
+            vec![
+                (Direction::Out, json!({"id": 1, "method": "db.list"})),
+                (Direction::In, json!("not json")),
+                (Direction::In, json!({"id": 1, "result": []})),
+            ]
+        );
+        assert!(recording.frames.windows(2).all(|w| w[0].t <= w[1].t));
+    }
+
+    #[test]
+    fn other_files_and_newer_versions_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.jsonl");
+
+        std::fs::write(&path, "{\"id\":1}\n").unwrap();
+        assert!(Recording::load(&path).err().unwrap().contains("missing header"));
+        let header = json!({"format": "other", "version": 1, "startedAt": "", "appVersion": "", "os": ""});
+        std::fs::write(&path, format!("{}\n", header)).unwrap();
+        assert!(Recording::load(&path).err().unwrap().contains("format is 'other'"));
+        let header = json!({"format": RECORDING_FORMAT, "version": RECORDING_VERSION + 1, "startedAt": "", "appVersion": "", "os": ""});
+        std::fs::write(&path, format!("{}\n", header)).unwrap();
+        assert!(Recording::load(&path).err().unwrap().contains("newer"));
+        let header = json!({"format": RECORDING_FORMAT, "version": RECORDING_VERSION, "startedAt": "", "appVersion": "", "os": ""});
+        std::fs::write(&path, format!("{}\n\n{{broken\n", header)).unwrap();
+        assert!(Recording::load(&path).err().unwrap().contains("line 3"));
+    }
+}
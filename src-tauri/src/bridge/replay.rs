@@ -0,0 +1,292 @@
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use super::inspector::Direction;
+use super::process::InProcessBridge;
+use super::recording::Recording;
+use super::redact::{self, Redactor};
+use super::router;
+
+/// A recorded request with everything the bridge sent back for it
+struct Exchange {
+    method: String,
+    params: Value,
+    /// Wire id in the recording, used to pair the response
+    id: Value,
+    /// Response and notifications with their delay after the request
+    replies: Vec<(Duration, Value)>,
+    used: bool,
+}
+
+fn delay(ms: f64) -> Duration {
+    Duration::from_secs_f64(ms.max(0.0) / 1000.0)
+}
+
+fn session_of(value: &Value) -> Option<&str> {
+    value.get("sessionId").and_then(Value::as_str)
+}
+
+/// Split a recording into what the bridge sent before any request and one exchange per request.
+/// A response goes with the request of the same id; a notification with the latest request for its session,
+/// or the latest request of all if it has none.
+fn exchanges(recording: &Recording) -> (Vec<(Duration, Value)>, Vec<Exchange>) {
+    let mut prelude = Vec::new();
+    let mut exchanges: Vec<Exchange> = Vec::new();
+    let mut started_at: Vec<f64> = Vec::new();
+
+    for recorded in &recording.frames {
+        let frame = &recorded.frame;
+        match recorded.direction {
+            Direction::Out => {
+                // Outgoing notifications have nothing to answer
+                let (Some(method), Some(id)) = (frame.get("method").and_then(Value::as_str), frame.get("id")) else {
+                    continue;
+                };
+                exchanges.push(Exchange {
+                    method: method.to_string(),
+                    params: frame.get("params").cloned().unwrap_or(Value::Null),
+                    id: id.clone(),
+                    replies: Vec::new(),
+                    used: false,
+                });
+                started_at.push(recorded.t);
+            }
+            Direction::In => {
+                let owner = if frame.get("method").is_none() && frame.get("id").is_some() {
+                    exchanges.iter().rposition(|e| Some(&e.id) == frame.get("id"))
+                } else {
+                    let session = frame.get("params").and_then(session_of);
+                    session
+                        .and_then(|s| exchanges.iter().rposition(|e| session_of(&e.params) == Some(s)))
+                        .or_else(|| exchanges.len().checked_sub(1))
+                };
+                match owner {
+                    Some(i) => exchanges[i].replies.push((delay(recorded.t - started_at[i]), frame.clone())),
+                    None => prelude.push((delay(recorded.t), frame.clone())),
+                }
+            }
+        }
+    }
+    (prelude, exchanges)
+}
+
+/// Pick the recorded exchange that answers a request: an unused one with the same method and params,
+/// then an unused one with the same method, then a used one with the same method and params
+fn take_exchange<'a>(exchanges: &'a mut [Exchange], method: &str, params: &Value) -> Option<&'a mut Exchange> {
+    let index = exchanges
+        .iter()
+        .position(|e| !e.used && e.method == method && &e.params == params)
+        .or_else(|| exchanges.iter().position(|e| !e.used && e.method == method))
+        .or_else(|| exchanges.iter().position(|e| e.method == method && &e.params == params))?;
+    let exchange = &mut exchanges[index];
+    exchange.used = true;
+    Some(exchange)
+}
+
+/// Route `frames` to the windows as if the bridge had written them, each at its delay from now
+fn play(app_handle: &AppHandle, stopped: &Arc<AtomicBool>, frames: Vec<(Duration, Value)>) {
+    if frames.is_empty() {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    let stopped = stopped.clone();
+    std::thread::spawn(move || {
+        let start = Instant::now();
+        for (at, frame) in frames {
+            if let Some(wait) = at.checked_sub(start.elapsed()) {
+                std::thread::sleep(wait);
+            }
+            if stopped.load(Ordering::Relaxed) {
+                return;
+            }
+            let line = match frame {
+                Value::String(line) => line,
+                frame => frame.to_string(),
+            };
+            let line = redact::filter_stdout(&app_handle, line);
+            router::route_stdout(&app_handle, line);
+        }
+    });
+}
+
+/// A fake bridge inside the shell that answers requests from a recording, with the recorded timings.
+/// Takes the place of the bridge process, so the frontend can be exercised offline against a captured session.
+pub struct ReplayBridge {
+    requests: Option<Sender<String>>,
+    stopped: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ReplayBridge {
+    pub fn start(app_handle: &AppHandle, path: &Path) -> Result<Self, String> {
+        let recording = Recording::load(path)?;
+        crate::diagnostics::shell_log(
+            app_handle,
+            format!(
+                "Replaying bridge recording {} (made {} by version {} on {})",
+                path.display(),
+                recording.header.started_at,
+                recording.header.app_version,
+                recording.header.os
+            ),
+        );
+        let (prelude, mut exchanges) = exchanges(&recording);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (requests, incoming) = mpsc::channel::<String>();
+
+        play(app_handle, &stopped, prelude);
+
+        let app_handle = app_handle.clone();
+        let worker_stopped = stopped.clone();
+        let worker = std::thread::spawn(move || {
+            for line in incoming {
+                let Ok(frame) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                let (Some(method), Some(id)) = (frame.get("method").and_then(Value::as_str), frame.get("id")) else {
+                    continue;
+                };
+                // Requests were recorded redacted, so compare them the same way
+                let mut params = frame.get("params").cloned().unwrap_or(Value::Null);
+                if let Some(redactor) = app_handle.try_state::<Redactor>() {
+                    redactor.redact_json(&mut params);
+                }
+
+                let replies = match take_exchange(&mut exchanges, method, &params) {
+                    Some(exchange) => {
+                        let recorded_id = exchange.id.clone();
+                        exchange
+                            .replies
+                            .iter()
+                            .map(|(at, reply)| {
+                                let mut reply = reply.clone();
+                                if reply.get("method").is_none() && reply.get("id") == Some(&recorded_id) {
+                                    reply["id"] = id.clone();
+                                }
+                                (*at, reply)
+                            })
+                            .collect()
+                    }
+                    None => vec![(
+                        Duration::ZERO,
+                        json!({
+                            "id": id,
+                            "error": {
+                                "code": "REPLAY_MISS",
+                                "message": format!("no recorded response for {}", method),
+                            },
+                        }),
+                    )],
+                };
+                play(&app_handle, &worker_stopped, replies);
+            }
+        });
+
+        Ok(Self {
+            requests: Some(requests),
+            stopped,
+            worker: Some(worker),
+        })
+    }
+
+}
+
+impl InProcessBridge for ReplayBridge {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    /// Hand a request frame to the replay worker
+    fn write_line(&mut self, data: &str) -> std::io::Result<()> {
+        self.requests
+            .as_ref()
+            .and_then(|r| r.send(data.to_string()).ok())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "replay bridge stopped"))
+    }
+
+    fn is_running(&self) -> bool {
+        !self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Stop answering; frames already scheduled are dropped
+    fn kill(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.requests = None;
+    }
+
+    fn wait(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::recording::BridgeRecorder;
+
+    fn record(frames: &[(Direction, Value)]) -> Recording {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.jsonl");
+        let recorder = BridgeRecorder::default();
+        recorder.start(path.clone(), "test").unwrap();
+        for (direction, frame) in frames {
+            recorder.write(*direction, frame.clone()).unwrap();
+        }
+        recorder.stop();
+        Recording::load(&path).unwrap()
+    }
+
+    #[test]
+    fn recorded_replies_go_with_their_requests() {
+        let recording = record(&[
+            (Direction::In, json!({"method": "bridge.ready"})),
+            (Direction::Out, json!({"id": 1, "method": "query.createSession", "params": {}})),
+            (Direction::Out, json!({"id": 2, "method": "query.run", "params": {"sessionId": "s1"}})),
+            (Direction::Out, json!({"id": 3, "method": "db.list", "params": {}})),
+            (Direction::In, json!({"id": 1, "result": {"sessionId": "s1"}})),
+            (Direction::In, json!({"method": "query.progress", "params": {"sessionId": "s1"}})),
+            (Direction::In, json!({"id": 2, "result": {"rows": []}})),
+            (Direction::Out, json!({"method": "bridge.ping"})),
+            (Direction::In, json!({"id": 3, "result": []})),
+        ]);
+
+        let (prelude, exchanges) = exchanges(&recording);
+        assert_eq!(prelude.len(), 1);
+        assert_eq!(exchanges.len(), 3);
+        let replies = |i: usize| exchanges[i].replies.iter().map(|(_, r)| r.clone()).collect::<Vec<_>>();
+        assert_eq!(replies(0), vec![json!({"id": 1, "result": {"sessionId": "s1"}})]);
+        assert_eq!(
+            replies(1),
+            vec![
+                json!({"method": "query.progress", "params": {"sessionId": "s1"}}),
+                json!({"id": 2, "result": {"rows": []}}),
+            ]
+        );
+        assert_eq!(replies(2), vec![json!({"id": 3, "result": []})]);
+    }
+
+    #[test]
+    fn requests_prefer_unused_exchanges_with_the_same_params() {
+        let recording = record(&[
+            (Direction::Out, json!({"id": 1, "method": "db.get", "params": {"id": "a"}})),
+            (Direction::In, json!({"id": 1, "result": "a"})),
+            (Direction::Out, json!({"id": 2, "method": "db.get", "params": {"id": "b"}})),
+            (Direction::In, json!({"id": 2, "result": "b"})),
+        ]);
+        let (_, mut exchanges) = exchanges(&recording);
+
+        let b = json!({"id": "b"});
+        assert_eq!(take_exchange(&mut exchanges, "db.get", &b).unwrap().id, json!(2));
+        assert_eq!(take_exchange(&mut exchanges, "db.get", &b).unwrap().id, json!(1));
+        assert_eq!(take_exchange(&mut exchanges, "db.get", &b).unwrap().id, json!(2));
+        assert!(take_exchange(&mut exchanges, "db.list", &Value::Null).is_none());
+    }
+}
@@ -0,0 +1,451 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::inspector;
+use super::metrics;
+use super::timeouts;
+use super::recording;
+use super::pending::{self, PendingRequest, PendingRequests};
+use super::client::BACKGROUND_WINDOW;
+use super::policy::MAIN_WINDOW;
+use super::protocol::{self, RequestId, RpcRequest};
+
+/// Event carrying bridge stdout lines to the webviews
+pub const STDOUT_EVENT: &str = "bridge-stdout";
+
+/// Responses collected for a batch request until every member has been answered
+struct Batch {
+    window: String,
+    waiting: usize,
+    responses: Vec<Value>,
+}
+
+/// Routes bridge traffic between windows.
+/// Every request gets a shell-wide id, and query sessions belong to the window that created or used them.
+pub struct BridgeRouter {
+    next_id: AtomicI64,
+    /// Session id to the label of the window that owns it
+    sessions: Mutex<HashMap<String, String>>,
+    next_batch: AtomicU64,
+    batches: Mutex<HashMap<u64, Batch>>,
+    /// Wire id of each batch member to its batch
+    batch_members: Mutex<HashMap<RequestId, u64>>,
+}
+
+impl Default for BridgeRouter {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicI64::new(1),
+            sessions: Mutex::new(HashMap::new()),
+            next_batch: AtomicU64::new(1),
+            batches: Mutex::new(HashMap::new()),
+            batch_members: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl BridgeRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give `request` a shell-wide id and rewrite `data` to carry it.
+    /// A session the request names becomes the window's if no other window owns it; one that another window owns is refused.
+    /// Returns the rewritten frame and the id the window used.
+    pub fn assign(&self, request: &mut RpcRequest, data: &str, window: &str) -> Result<(String, RequestId), String> {
+        let mut frame: Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+        if let Some(session) = request.param_str("sessionId") {
+            self.claim(session, window)?;
+        }
+
+        let client_id = request.id.clone();
+        request.id = RequestId::Number(self.next_id.fetch_add(1, Ordering::Relaxed));
+        frame["id"] = json!(request.id);
+        Ok((frame.to_string(), client_id))
+    }
+
+    /// Make `window` the owner of `session` unless another window already is
+    fn claim(&self, session: &str, window: &str) -> Result<(), String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(session) {
+            Some(owner) if owner != window => Err(format!("session {} belongs to another window", session)),
+            Some(_) => Ok(()),
+            None => {
+                sessions.insert(session.to_string(), window.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    fn owner(&self, session: &str) -> Option<String> {
+        self.sessions.lock().unwrap().get(session).cloned()
+    }
+
+    /// Query sessions owned by `window`
+    pub fn sessions_of(&self, window: &str) -> Vec<String> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, w)| w.as_str() == window)
+            .map(|(s, _)| s.clone())
+            .collect()
+    }
+
+    /// Forget the sessions of a closed window and return them so they can be cancelled
+    pub fn release_window(&self, window: &str) -> Vec<String> {
+        let owned = self.sessions_of(window);
+        self.sessions.lock().unwrap().retain(|_, w| w != window);
+        self.release_batches(window);
+        owned
+    }
+
+    /// Forget every session and batch, e.g. when the bridge restarts
+    pub fn clear(&self) {
+        self.sessions.lock().unwrap().clear();
+        self.batches.lock().unwrap().clear();
+        self.batch_members.lock().unwrap().clear();
+    }
+
+    /// Start collecting responses for a batch from `window`.
+    /// `members` are the wire ids sent to the bridge; `responses` those answered already (e.g. rejected by the policy).
+    /// Returns the complete batch response right away if nothing was sent.
+    pub fn open_batch(&self, window: &str, members: &[RequestId], responses: Vec<Value>) -> Option<Vec<Value>> {
+        if members.is_empty() {
+            return Some(responses);
+        }
+        let batch = self.next_batch.fetch_add(1, Ordering::Relaxed);
+        self.batches.lock().unwrap().insert(
+            batch,
+            Batch {
+                window: window.to_string(),
+                waiting: members.len(),
+                responses,
+            },
+        );
+        let mut batch_members = self.batch_members.lock().unwrap();
+        for id in members {
+            batch_members.insert(id.clone(), batch);
+        }
+        None
+    }
+
+    /// Add the response to a batch member.
+    /// Returns the frame back if `id` is not part of a batch, and the window and responses once the batch is complete.
+    pub fn add_to_batch(&self, id: &RequestId, frame: Value) -> Result<Option<(String, Vec<Value>)>, Value> {
+        let Some(batch_id) = self.batch_members.lock().unwrap().remove(id) else {
+            return Err(frame);
+        };
+        let mut batches = self.batches.lock().unwrap();
+        let Some(batch) = batches.get_mut(&batch_id) else {
+            return Ok(None);
+        };
+        batch.responses.push(frame);
+        batch.waiting -= 1;
+        if batch.waiting > 0 {
+            return Ok(None);
+        }
+        Ok(batches.remove(&batch_id).map(|b| (b.window, b.responses)))
+    }
+
+    fn release_batches(&self, window: &str) {
+        let mut batches = self.batches.lock().unwrap();
+        batches.retain(|_, b| b.window != window);
+        self.batch_members
+            .lock()
+            .unwrap()
+            .retain(|_, batch| batches.contains_key(batch));
+    }
+}
+
+fn session_of(value: &Value) -> Option<&str> {
+    value
+        .get("sessionId")
+        .or_else(|| value.get("data").and_then(|d| d.get("sessionId")))
+        .and_then(Value::as_str)
+}
+
+/// Deliver a (redacted) stdout line to the window it belongs to.
+/// Responses go to the window that sent the request, session notifications to the session's owner,
+/// responses nobody is waiting for to the main window only, and everything else (bridge status,
+/// unparseable lines) to every window.
+pub fn route_stdout(app_handle: &AppHandle, line: String) {
+    let Ok(mut frame) = serde_json::from_str::<Value>(&line) else {
+        recording::record_in(app_handle, &line);
+        inspector::record_stdout(app_handle, &line, None, None, None);
+        let _ = app_handle.emit(STDOUT_EVENT, line);
+        return;
+    };
+    let router = app_handle.try_state::<BridgeRouter>();
+
+    if frame.get("method").is_some() {
+        recording::record_in(app_handle, &line);
+        let owner = frame
+            .get("params")
+            .and_then(session_of)
+            .and_then(|s| router.as_ref().and_then(|r| r.owner(s)));
+        inspector::record_stdout(app_handle, &line, Some(&frame), None, owner.as_deref());
+        metrics::observe_notification(app_handle, &frame, &line);
+        crate::notifications::observe_notification(app_handle, &frame, owner.as_deref());
+        protocol::normalize_frame(&mut frame);
+        let line = frame.to_string();
+        let _ = match owner {
+            Some(window) => app_handle.emit_to(window.as_str(), STDOUT_EVENT, line),
+            None => app_handle.emit(STDOUT_EVENT, line),
+        };
+        return;
+    }
+
+    let pending = pending::complete_response(app_handle, &frame);
+    // The shell's housekeeping stays out of the user's traffic
+    let background = pending.as_ref().is_some_and(|p| p.window == BACKGROUND_WINDOW);
+    if !background {
+        recording::record_in(app_handle, &line);
+        inspector::record_stdout(app_handle, &line, Some(&frame), pending.as_ref(), None);
+    }
+    let Some(pending) = pending else {
+        if timeouts::observe_late(app_handle, &frame) {
+            return;
+        }
+        protocol::normalize_frame(&mut frame);
+        let _ = app_handle.emit_to(MAIN_WINDOW, STDOUT_EVENT, frame.to_string());
+        return;
+    };
+    if !background {
+        metrics::observe_response(app_handle, &pending, &frame, &line);
+    }
+    if let Some(reply) = pending.reply {
+        let _ = reply.send(frame);
+        return;
+    }
+
+    crate::notifications::observe_response(app_handle, &pending, &frame);
+
+    // A new session belongs to the window that asked for it
+    if pending.request.method == "query.createSession" {
+        if let (Some(router), Some(session)) = (&router, frame.get("result").and_then(session_of)) {
+            let _ = router.claim(session, &pending.window);
+        }
+    }
+
+    frame["id"] = json!(pending.client_id);
+    deliver(app_handle, &pending.request.id, &pending.window, frame);
+}
+
+/// Send a response to the window that asked for it as JSON-RPC 2.0, inside its batch if it was part of one.
+/// `frame` already carries the window's id; `id` is the wire id.
+pub fn deliver(app_handle: &AppHandle, id: &RequestId, window: &str, mut frame: Value) {
+    protocol::normalize_frame(&mut frame);
+    let frame = match app_handle.try_state::<BridgeRouter>() {
+        Some(router) => match router.add_to_batch(id, frame) {
+            Ok(Some((window, responses))) => {
+                let _ = app_handle.emit_to(window.as_str(), STDOUT_EVENT, Value::Array(responses).to_string());
+                return;
+            }
+            Ok(None) => return,
+            Err(frame) => frame,
+        },
+        None => frame,
+    };
+    let _ = app_handle.emit_to(window, STDOUT_EVENT, frame.to_string());
+}
+
+/// Answer a request with an error the shell made up, e.g. when it timed out
+pub fn fail_request(app_handle: &AppHandle, pending: PendingRequest, error: Value) {
+    if let Some(reply) = pending.reply {
+        let _ = reply.send(json!({ "id": pending.request.id, "error": error }));
+        return;
+    }
+    let frame = json!({ "id": pending.client_id, "error": error });
+    deliver(app_handle, &pending.request.id, &pending.window, frame);
+}
+
+/// Cancel the sessions and drop the requests of a window that has been closed
+pub fn window_closed(app_handle: &AppHandle, window: &str) {
+    if let Some(pending) = app_handle.try_state::<PendingRequests>() {
+        pending::abandon(app_handle, pending.drop_window(window), "window closed");
+    }
+    if let Some(notifier) = app_handle.try_state::<crate::notifications::Notifier>() {
+        notifier.drop_window(window);
+    }
+    let Some(router) = app_handle.try_state::<BridgeRouter>() else {
+        return;
+    };
+    let sessions = router.release_window(window);
+    if sessions.is_empty() {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        let failures = end_sessions(&sessions, |method, params| super::client::request(&app_handle, method, params));
+        for failure in failures {
+            crate::diagnostics::shell_log(&app_handle, failure);
+        }
+    });
+}
+
+/// Cancel whatever the sessions still run, then destroy them so the bridge does not keep them.
+/// Returns a message for every request that failed.
+fn end_sessions(sessions: &[String], mut request: impl FnMut(&str, Value) -> Result<Value, String>) -> Vec<String> {
+    let mut failures = Vec::new();
+    for session in sessions {
+        for (method, action) in [("query.cancel", "cancel"), ("query.destroySession", "destroy")] {
+            if let Err(e) = request(method, json!({ "sessionId": session })) {
+                failures.push(format!("Failed to {} session {}: {}", action, session, e));
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::fake::{FakeLauncher, Script, Step};
+    use crate::bridge::process::BridgeProcess;
+    use std::time::Duration;
+
+    fn echo_script() -> Script {
+        Script::new()
+            .on("echo", |params| vec![Step::Respond(params.clone())])
+            .on("query.run", |params| {
+                vec![
+                    Step::Notify {
+                        method: "query.done",
+                        params: json!({ "sessionId": params["sessionId"] }),
+                    },
+                    Step::Respond(json!({ "ok": true })),
+                ]
+            })
+    }
+
+    /// Assign and send a request from `window` the way `bridge_write` does
+    fn send(router: &BridgeRouter, process: &BridgeProcess, pending: &PendingRequests, data: &str, window: &str) -> RequestId {
+        let mut request = protocol::parse_request(data).unwrap();
+        let (frame, client_id) = router.assign(&mut request, data, window).unwrap();
+        let id = request.id.clone();
+        process.send(pending, request, client_id, &frame, window, None).unwrap();
+        id
+    }
+
+    #[test]
+    fn windows_reusing_ids_get_their_own_responses() {
+        let launcher = FakeLauncher::new(echo_script());
+        let (child, stdout) = launcher.start().unwrap();
+        let process = BridgeProcess::new(Some(child));
+        let pending = PendingRequests::new();
+        let router = BridgeRouter::new();
+
+        let first = send(&router, &process, &pending, r#"{"id":1,"method":"echo","params":{"from":"main"}}"#, "main");
+        let second = send(&router, &process, &pending, r#"{"id":1,"method":"echo","params":{"from":"tool"}}"#, "tool");
+        assert_ne!(first, second);
+
+        for _ in 0..2 {
+            let frame: Value = serde_json::from_str(&stdout.recv_timeout(Duration::from_secs(5)).unwrap()).unwrap();
+            let id: RequestId = serde_json::from_value(frame["id"].clone()).unwrap();
+            let request = pending.complete(&id).expect("response matches a pending request");
+            assert_eq!(request.client_id, RequestId::Number(1));
+            assert_eq!(frame["result"]["from"], request.window);
+        }
+    }
+
+    #[test]
+    fn sessions_belong_to_the_window_that_used_them() {
+        let launcher = FakeLauncher::new(echo_script());
+        let (child, stdout) = launcher.start().unwrap();
+        let process = BridgeProcess::new(Some(child));
+        let pending = PendingRequests::new();
+        let router = BridgeRouter::new();
+
+        send(&router, &process, &pending, r#"{"id":1,"method":"query.run","params":{"sessionId":"s1"}}"#, "tool");
+        let notification: Value = serde_json::from_str(&stdout.recv_timeout(Duration::from_secs(5)).unwrap()).unwrap();
+        let owner = notification.get("params").and_then(session_of).and_then(|s| router.owner(s));
+        assert_eq!(owner.as_deref(), Some("tool"));
+
+        assert_eq!(router.release_window("tool"), vec!["s1".to_string()]);
+        assert!(router.owner("s1").is_none());
+    }
+
+    #[test]
+    fn windows_cannot_take_over_each_others_sessions() {
+        let router = BridgeRouter::new();
+        let data = r#"{"id":1,"method":"query.run","params":{"sessionId":"s1"}}"#;
+        let mut request = protocol::parse_request(data).unwrap();
+        router.assign(&mut request, data, "main").unwrap();
+
+        let mut request = protocol::parse_request(data).unwrap();
+        let error = router.assign(&mut request, data, "tool").unwrap_err();
+        assert!(error.contains("another window"), "{}", error);
+        assert_eq!(request.id, RequestId::Number(1));
+        assert_eq!(router.owner("s1").as_deref(), Some("main"));
+
+        let mut request = protocol::parse_request(data).unwrap();
+        assert!(router.assign(&mut request, data, "main").is_ok());
+        router.release_window("main");
+        assert!(router.assign(&mut request, data, "tool").is_ok());
+        assert_eq!(router.owner("s1").as_deref(), Some("tool"));
+    }
+
+    #[test]
+    fn batches_complete_once_every_member_is_answered() {
+        let router = BridgeRouter::new();
+        let members = [RequestId::Number(10), RequestId::Number(11)];
+        let rejected = json!({ "id": 3, "error": { "code": -32002, "message": "denied" } });
+        assert!(router.open_batch("main", &members, vec![rejected]).is_none());
+
+        assert_eq!(router.add_to_batch(&members[0], json!({ "id": 10 })), Ok(None));
+        let (window, responses) = router.add_to_batch(&members[1], json!({ "id": 11 })).unwrap().unwrap();
+        assert_eq!(window, "main");
+        assert_eq!(responses.len(), 3);
+
+        let stray = json!({ "id": 12 });
+        assert_eq!(router.add_to_batch(&RequestId::Number(12), stray.clone()), Err(stray));
+        assert_eq!(router.open_batch("main", &[], vec![]), Some(vec![]));
+    }
+
+    #[test]
+    fn closed_windows_cancel_and_destroy_their_sessions() {
+        let script = Script::new()
+            .on("query.cancel", |params| {
+                if params["sessionId"] == "done" {
+                    vec![Step::Fail {
+                        code: "NOT_RUNNING",
+                        message: "session is not running",
+                    }]
+                } else {
+                    vec![Step::Respond(json!({ "ok": true }))]
+                }
+            })
+            .on("query.destroySession", |_| vec![Step::Respond(json!({ "ok": true }))]);
+        let launcher = FakeLauncher::new(script);
+        let (mut child, stdout) = launcher.start().unwrap();
+        let request = |method: &str, params: Value| {
+            let frame = json!({ "id": 1, "method": method, "params": params });
+            child.write_line(&frame.to_string()).map_err(|e| e.to_string())?;
+            let response: Value = serde_json::from_str(&stdout.recv_timeout(Duration::from_secs(5)).unwrap()).unwrap();
+            match response.get("error") {
+                Some(error) => Err(error["message"].as_str().unwrap_or_default().to_string()),
+                None => Ok(response["result"].clone()),
+            }
+        };
+
+        let failures = end_sessions(&["running".to_string(), "done".to_string()], request);
+        assert_eq!(failures, vec!["Failed to cancel session done: session is not running".to_string()]);
+        let sent: Vec<(String, String)> = launcher
+            .received()
+            .iter()
+            .map(|f| (f["method"].as_str().unwrap().to_string(), f["params"]["sessionId"].as_str().unwrap().to_string()))
+            .collect();
+        let expected = [
+            ("query.cancel", "running"),
+            ("query.destroySession", "running"),
+            ("query.cancel", "done"),
+            ("query.destroySession", "done"),
+        ];
+        assert_eq!(sent, expected.map(|(m, s)| (m.to_string(), s.to_string())));
+    }
+}
@@ -0,0 +1,459 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use regex::Regex;
+use sqlparser::ast::{ObjectName, Query, SetExpr, Statement};
+use sqlparser::dialect::{Dialect, GenericDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+use sqlparser::parser::Parser;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::protocol::RpcRequest;
+
+/// How long an issued confirmation token stays valid
+const CONFIRMATION_TTL: Duration = Duration::from_secs(120);
+
+/// Bridge methods that modify the schema or data of a connection.
+/// They name it with `dbId`, except the migration ones, which also accept a `projectId` alone.
+const DATABASE_WRITE_METHODS: &[&str] = &[
+    "query.insertRow",
+    "query.updateRow",
+    "query.deleteRow",
+    "query.createTable",
+    "query.createIndexes",
+    "query.dropTable",
+    "query.alterTable",
+    "migration.apply",
+    "migration.applyMigrations",
+    "migration.applySnapshot",
+    "migration.rollback",
+];
+
+/// SQLite pragmas that only report on the schema when called as `PRAGMA name(arg)`
+const READ_ONLY_PRAGMAS: &[&str] = &[
+    "table_info",
+    "table_xinfo",
+    "index_list",
+    "index_info",
+    "foreign_key_list",
+];
+
+/// Safety mode of a connection, enforced in the shell before requests reach the bridge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionMode {
+    /// No restrictions
+    #[default]
+    Normal,
+    /// Mutating methods and statements are rejected
+    ReadOnly,
+    /// Mutating methods and statements need a confirmation token
+    Production,
+}
+
+impl ConnectionMode {
+    fn strictness(self) -> u8 {
+        match self {
+            Self::Normal => 0,
+            Self::Production => 1,
+            Self::ReadOnly => 2,
+        }
+    }
+
+    /// Whether switching from `self` to `to` lifts any restriction
+    pub fn is_relaxed_by(self, to: Self) -> bool {
+        to.strictness() < self.strictness()
+    }
+}
+
+/// Outcome of checking a request against the connection's mode
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    Confirm(String),
+    Deny(String),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct SafetyFile {
+    #[serde(default)]
+    connections: HashMap<String, ConnectionMode>,
+}
+
+struct Confirmation {
+    fingerprint: String,
+    expires_at: Instant,
+}
+
+/// Per-connection read-only/production flags and outstanding confirmation tokens
+pub struct SafetyGuard {
+    modes: Mutex<HashMap<String, ConnectionMode>>,
+    confirmations: Mutex<HashMap<String, Confirmation>>,
+    path: Option<PathBuf>,
+    /// The bridge's `projects/index.json`, to find the connection of a project
+    projects_index: Option<PathBuf>,
+}
+
+impl SafetyGuard {
+    /// Load connection modes from `path`, starting empty if it is missing or unreadable.
+    /// Requests that name only a project are matched to its connection through `projects_index`.
+    pub fn load(path: Option<PathBuf>, projects_index: Option<PathBuf>) -> Self {
+        let modes = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str::<SafetyFile>(&s).ok())
+            .map(|f| f.connections)
+            .unwrap_or_default();
+
+        Self {
+            modes: Mutex::new(modes),
+            confirmations: Mutex::new(HashMap::new()),
+            path,
+            projects_index,
+        }
+    }
+
+    pub fn modes(&self) -> HashMap<String, ConnectionMode> {
+        self.modes.lock().unwrap().clone()
+    }
+
+    pub fn mode(&self, connection_id: &str) -> ConnectionMode {
+        self.modes
+            .lock()
+            .unwrap()
+            .get(connection_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Change a connection's mode and persist the table
+    pub fn set_mode(&self, connection_id: &str, mode: ConnectionMode) -> Result<(), String> {
+        let mut modes = self.modes.lock().unwrap();
+        if mode == ConnectionMode::Normal {
+            modes.remove(connection_id);
+        } else {
+            modes.insert(connection_id.to_string(), mode);
+        }
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create config dir: {}", e))?;
+        }
+        let file = SafetyFile {
+            connections: modes.clone(),
+        };
+        let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("failed to save safety modes: {}", e))
+    }
+
+    /// Decide whether a request may run against its connection.
+    /// Mutating requests whose connection cannot be worked out are denied.
+    pub fn evaluate(&self, request: &RpcRequest) -> Verdict {
+        let mutation = if request.method == "query.run" {
+            let sql = request.param_str("sql").unwrap_or_default();
+            let statements = mutating_statements(sql);
+            if statements.is_empty() {
+                return Verdict::Allow;
+            }
+            statements.join(", ")
+        } else if DATABASE_WRITE_METHODS.contains(&request.method.as_str()) {
+            request.method.clone()
+        } else {
+            return Verdict::Allow;
+        };
+
+        let Some(connection_id) = self.connection_id(request) else {
+            return Verdict::Deny(format!(
+                "{} blocked: the request names no connection the shell can check",
+                mutation
+            ));
+        };
+        match self.mode(&connection_id) {
+            ConnectionMode::Normal => Verdict::Allow,
+            ConnectionMode::ReadOnly => Verdict::Deny(format!(
+                "connection '{}' is read-only: {} blocked",
+                connection_id, mutation
+            )),
+            ConnectionMode::Production => Verdict::Confirm(format!(
+                "{} on production connection '{}'",
+                mutation, connection_id
+            )),
+        }
+    }
+
+    /// `dbId`, or the connection linked to `projectId` as the bridge resolves it
+    pub fn connection_id(&self, request: &RpcRequest) -> Option<String> {
+        if let Some(db_id) = request.param_str("dbId") {
+            return Some(db_id.to_string());
+        }
+        let project_id = request.param_str("projectId")?;
+        let index = std::fs::read_to_string(self.projects_index.as_ref()?).ok()?;
+        let index: Value = serde_json::from_str(&index).ok()?;
+        index
+            .get("projects")?
+            .as_array()?
+            .iter()
+            .find(|p| p.get("id").and_then(Value::as_str) == Some(project_id))?
+            .get("databaseId")?
+            .as_str()
+            .map(str::to_string)
+    }
+
+    /// Issue a single-use token that authorises exactly this request
+    pub fn issue_confirmation(&self, request: &RpcRequest) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        let mut confirmations = self.confirmations.lock().unwrap();
+        let now = Instant::now();
+        confirmations.retain(|_, c| c.expires_at > now);
+        confirmations.insert(
+            token.clone(),
+            Confirmation {
+                fingerprint: fingerprint(request),
+                expires_at: now + CONFIRMATION_TTL,
+            },
+        );
+        token
+    }
+
+    /// Enforce the connection's mode, consuming `confirmation` if one is needed
+    pub fn check(&self, request: &RpcRequest, confirmation: Option<&str>) -> Result<(), String> {
+        match self.evaluate(request) {
+            Verdict::Allow => Ok(()),
+            Verdict::Deny(reason) => Err(reason),
+            Verdict::Confirm(description) => {
+                let token = confirmation
+                    .ok_or_else(|| format!("confirmation required: {}", description))?;
+                let issued = self.confirmations.lock().unwrap().remove(token);
+                match issued {
+                    Some(c) if c.expires_at > Instant::now() && c.fingerprint == fingerprint(request) => {
+                        Ok(())
+                    }
+                    _ => Err(format!("invalid or expired confirmation token: {}", description)),
+                }
+            }
+        }
+    }
+}
+
+fn fingerprint(request: &RpcRequest) -> String {
+    format!("{}:{}", request.method, request.params)
+}
+
+/// Describe every statement in `sql` that could modify data or schema.
+///
+/// SQL that none of the supported dialects can parse is treated as mutating.
+pub fn mutating_statements(sql: &str) -> Vec<String> {
+    let dialects: [&dyn Dialect; 4] = [
+        &GenericDialect {},
+        &PostgreSqlDialect {},
+        &MySqlDialect {},
+        &SQLiteDialect {},
+    ];
+
+    let sql = quote_pragma_arguments(sql);
+    let Some(statements) = dialects
+        .iter()
+        .find_map(|d| Parser::parse_sql(*d, &sql).ok())
+    else {
+        return vec!["unrecognised SQL".into()];
+    };
+
+    statements
+        .iter()
+        .filter(|s| !is_read_only(s))
+        .map(statement_label)
+        .collect()
+}
+
+fn is_read_only(statement: &Statement) -> bool {
+    match statement {
+        Statement::Query(query) => query_is_read_only(query),
+        Statement::Explain {
+            analyze, statement, ..
+        } => !analyze || is_read_only(statement),
+        Statement::Pragma { name, value, is_eq } => {
+            value.is_none() || (!is_eq && is_read_only_pragma(name))
+        }
+        Statement::ExplainTable { .. }
+        | Statement::ShowFunctions { .. }
+        | Statement::ShowVariable { .. }
+        | Statement::ShowStatus { .. }
+        | Statement::ShowVariables { .. }
+        | Statement::ShowCreate { .. }
+        | Statement::ShowColumns { .. }
+        | Statement::ShowDatabases { .. }
+        | Statement::ShowSchemas { .. }
+        | Statement::ShowTables { .. }
+        | Statement::ShowViews { .. }
+        | Statement::ShowCollation { .. }
+        | Statement::Use(_)
+        | Statement::StartTransaction { .. }
+        | Statement::Commit { .. }
+        | Statement::Rollback { .. } => true,
+        _ => false,
+    }
+}
+
+fn is_read_only_pragma(name: &ObjectName) -> bool {
+    name.0
+        .last()
+        .is_some_and(|ident| READ_ONLY_PRAGMAS.contains(&ident.value.to_lowercase().as_str()))
+}
+
+/// Quote bare identifiers in `PRAGMA name(arg)`, which SQLite accepts but the parser does not
+fn quote_pragma_arguments(sql: &str) -> String {
+    static PRAGMA_CALL: OnceLock<Regex> = OnceLock::new();
+    PRAGMA_CALL
+        .get_or_init(|| Regex::new(r"(?i)(\bPRAGMA\s+[\w.]+\s*\(\s*)([A-Za-z_]\w*)(\s*\))").unwrap())
+        .replace_all(sql, "$1'$2'$3")
+        .into_owned()
+}
+
+fn query_is_read_only(query: &Query) -> bool {
+    let ctes_read_only = query
+        .with
+        .as_ref()
+        .is_none_or(|w| w.cte_tables.iter().all(|cte| query_is_read_only(&cte.query)));
+    ctes_read_only && set_expr_is_read_only(&query.body)
+}
+
+fn set_expr_is_read_only(expr: &SetExpr) -> bool {
+    match expr {
+        SetExpr::Select(select) => select.into.is_none(),
+        SetExpr::Query(query) => query_is_read_only(query),
+        SetExpr::SetOperation { left, right, .. } => {
+            set_expr_is_read_only(left) && set_expr_is_read_only(right)
+        }
+        SetExpr::Values(_) | SetExpr::Table(_) => true,
+        SetExpr::Insert(_) | SetExpr::Update(_) => false,
+    }
+}
+
+/// Short label such as `DROP TABLE` or `DELETE` for messages and dialogs
+fn statement_label(statement: &Statement) -> String {
+    let text = statement.to_string();
+    let mut words = text.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some(first), Some(second))
+            if matches!(first.to_ascii_uppercase().as_str(), "CREATE" | "DROP" | "ALTER" | "TRUNCATE") =>
+        {
+            format!("{} {}", first.to_ascii_uppercase(), second.to_ascii_uppercase())
+        }
+        (Some(first), _) => first.to_ascii_uppercase(),
+        _ => "statement".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::protocol::RequestId;
+    use serde_json::json;
+
+    fn request(method: &str, params: Value) -> RpcRequest {
+        RpcRequest {
+            id: RequestId::Number(1),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    fn guard(dir: &tempfile::TempDir) -> SafetyGuard {
+        let index = dir.path().join("index.json");
+        std::fs::write(
+            &index,
+            json!({ "projects": [{ "id": "p1", "databaseId": "prod" }, { "id": "p2", "databaseId": null }] }).to_string(),
+        )
+        .unwrap();
+        let guard = SafetyGuard::load(Some(dir.path().join("safety.json")), Some(index));
+        guard.set_mode("prod", ConnectionMode::Production).unwrap();
+        guard.set_mode("replica", ConnectionMode::ReadOnly).unwrap();
+        guard
+    }
+
+    #[test]
+    fn reads_pass_and_writes_follow_the_connection_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = guard(&dir);
+
+        let select = json!({ "dbId": "replica", "sql": "SELECT * FROM users" });
+        assert_eq!(guard.evaluate(&request("query.run", select)), Verdict::Allow);
+        let delete = json!({ "dbId": "replica", "sql": "DELETE FROM users" });
+        assert!(matches!(guard.evaluate(&request("query.run", delete)), Verdict::Deny(_)));
+        let insert = json!({ "dbId": "prod", "table": "users" });
+        assert!(matches!(guard.evaluate(&request("query.insertRow", insert)), Verdict::Confirm(_)));
+        let other = json!({ "dbId": "dev", "table": "users" });
+        assert_eq!(guard.evaluate(&request("query.dropTable", other)), Verdict::Allow);
+        assert_eq!(guard.evaluate(&request("query.getTables", json!({}))), Verdict::Allow);
+    }
+
+    #[test]
+    fn migrations_by_project_use_the_linked_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = guard(&dir);
+
+        let linked = request("migration.applyMigrations", json!({ "projectId": "p1" }));
+        assert_eq!(
+            guard.evaluate(&linked),
+            Verdict::Confirm("migration.applyMigrations on production connection 'prod'".into())
+        );
+        for params in [json!({ "projectId": "p2" }), json!({ "projectId": "missing" }), json!({})] {
+            let unresolved = request("migration.applySnapshot", params);
+            assert!(matches!(guard.evaluate(&unresolved), Verdict::Deny(_)));
+        }
+    }
+
+    #[test]
+    fn confirmations_are_single_use_and_bound_to_the_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = guard(&dir);
+        let drop = request("query.dropTable", json!({ "dbId": "prod", "table": "users" }));
+        let other = request("query.dropTable", json!({ "dbId": "prod", "table": "orders" }));
+
+        assert!(guard.check(&drop, None).unwrap_err().starts_with("confirmation required"));
+        let token = guard.issue_confirmation(&drop);
+        assert!(guard.check(&other, Some(&token)).is_err());
+        let token = guard.issue_confirmation(&drop);
+        assert!(guard.check(&drop, Some(&token)).is_ok());
+        assert!(guard.check(&drop, Some(&token)).is_err());
+    }
+
+    #[test]
+    fn modes_persist_and_only_loosening_counts_as_relaxing() {
+        let dir = tempfile::tempdir().unwrap();
+        let _ = guard(&dir);
+        let reloaded = SafetyGuard::load(Some(dir.path().join("safety.json")), None);
+        assert_eq!(reloaded.mode("replica"), ConnectionMode::ReadOnly);
+        assert_eq!(reloaded.mode("unknown"), ConnectionMode::Normal);
+
+        assert!(ConnectionMode::ReadOnly.is_relaxed_by(ConnectionMode::Production));
+        assert!(ConnectionMode::Production.is_relaxed_by(ConnectionMode::Normal));
+        assert!(!ConnectionMode::Normal.is_relaxed_by(ConnectionMode::ReadOnly));
+        assert!(!ConnectionMode::Production.is_relaxed_by(ConnectionMode::Production));
+    }
+
+    #[test]
+    fn statements_are_classified_by_what_they_change() {
+        assert!(mutating_statements("SELECT 1; EXPLAIN SELECT * FROM t; SHOW TABLES").is_empty());
+        assert!(mutating_statements("WITH x AS (SELECT 1) SELECT * FROM x").is_empty());
+        assert!(mutating_statements("PRAGMA user_version").is_empty());
+        assert_eq!(
+            mutating_statements("SELECT 1; DELETE FROM users; DROP TABLE users"),
+            vec!["DELETE", "DROP TABLE"]
+        );
+        assert_eq!(mutating_statements("SELECT * INTO copy FROM users"), vec!["SELECT"]);
+        assert_eq!(mutating_statements("PRAGMA user_version = 5"), vec!["PRAGMA"]);
+        assert!(mutating_statements(
+            "PRAGMA table_info(users); PRAGMA main.index_list('users'); PRAGMA foreign_key_list(\"orders\")"
+        )
+        .is_empty());
+        assert!(mutating_statements("pragma TABLE_XINFO(users); PRAGMA index_info(idx_users)").is_empty());
+        assert_eq!(mutating_statements("PRAGMA journal_mode(wal)"), vec!["PRAGMA"]);
+        assert_eq!(mutating_statements("PRAGMA table_info = 1"), vec!["PRAGMA"]);
+        assert_eq!(mutating_statements("EXPLAIN ANALYZE DELETE FROM users"), vec!["EXPLAIN"]);
+        assert_eq!(mutating_statements("NOT REALLY SQL"), vec!["unrecognised SQL"]);
+    }
+}
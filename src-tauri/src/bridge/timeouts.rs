@@ -0,0 +1,411 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use super::pending::{PendingRequest, PendingRequests};
+use super::protocol::{self, RequestId, RpcRequest};
+use super::router;
+use crate::audit::{self, Outcome};
+
+/// How often the watchdog looks for requests past their deadline
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Timed-out and cancelled requests remembered so a response that turns up later is recognised
+const EXPIRED_LEN: usize = 500;
+
+/// Late responses kept for `bridge_late_responses`
+const LATE_HISTORY_LEN: usize = 100;
+
+/// How long the shell waits for the bridge to answer each method.
+/// Keys are exact method names or prefixes ending in `.`; the longest match wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeoutSettings {
+    pub default_ms: u64,
+    #[serde(default)]
+    pub methods: BTreeMap<String, u64>,
+}
+
+impl Default for TimeoutSettings {
+    fn default() -> Self {
+        let methods = [
+            ("health.ping", 5_000),
+            // Schema operations can be very slow on large databases
+            ("db.getSchema", 180_000),
+            // Table listing can be slow on MySQL
+            ("db.listTables", 120_000),
+            ("db.getStats", 60_000),
+            ("query.", 300_000),
+        ];
+        Self {
+            default_ms: 30_000,
+            methods: methods.into_iter().map(|(m, ms)| (m.to_string(), ms)).collect(),
+        }
+    }
+}
+
+impl TimeoutSettings {
+    pub fn timeout_for(&self, method: &str) -> Duration {
+        let ms = self.methods.get(method).copied().or_else(|| {
+            self.methods
+                .iter()
+                .filter(|(key, _)| key.ends_with('.') && method.starts_with(key.as_str()))
+                .max_by_key(|(key, _)| key.len())
+                .map(|(_, ms)| *ms)
+        });
+        Duration::from_millis(ms.unwrap_or(self.default_ms))
+    }
+}
+
+/// Why the shell gave up on a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AbortReason {
+    Timeout,
+    Cancelled,
+}
+
+struct Expired {
+    request: RpcRequest,
+    window: String,
+    started_at: Instant,
+    reason: AbortReason,
+    at: Instant,
+}
+
+/// A response that arrived after its request had timed out or been cancelled
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LateResponse {
+    pub id: RequestId,
+    pub method: String,
+    pub window: String,
+    pub reason: AbortReason,
+    /// Time between giving up on the request and the response arriving
+    pub late_by_ms: f64,
+    pub error: bool,
+    pub received_at: String,
+}
+
+/// Deadlines of bridge requests, enforced by a watchdog thread, and the responses that arrive after them.
+/// Settings are persisted to `timeouts.json`.
+pub struct RequestTimeouts {
+    settings: Mutex<TimeoutSettings>,
+    path: Option<PathBuf>,
+    /// Timeouts a window asked for on individual requests, by wire id
+    overrides: Mutex<HashMap<RequestId, Duration>>,
+    expired: Mutex<VecDeque<(RequestId, Expired)>>,
+    late: Mutex<VecDeque<LateResponse>>,
+}
+
+impl RequestTimeouts {
+    /// Load settings from `path`, starting with the defaults if it is missing or unreadable
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let settings = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str::<TimeoutSettings>(&s).ok())
+            .unwrap_or_default();
+
+        Self {
+            settings: Mutex::new(settings),
+            path,
+            overrides: Mutex::new(HashMap::new()),
+            expired: Mutex::new(VecDeque::new()),
+            late: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn settings(&self) -> TimeoutSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set_settings(&self, settings: TimeoutSettings) -> Result<(), String> {
+        if settings.default_ms == 0 || settings.methods.values().any(|ms| *ms == 0) {
+            return Err("timeouts must be greater than zero".into());
+        }
+        *self.settings.lock().unwrap() = settings.clone();
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create config dir: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("failed to save timeouts: {}", e))
+    }
+
+    pub fn timeout_for(&self, method: &str) -> Duration {
+        self.settings.lock().unwrap().timeout_for(method)
+    }
+
+    /// Give a request in flight a timeout other than its method's; ignored once it has been answered
+    pub fn set_timeout(&self, pending: &PendingRequests, id: &RequestId, timeout: Duration) {
+        // An override outliving its request is dropped by the next prune
+        if pending.contains(id) {
+            self.overrides.lock().unwrap().insert(id.clone(), timeout);
+        }
+    }
+
+    fn timeout_of(&self, pending: &PendingRequest) -> Duration {
+        match self.overrides.lock().unwrap().get(&pending.request.id) {
+            Some(timeout) => *timeout,
+            None => self.settings.lock().unwrap().timeout_for(&pending.request.method),
+        }
+    }
+
+    fn remember(&self, pending: &PendingRequest, reason: AbortReason) {
+        self.overrides.lock().unwrap().remove(&pending.request.id);
+        let mut expired = self.expired.lock().unwrap();
+        if expired.len() == EXPIRED_LEN {
+            expired.pop_front();
+        }
+        expired.push_back((
+            pending.request.id.clone(),
+            Expired {
+                request: pending.request.clone(),
+                window: pending.window.clone(),
+                started_at: pending.started_at,
+                reason,
+                at: Instant::now(),
+            },
+        ));
+    }
+
+    /// If `frame` answers a request the shell gave up on, record it as late
+    fn take_late(&self, frame: &Value) -> Option<(LateResponse, Expired)> {
+        let id = frame
+            .get("id")
+            .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok())?;
+        let expired = {
+            let mut expired = self.expired.lock().unwrap();
+            let index = expired.iter().position(|(e, _)| e == &id)?;
+            expired.remove(index)?.1
+        };
+        let late = LateResponse {
+            id,
+            method: expired.request.method.clone(),
+            window: expired.window.clone(),
+            reason: expired.reason,
+            late_by_ms: expired.at.elapsed().as_secs_f64() * 1000.0,
+            error: frame.get("error").is_some(),
+            received_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let mut history = self.late.lock().unwrap();
+        if history.len() == LATE_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(late.clone());
+        Some((late, expired))
+    }
+
+    pub fn late_responses(&self) -> Vec<LateResponse> {
+        self.late.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Forget per-request timeouts of requests that are no longer in flight
+    fn prune_overrides(&self, pending: &PendingRequests) {
+        let ids: Vec<RequestId> = self.overrides.lock().unwrap().keys().cloned().collect();
+        let finished: Vec<RequestId> = ids.into_iter().filter(|id| !pending.contains(id)).collect();
+        let mut overrides = self.overrides.lock().unwrap();
+        for id in finished {
+            overrides.remove(&id);
+        }
+    }
+}
+
+/// Stop a query session's work with `query.cancel`.
+/// The bridge has no way to interrupt other methods, so they run to completion and their response arrives late.
+fn cancel_in_bridge(app_handle: &AppHandle, pending: &PendingRequest) {
+    let method = pending.request.method.as_str();
+    if !method.starts_with("query.") || method == "query.cancel" {
+        return;
+    }
+    let Some(session) = pending.request.param_str("sessionId").map(str::to_string) else {
+        return;
+    };
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = super::client::request(&app_handle, "query.cancel", json!({ "sessionId": session })) {
+            crate::diagnostics::shell_log(&app_handle, format!("Failed to cancel session {}: {}", session, e));
+        }
+    });
+}
+
+/// Give up on a request: cancel its query session if it has one, answer the caller with an error and watch for a late response
+pub fn abort(app_handle: &AppHandle, pending: PendingRequest, reason: AbortReason) {
+    let method = pending.request.method.clone();
+    let (outcome, error) = match reason {
+        AbortReason::Timeout => {
+            let timeout = app_handle
+                .try_state::<RequestTimeouts>()
+                .map(|t| t.timeout_of(&pending))
+                .unwrap_or_default();
+            crate::diagnostics::shell_log(
+                app_handle,
+                format!("{} from {} timed out after {} ms", method, pending.window, timeout.as_millis()),
+            );
+            let error = protocol::error_object(
+                "TIMEOUT",
+                format!("Bridge request timeout after {}ms: {}", timeout.as_millis(), method),
+            );
+            ("timeout", error)
+        }
+        AbortReason::Cancelled => (
+            "cancelled",
+            protocol::error_object("CANCELLED", format!("Bridge request cancelled: {}", method)),
+        ),
+    };
+    audit::observe(
+        app_handle,
+        &pending.request,
+        &pending.window,
+        pending.started_at,
+        Outcome::Aborted(outcome, error.clone()),
+    );
+
+    if let Some(timeouts) = app_handle.try_state::<RequestTimeouts>() {
+        timeouts.remember(&pending, reason);
+    }
+    cancel_in_bridge(app_handle, &pending);
+    router::fail_request(app_handle, pending, error);
+}
+
+/// Recognise a response to a request the shell already gave up on; it is logged and audited but not delivered
+pub fn observe_late(app_handle: &AppHandle, frame: &Value) -> bool {
+    let Some((late, expired)) = app_handle
+        .try_state::<RequestTimeouts>()
+        .and_then(|t| t.take_late(frame))
+    else {
+        return false;
+    };
+    crate::diagnostics::shell_log(
+        app_handle,
+        format!(
+            "Late response to {} from {}: arrived {:.0} ms after it was {}",
+            late.method,
+            late.window,
+            late.late_by_ms,
+            match late.reason {
+                AbortReason::Timeout => "timed out",
+                AbortReason::Cancelled => "cancelled",
+            }
+        ),
+    );
+    audit::observe(
+        app_handle,
+        &expired.request,
+        &expired.window,
+        expired.started_at,
+        Outcome::Late(frame),
+    );
+    true
+}
+
+/// Start the thread that times out requests the bridge has not answered in time
+pub fn start_watchdog(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(WATCHDOG_INTERVAL);
+        let (Some(pending), Some(timeouts)) = (
+            app_handle.try_state::<PendingRequests>(),
+            app_handle.try_state::<RequestTimeouts>(),
+        ) else {
+            continue;
+        };
+        let expired = pending.take_where(|p| p.started_at.elapsed() >= timeouts.timeout_of(p));
+        for request in expired {
+            abort(&app_handle, request, AbortReason::Timeout);
+        }
+        timeouts.prune_overrides(&pending);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::process::BridgeProcess;
+    use crate::bridge::fake::{FakeLauncher, Script, Step};
+    use crate::bridge::protocol::parse_request;
+
+    #[test]
+    fn longest_prefix_wins() {
+        let mut settings = TimeoutSettings::default();
+        settings.methods.insert("query.run".into(), 1_000);
+        settings.methods.insert("db.".into(), 2_000);
+
+        assert_eq!(settings.timeout_for("query.run"), Duration::from_millis(1_000));
+        assert_eq!(settings.timeout_for("query.cancel"), Duration::from_millis(300_000));
+        assert_eq!(settings.timeout_for("db.getSchema"), Duration::from_millis(180_000));
+        assert_eq!(settings.timeout_for("db.list"), Duration::from_millis(2_000));
+        assert_eq!(settings.timeout_for("git.status"), Duration::from_millis(30_000));
+    }
+
+    #[test]
+    fn zero_timeouts_are_rejected() {
+        let timeouts = RequestTimeouts::load(None);
+        let mut settings = TimeoutSettings::default();
+        settings.methods.insert("health.ping".into(), 0);
+        assert!(timeouts.set_settings(settings).is_err());
+        assert!(timeouts
+            .set_settings(TimeoutSettings {
+                default_ms: 0,
+                methods: BTreeMap::new(),
+            })
+            .is_err());
+        assert_eq!(timeouts.timeout_for("health.ping"), Duration::from_millis(5_000));
+    }
+
+    #[test]
+    fn unanswered_requests_expire_and_late_responses_are_recognised() {
+        let script = Script::new().on("slow", |_| {
+            vec![Step::Sleep(Duration::from_millis(200)), Step::Respond(json!({ "ok": true }))]
+        });
+        let launcher = FakeLauncher::new(script);
+        let (child, stdout) = launcher.start().unwrap();
+        let process = BridgeProcess::new(Some(child));
+        let pending = PendingRequests::new();
+        let timeouts = RequestTimeouts::load(None);
+
+        let data = r#"{"id":1,"method":"slow"}"#;
+        let request = parse_request(data).unwrap();
+        let id = request.id.clone();
+        timeouts.set_timeout(&pending, &id, Duration::from_millis(20));
+        assert!(timeouts.overrides.lock().unwrap().is_empty());
+        process.send(&pending, request, id.clone(), data, "main", None).unwrap();
+        timeouts.set_timeout(&pending, &id, Duration::from_millis(20));
+        timeouts.prune_overrides(&pending);
+        assert!(timeouts.overrides.lock().unwrap().contains_key(&id));
+
+        std::thread::sleep(Duration::from_millis(50));
+        let expired = pending.take_where(|p| p.started_at.elapsed() >= timeouts.timeout_of(p));
+        assert_eq!(expired.len(), 1);
+        timeouts.remember(&expired[0], AbortReason::Timeout);
+        timeouts.prune_overrides(&pending);
+        assert!(timeouts.overrides.lock().unwrap().is_empty());
+
+        let frame: Value = serde_json::from_str(&stdout.recv_timeout(Duration::from_secs(5)).unwrap()).unwrap();
+        assert!(!pending.contains(&id));
+        let (late, _) = timeouts.take_late(&frame).expect("response is recognised as late");
+        assert_eq!(late.method, "slow");
+        assert_eq!(late.reason, AbortReason::Timeout);
+        assert!(!late.error);
+        assert!(timeouts.take_late(&frame).is_none());
+        assert_eq!(timeouts.late_responses().len(), 1);
+    }
+
+    #[test]
+    fn requests_within_their_timeout_are_left_alone() {
+        let pending = PendingRequests::new();
+        let timeouts = RequestTimeouts::load(None);
+        let request = parse_request(r#"{"id":1,"method":"db.getSchema"}"#).unwrap();
+        pending.track(request, RequestId::Number(1), "main", None);
+
+        assert!(pending.take_where(|p| p.started_at.elapsed() >= timeouts.timeout_of(p)).is_empty());
+        assert!(pending.contains(&RequestId::Number(1)));
+    }
+}
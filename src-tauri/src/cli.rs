@@ -0,0 +1,762 @@
+//! Headless `relwave rpc|db|migrate|project` subcommands.
+//! They spawn the bridge like the desktop app and apply the same policy, safety modes and audit log.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::audit::{self, AuditLog, Outcome};
+use crate::diagnostics;
+use crate::bridge::policy::{self, MAIN_WINDOW};
+use crate::bridge::{
+    BridgeChild, BridgeLauncher, LaunchConfig, ProcessLauncher, Redactor, RequestId, RequestTimeouts,
+    RpcRequest, SafetyGuard, SpawnReport, Verdict,
+};
+
+/// Success
+pub const EXIT_OK: i32 = 0;
+/// The bridge answered with an error
+pub const EXIT_REQUEST_FAILED: i32 = 1;
+/// Bad arguments
+pub const EXIT_USAGE: i32 = 2;
+/// Rejected by the method policy or the connection's safety mode
+pub const EXIT_DENIED: i32 = 3;
+/// The bridge could not be started, stopped responding or did not answer in time
+pub const EXIT_BRIDGE: i32 = 4;
+
+/// Recorded as the window of audit entries made from the command line
+const CLI_WINDOW: &str = "cli";
+
+const USAGE: &str = "\
+Usage:
+  relwave rpc <method> [<params-json>]
+  relwave db list
+  relwave migrate status --connection <id>
+  relwave migrate apply --connection <id>
+  relwave migrate rollback --connection <id> [--version <version>]
+  relwave project export --project <id> [--output <file>]
+
+Options:
+  --format <json|table>  Output format (default: table; `rpc` always prints JSON)
+  --yes                  Confirm changes on production connections
+  --verbose              Forward bridge logs to stderr
+
+Exit codes:
+  0 success, 1 request failed, 2 usage error, 3 denied by policy or safety mode,
+  4 bridge unavailable or timed out (see timeouts.json)";
+
+const SUBCOMMANDS: &[&str] = &["rpc", "db", "migrate", "project", "help"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Table,
+}
+
+#[derive(Debug)]
+struct CliError {
+    code: i32,
+    message: String,
+}
+
+impl CliError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn usage(message: impl Into<String>) -> Self {
+        Self::new(EXIT_USAGE, message)
+    }
+}
+
+/// Parsed command line: positional words plus `--flag [value]` options
+struct Args {
+    words: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse(raw: &[String]) -> Result<Self, CliError> {
+        const VALUE_OPTIONS: &[&str] = &["--format", "--connection", "--version", "--project", "--output"];
+        let mut words = Vec::new();
+        let mut options = Vec::new();
+        let mut iter = raw.iter();
+        while let Some(arg) = iter.next() {
+            if let Some((name, value)) = arg.split_once('=').filter(|_| arg.starts_with("--")) {
+                options.push((name.to_string(), Some(value.to_string())));
+            } else if VALUE_OPTIONS.contains(&arg.as_str()) {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| CliError::usage(format!("{} needs a value", arg)))?;
+                options.push((arg.clone(), Some(value.clone())));
+            } else if arg.starts_with("--") {
+                options.push((arg.clone(), None));
+            } else {
+                words.push(arg.clone());
+            }
+        }
+        Ok(Self { words, options })
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, v)| v.as_deref())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| n == name)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, CliError> {
+        self.value(name)
+            .ok_or_else(|| CliError::usage(format!("missing {} <value>", name)))
+    }
+
+    fn format(&self) -> Result<Format, CliError> {
+        match self.value("--format") {
+            None | Some("table") => Ok(Format::Table),
+            Some("json") => Ok(Format::Json),
+            Some(other) => Err(CliError::usage(format!("unknown format '{}'", other))),
+        }
+    }
+}
+
+/// A subcommand and its arguments
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help,
+    Rpc { method: String, params: Value },
+    DbList,
+    MigrateStatus { connection: String },
+    MigrateApply { connection: String },
+    MigrateRollback { connection: String, version: Option<String> },
+    ProjectExport { project: String, output: Option<String> },
+}
+
+impl Command {
+    fn parse(args: &Args) -> Result<Self, CliError> {
+        let words: Vec<&str> = args.words.iter().map(String::as_str).collect();
+        let connection = || args.required("--connection").map(str::to_string);
+
+        match words.as_slice() {
+            ["help"] | ["help", ..] => Ok(Self::Help),
+            ["rpc", method, rest @ ..] => {
+                let params = match rest {
+                    [] => Value::Null,
+                    [raw] => serde_json::from_str(raw)
+                        .map_err(|e| CliError::usage(format!("params are not valid JSON: {}", e)))?,
+                    _ => return Err(CliError::usage("rpc takes one params argument")),
+                };
+                Ok(Self::Rpc {
+                    method: method.to_string(),
+                    params,
+                })
+            }
+            ["db", "list"] => Ok(Self::DbList),
+            ["migrate", "status"] => Ok(Self::MigrateStatus { connection: connection()? }),
+            ["migrate", "apply"] => Ok(Self::MigrateApply { connection: connection()? }),
+            ["migrate", "rollback"] => Ok(Self::MigrateRollback {
+                connection: connection()?,
+                version: args.value("--version").map(str::to_string),
+            }),
+            ["project", "export"] => Ok(Self::ProjectExport {
+                project: args.required("--project")?.to_string(),
+                output: args.value("--output").map(str::to_string),
+            }),
+            _ => Err(CliError::usage(format!("unknown command '{}'", words.join(" ")))),
+        }
+    }
+}
+
+/// Run a headless subcommand if the arguments name one.
+/// Returns the process exit code, or `None` to start the desktop app.
+pub fn run(args: &[String], context: &tauri::Context) -> Option<i32> {
+    let subcommand = args.first()?;
+    if !SUBCOMMANDS.contains(&subcommand.as_str()) {
+        return None;
+    }
+    attach_console();
+
+    let code = match Args::parse(args).and_then(|args| dispatch(&args, context)) {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            EXIT_OK
+        }
+        Err(e) => {
+            if e.code == EXIT_USAGE {
+                eprintln!("error: {}\n\n{}", e.message, USAGE);
+            } else {
+                eprintln!("error: {}", e.message);
+            }
+            e.code
+        }
+    };
+    Some(code)
+}
+
+fn dispatch(args: &Args, context: &tauri::Context) -> Result<String, CliError> {
+    let format = args.format()?;
+    let command = Command::parse(args)?;
+    execute(&command, format, || Session::start(args, context))
+}
+
+/// Run `command` on a bridge started by `start`, returning what to print on stdout
+fn execute(
+    command: &Command,
+    format: Format,
+    start: impl FnOnce() -> Result<Session, CliError>,
+) -> Result<String, CliError> {
+    match command {
+        Command::Help => Ok(USAGE.to_string()),
+        Command::Rpc { method, params } => Ok(to_json(&start()?.call(method, params.clone())?)),
+        Command::DbList => {
+            let result = start()?.call("db.list", json!({}))?;
+            let databases = result.get("data").cloned().unwrap_or(Value::Null);
+            Ok(match format {
+                Format::Json => to_json(&databases),
+                Format::Table => table(
+                    &["ID", "NAME", "TYPE", "HOST", "DATABASE"],
+                    &rows(&databases, &["id", "name", "type", "host", "database"]),
+                ),
+            })
+        }
+        Command::MigrateStatus { connection } => {
+            let status = migration_status(&mut start()?, connection)?;
+            Ok(match format {
+                Format::Json => to_json(&status),
+                Format::Table => table(
+                    &["VERSION", "NAME", "STATUS"],
+                    &rows(&status["migrations"], &["version", "name", "status"]),
+                ),
+            })
+        }
+        Command::MigrateApply { connection } => {
+            let result = start()?.call("migration.applyMigrations", json!({ "dbId": connection }))?;
+            Ok(match format {
+                Format::Json => to_json(&result),
+                Format::Table => format!(
+                    "Applied {} migration(s)",
+                    result.get("count").and_then(Value::as_u64).unwrap_or(0)
+                ),
+            })
+        }
+        Command::MigrateRollback { connection, version } => {
+            let mut session = start()?;
+            let version = match version {
+                Some(v) => v.clone(),
+                None => latest_applied(&mut session, connection)?,
+            };
+            let result = session.call(
+                "migration.rollback",
+                json!({ "dbId": connection, "version": version }),
+            )?;
+            Ok(match format {
+                Format::Json => to_json(&json!({ "version": version, "result": result })),
+                Format::Table => format!("Rolled back {}", version),
+            })
+        }
+        Command::ProjectExport { project, output } => {
+            let result = start()?.call("project.export", json!({ "projectId": project }))?;
+            let bundle = result.get("data").cloned().unwrap_or(Value::Null);
+            let text = to_json(&bundle);
+            match output {
+                Some(path) => {
+                    std::fs::write(path, text).map_err(|e| {
+                        CliError::new(EXIT_REQUEST_FAILED, format!("failed to write {}: {}", path, e))
+                    })?;
+                    eprintln!("Exported project {} to {}", project, path);
+                    Ok(String::new())
+                }
+                None => Ok(text),
+            }
+        }
+    }
+}
+
+fn migration_status(session: &mut Session, db_id: &str) -> Result<Value, CliError> {
+    let result = session.call("migration.status", json!({ "dbId": db_id }))?;
+    Ok(merge_migration_status(db_id, &result))
+}
+
+/// Local and applied migrations from a `migration.status` result, merged into one list with a status per version
+fn merge_migration_status(db_id: &str, result: &Value) -> Value {
+    let info = result.get("result").cloned().unwrap_or(Value::Null);
+    let list = |key: &str| info["migrations"][key].as_array().cloned().unwrap_or_default();
+    let (local, applied) = (list("local"), list("applied"));
+
+    let version = |m: &Value| m.get("version").and_then(Value::as_str).map(str::to_string);
+    let applied_versions: Vec<String> = applied.iter().filter_map(version).collect();
+    let mut migrations: Vec<Value> = local
+        .iter()
+        .map(|m| {
+            let status = match version(m) {
+                Some(v) if applied_versions.contains(&v) => "applied",
+                _ => "pending",
+            };
+            json!({ "version": m.get("version"), "name": m.get("name"), "status": status })
+        })
+        .collect();
+    // Applied migrations whose file no longer exists locally
+    for m in &applied {
+        if !local.iter().any(|l| version(l) == version(m)) {
+            migrations.push(json!({ "version": m.get("version"), "name": m.get("name"), "status": "missing" }));
+        }
+    }
+    migrations.sort_by_key(|m| m["version"].as_str().unwrap_or_default().to_string());
+
+    json!({
+        "connection": db_id,
+        "baselined": info.get("baselined").cloned().unwrap_or(Value::Bool(false)),
+        "migrations": migrations,
+    })
+}
+
+fn latest_applied(session: &mut Session, db_id: &str) -> Result<String, CliError> {
+    let status = migration_status(session, db_id)?;
+    status["migrations"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|m| m["status"] == "applied")
+        .filter_map(|m| m["version"].as_str())
+        .max()
+        .map(str::to_string)
+        .ok_or_else(|| CliError::new(EXIT_REQUEST_FAILED, format!("no applied migrations on '{}'", db_id)))
+}
+
+/// A bridge driven over stdin/stdout for the lifetime of one command
+struct Session {
+    child: BridgeChild,
+    /// Bridge stdout lines, read on a thread so waiting for a response can time out
+    stdout: Receiver<String>,
+    timeouts: RequestTimeouts,
+    next_id: i64,
+    yes: bool,
+    redactor: Redactor,
+    safety: SafetyGuard,
+    audit: Option<AuditLog>,
+}
+
+impl Session {
+    fn start(args: &Args, context: &tauri::Context) -> Result<Self, CliError> {
+        let identifier = &context.config().identifier;
+        let resource_dir = tauri::utils::platform::resource_dir(context.package_info(), &tauri::Env::default()).ok();
+
+        let launch_config = match LaunchConfig::discover(app_dir(dirs::config_dir(), identifier)) {
+            Some(Ok(config)) => Some(config),
+            Some(Err(e)) => {
+                eprintln!("Ignoring {}", e);
+                None
+            }
+            None => None,
+        };
+
+        Self::launch(
+            &ProcessLauncher::new(resource_dir, launch_config),
+            args,
+            RequestTimeouts::load(app_dir(dirs::config_dir(), identifier).map(|d| d.join("timeouts.json"))),
+            SafetyGuard::load(
+                app_dir(dirs::config_dir(), identifier).map(|d| d.join("safety.json")),
+                diagnostics::relwave_home_under(dirs::home_dir()).map(|d| d.join("projects").join("index.json")),
+            ),
+            app_dir(dirs::data_dir(), identifier)
+                .zip(app_dir(dirs::config_dir(), identifier))
+                .map(|(data, config)| AuditLog::open(data.join("audit.log"), config.join("audit-head.json"))),
+        )
+    }
+
+    fn launch(
+        launcher: &dyn BridgeLauncher,
+        args: &Args,
+        timeouts: RequestTimeouts,
+        safety: SafetyGuard,
+        audit: Option<AuditLog>,
+    ) -> Result<Self, CliError> {
+        let launched = launcher
+            .launch(&mut SpawnReport::new())
+            .map_err(|e| CliError::new(EXIT_BRIDGE, e))?;
+        let stdout = launched
+            .stdout
+            .ok_or_else(|| CliError::new(EXIT_BRIDGE, "bridge stdout missing"))?;
+
+        // Drain stderr so the bridge never blocks on a full pipe
+        if let Some(stderr) = launched.stderr {
+            let verbose = args.flag("--verbose");
+            std::thread::spawn(move || {
+                let redactor = Redactor::new();
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    if verbose {
+                        eprintln!("{}", redactor.filter_stderr(line));
+                    }
+                }
+            });
+        }
+
+        let (lines, stdout_lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if lines.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child: launched.child,
+            stdout: stdout_lines,
+            timeouts,
+            next_id: 1,
+            yes: args.flag("--yes"),
+            redactor: Redactor::new(),
+            safety,
+            audit,
+        })
+    }
+
+    /// Send one request and wait for its response, returning the `result` payload
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, CliError> {
+        let request = RpcRequest {
+            id: RequestId::Number(self.next_id),
+            method: method.to_string(),
+            params,
+        };
+        self.next_id += 1;
+
+        policy::check(&request, MAIN_WINDOW).map_err(|e| CliError::new(EXIT_DENIED, e))?;
+        match self.safety.evaluate(&request) {
+            Verdict::Allow => {}
+            Verdict::Deny(reason) => return Err(CliError::new(EXIT_DENIED, reason)),
+            Verdict::Confirm(description) if !self.yes => {
+                return Err(CliError::new(
+                    EXIT_DENIED,
+                    format!("confirmation required: {} (pass --yes to confirm)", description),
+                ))
+            }
+            Verdict::Confirm(_) => {}
+        }
+        self.redactor.register_from_json(&request.params);
+
+        let frame = serde_json::to_string(&request).map_err(|e| CliError::new(EXIT_BRIDGE, e.to_string()))?;
+        let started_at = Instant::now();
+        self.audit(&request, started_at, Outcome::Sent);
+        let written = self
+            .child
+            .write_line(&frame)
+            .map_err(|e| format!("failed to write to bridge: {}", e));
+        if let Err(e) = written {
+            let error = json!({ "code": "BRIDGE_UNAVAILABLE", "message": e });
+            self.audit(&request, started_at, Outcome::Aborted("unsent", error));
+            return Err(CliError::new(EXIT_BRIDGE, e));
+        }
+
+        let timeout = self.timeouts.timeout_for(&request.method);
+        let response = match self.read_response(&request.id, timeout) {
+            Ok(response) => response,
+            Err((outcome, e)) => {
+                let error = json!({ "code": "BRIDGE_UNAVAILABLE", "message": e.message });
+                self.audit(&request, started_at, Outcome::Aborted(outcome, error));
+                return Err(e);
+            }
+        };
+        self.audit(&request, started_at, Outcome::Response(&response));
+
+        if let Some(error) = response.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            return Err(CliError::new(EXIT_REQUEST_FAILED, message));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    fn audit(&self, request: &RpcRequest, started_at: Instant, outcome: Outcome) {
+        let (Some(log), Some(record)) = (
+            &self.audit,
+            audit::record_for(request, CLI_WINDOW, started_at, outcome, Some(&self.redactor), Some(&self.safety)),
+        ) else {
+            return;
+        };
+        if let Err(e) = log.append(record) {
+            eprintln!("Failed to append audit entry: {}", e);
+        }
+    }
+
+    /// Read stdout until the response for `id`, skipping notifications and unrelated frames.
+    /// Fails with the audit outcome (`timeout` or `abandoned`) if none arrives within `timeout`.
+    fn read_response(&mut self, id: &RequestId, timeout: Duration) -> Result<Value, (&'static str, CliError)> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = match self.stdout.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => self.redactor.filter_stdout(line),
+                Err(RecvTimeoutError::Timeout) => {
+                    let message = format!("bridge did not answer within {} ms", timeout.as_millis());
+                    return Err(("timeout", CliError::new(EXIT_BRIDGE, message)));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(("abandoned", CliError::new(EXIT_BRIDGE, "bridge exited before responding")));
+                }
+            };
+            let Ok(frame) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if frame.get("method").is_some() {
+                continue;
+            }
+            let matches = frame
+                .get("id")
+                .and_then(|v| serde_json::from_value::<RequestId>(v.clone()).ok())
+                .is_some_and(|frame_id| &frame_id == id);
+            if matches {
+                return Ok(frame);
+            }
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // Closing stdin lets the bridge shut down on its own; kill it if it lingers
+        if let BridgeChild::Process(child) = &mut self.child {
+            drop(child.stdin.take());
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Same folder Tauri resolves for `app_config_dir` / `app_data_dir`
+fn app_dir(base: Option<PathBuf>, identifier: &str) -> Option<PathBuf> {
+    base.map(|d| d.join(identifier))
+}
+
+fn to_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+/// Pick `columns` out of every object in a JSON array, as display strings
+fn rows(list: &Value, columns: &[&str]) -> Vec<Vec<String>> {
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            columns
+                .iter()
+                .map(|c| match item.get(*c) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(s)) => s.clone(),
+                    Some(other) => other.to_string(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Columns padded to their widest cell, one line per row under the headers
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{:<width$}", c, width = w))
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+    std::iter::once(line(headers.to_vec()))
+        .chain(rows.iter().map(|row| line(row.iter().map(String::as_str).collect())))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Release builds use the Windows GUI subsystem; reattach to the calling terminal for output
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::fake::{FakeLauncher, Script, Step};
+    use crate::bridge::safety::ConnectionMode;
+    use std::time::Duration;
+
+    fn args(raw: &[&str]) -> Result<Args, CliError> {
+        Args::parse(&raw.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    fn command(raw: &[&str]) -> Result<Command, CliError> {
+        args(raw).and_then(|a| Command::parse(&a))
+    }
+
+    fn script() -> Script {
+        Script::new()
+            .on("db.list", |_| {
+                vec![Step::Respond(json!({ "data": [
+                    { "id": "db1", "name": "Local", "type": "sqlite", "host": null, "database": "app.db" },
+                    { "id": "db2", "name": "Reporting", "type": "postgres", "host": "10.0.0.5", "database": "reports" },
+                ] }))]
+            })
+            .on("migration.applyMigrations", |_| vec![Step::Respond(json!({ "count": 2 }))])
+            .on("project.export", |_| {
+                vec![Step::Fail {
+                    code: "NOT_FOUND",
+                    message: "project not found",
+                }]
+            })
+            .on("health.ping", |_| vec![Step::Sleep(Duration::from_secs(5))])
+    }
+
+    /// A session on `launcher` that gives up on requests after 200 ms, as `Session::start` would set it up
+    fn session(launcher: &FakeLauncher, raw: &[&str]) -> Result<Session, CliError> {
+        let timeouts = RequestTimeouts::load(None);
+        let mut settings = timeouts.settings();
+        settings.default_ms = 200;
+        settings.methods.clear();
+        timeouts.set_settings(settings).unwrap();
+        let safety = SafetyGuard::load(None, None);
+        safety.set_mode("replica", ConnectionMode::ReadOnly).unwrap();
+        safety.set_mode("prod", ConnectionMode::Production).unwrap();
+        Session::launch(launcher, &args(raw).unwrap(), timeouts, safety, None)
+    }
+
+    /// Exit code of running `raw` against a fake bridge
+    fn exit_code(launcher: &FakeLauncher, raw: &[&str]) -> i32 {
+        match command(raw).and_then(|c| execute(&c, Format::Json, || session(launcher, raw))) {
+            Ok(_) => EXIT_OK,
+            Err(e) => e.code,
+        }
+    }
+
+    #[test]
+    fn commands_and_options_are_parsed() {
+        assert_eq!(command(&["help", "migrate"]).unwrap(), Command::Help);
+        assert_eq!(
+            command(&["rpc", "db.list", r#"{"limit":1}"#]).unwrap(),
+            Command::Rpc {
+                method: "db.list".into(),
+                params: json!({ "limit": 1 }),
+            }
+        );
+        assert_eq!(
+            command(&["migrate", "rollback", "--connection", "db1", "--version=002"]).unwrap(),
+            Command::MigrateRollback {
+                connection: "db1".into(),
+                version: Some("002".into()),
+            }
+        );
+        assert_eq!(
+            command(&["project", "export", "--project", "p1"]).unwrap(),
+            Command::ProjectExport {
+                project: "p1".into(),
+                output: None,
+            }
+        );
+        assert_eq!(args(&["db", "list", "--format", "json"]).unwrap().format().unwrap(), Format::Json);
+        assert_eq!(args(&["db", "list"]).unwrap().format().unwrap(), Format::Table);
+    }
+
+    #[test]
+    fn bad_arguments_are_usage_errors() {
+        let usage = |raw: &[&str]| command(raw).unwrap_err();
+        for (raw, message) in [
+            (&["db"][..], "unknown command 'db'"),
+            (&["migrate", "status"], "missing --connection <value>"),
+            (&["project", "export", "--project"], "--project needs a value"),
+            (&["rpc", "db.list", "{"], "params are not valid JSON"),
+            (&["rpc", "db.list", "{}", "{}"], "rpc takes one params argument"),
+        ] {
+            let error = usage(raw);
+            assert_eq!(error.code, EXIT_USAGE);
+            assert!(error.message.starts_with(message), "{}", error.message);
+        }
+        assert_eq!(args(&["db", "list", "--format", "xml"]).unwrap().format().unwrap_err().code, EXIT_USAGE);
+    }
+
+    #[test]
+    fn results_render_as_tables_or_json() {
+        let launcher = FakeLauncher::new(script());
+        let table = execute(&Command::DbList, Format::Table, || session(&launcher, &[])).unwrap();
+        assert_eq!(
+            table,
+            "ID   NAME       TYPE      HOST      DATABASE\n\
+             db1  Local      sqlite              app.db\n\
+             db2  Reporting  postgres  10.0.0.5  reports"
+        );
+
+        let output = execute(&Command::DbList, Format::Json, || session(&launcher, &[])).unwrap();
+        let databases: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(databases[1]["host"], "10.0.0.5");
+
+        let apply = Command::MigrateApply { connection: "db1".into() };
+        let applied = execute(&apply, Format::Table, || session(&launcher, &[])).unwrap();
+        assert_eq!(applied, "Applied 2 migration(s)");
+    }
+
+    #[test]
+    fn migration_status_merges_local_and_applied_versions() {
+        let result = json!({ "result": { "baselined": true, "migrations": {
+            "local": [{ "version": "002", "name": "add_index" }, { "version": "001", "name": "init" }],
+            "applied": [{ "version": "001", "name": "init" }, { "version": "000", "name": "removed" }],
+        } } });
+        let status = merge_migration_status("db1", &result);
+        assert_eq!(status["connection"], "db1");
+        assert_eq!(status["baselined"], true);
+        assert_eq!(
+            status["migrations"],
+            json!([
+                { "version": "000", "name": "removed", "status": "missing" },
+                { "version": "001", "name": "init", "status": "applied" },
+                { "version": "002", "name": "add_index", "status": "pending" },
+            ])
+        );
+        assert_eq!(merge_migration_status("db1", &Value::Null)["baselined"], false);
+    }
+
+    #[test]
+    fn exit_codes_follow_the_outcome() {
+        let launcher = FakeLauncher::new(script());
+        assert_eq!(exit_code(&launcher, &["db", "list"]), EXIT_OK);
+        assert_eq!(exit_code(&launcher, &["project", "export", "--project", "p1"]), EXIT_REQUEST_FAILED);
+        assert_eq!(exit_code(&launcher, &["migrate", "apply", "--connection", "replica"]), EXIT_DENIED);
+        assert_eq!(exit_code(&launcher, &["migrate", "apply", "--connection", "prod"]), EXIT_DENIED);
+        assert_eq!(exit_code(&launcher, &["migrate", "apply", "--connection", "prod", "--yes"]), EXIT_OK);
+        assert_eq!(exit_code(&launcher, &["rpc", "db.list", "{}"]), EXIT_OK);
+        assert_eq!(exit_code(&launcher, &["rpc", "health.ping"]), EXIT_BRIDGE);
+        // Not on the method allowlist
+        assert_eq!(exit_code(&launcher, &["rpc", "fs.readFile"]), EXIT_DENIED);
+
+        // Denied requests never reach the bridge
+        let sent: Vec<Value> = launcher
+            .received()
+            .into_iter()
+            .filter(|f| f["params"]["dbId"] == "replica" || f["method"] == "fs.readFile")
+            .collect();
+        assert!(sent.is_empty());
+
+        let unavailable = FakeLauncher::new(script()).failing(1);
+        assert_eq!(exit_code(&unavailable, &["db", "list"]), EXIT_BRIDGE);
+    }
+}
@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Manager, State, Webview};
+
+use crate::bridge::{BridgeProcess, Redactor};
+use crate::diagnostics;
+
+/// Event emitted when a page loads and crash reports from earlier runs are waiting
+pub const CRASH_REPORTS_EVENT: &str = "crash-reports-found";
+
+/// Folder under the app data dir that holds crash reports
+const CRASH_DIR: &str = "crash-reports";
+
+/// Subfolder that dismissed reports are moved to
+const SENT_DIR: &str = "sent";
+
+/// Set once the app is built, so the hook can describe the bridge
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeState {
+    pub status: String,
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub id: String,
+    pub timestamp: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: String,
+    pub backtrace: String,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub bridge: Option<BridgeState>,
+}
+
+/// Where crash reports live for this app
+pub struct CrashReports {
+    dir: PathBuf,
+}
+
+impl CrashReports {
+    /// Reports that have not been dismissed yet, oldest first
+    pub fn unsent(&self) -> Vec<CrashReport> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut reports: Vec<CrashReport> = entries
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| std::fs::read_to_string(p).ok())
+            .filter_map(|raw| serde_json::from_str(&raw).ok())
+            .collect();
+        reports.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        reports
+    }
+
+    /// Move a report out of the unsent list
+    pub fn dismiss(&self, id: &str) -> Result<(), String> {
+        if id.is_empty() || id.contains(['/', '\\', '.']) {
+            return Err(format!("invalid crash report id: {}", id));
+        }
+        let file = format!("{}.json", id);
+        let sent = self.dir.join(SENT_DIR);
+        std::fs::create_dir_all(&sent).map_err(|e| format!("failed to create {}: {}", sent.display(), e))?;
+        std::fs::rename(self.dir.join(&file), sent.join(&file))
+            .map_err(|e| format!("failed to dismiss crash report {}: {}", id, e))
+    }
+}
+
+/// Crash report folder, resolved the same way as `app_data_dir` so it works before the app is built
+fn crash_dir(context: &tauri::Context) -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join(&context.config().identifier).join(CRASH_DIR))
+}
+
+/// Install the panic hook. Must run before the Tauri builder so startup panics are captured too.
+pub fn install(context: &tauri::Context) -> CrashReports {
+    let dir = crash_dir(context).unwrap_or_else(|| std::env::temp_dir().join("relwave").join(CRASH_DIR));
+    let version = context.package_info().version.to_string();
+
+    let hook_dir = dir.clone();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let report = build_report(info, &version);
+        match write_report(&hook_dir, &report) {
+            Ok(path) => log(format!("Crash report written to {}", path.display())),
+            Err(e) => log(format!("Failed to write crash report: {}", e)),
+        }
+    }));
+
+    CrashReports { dir }
+}
+
+/// Log from the hook: to the shell log once the app exists, to stderr before that
+fn log(message: String) {
+    match APP_HANDLE.get() {
+        Some(app_handle) => diagnostics::try_shell_log(app_handle, message),
+        None => eprintln!("{}", message),
+    }
+}
+
+/// Give the hook access to app state once the app exists
+pub fn attach(app_handle: &AppHandle) {
+    let _ = APP_HANDLE.set(app_handle.clone());
+}
+
+fn build_report(info: &PanicHookInfo<'_>, version: &str) -> CrashReport {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".into());
+    let message = redact_message(&message);
+    let thread = std::thread::current();
+
+    CrashReport {
+        id: format!(
+            "crash-{}-{}",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        ),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        message,
+        location: info.location().map(|l| l.to_string()),
+        thread: thread.name().unwrap_or("<unnamed>").to_string(),
+        backtrace: Backtrace::force_capture().to_string(),
+        app_version: version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        bridge: APP_HANDLE.get().and_then(bridge_state),
+    }
+}
+
+/// Panic messages often quote connection strings; mask them before they are written to disk.
+/// Before the app is built there are no registered secrets, but credential patterns are still masked.
+fn redact_message(message: &str) -> String {
+    match APP_HANDLE.get().and_then(|a| a.try_state::<Redactor>()) {
+        Some(redactor) => redactor.try_redact(message),
+        None => Redactor::new().redact(message),
+    }
+}
+
+/// Bridge status without blocking: the panicking thread may hold the process lock
+fn bridge_state(app_handle: &AppHandle) -> Option<BridgeState> {
+    let process = app_handle.try_state::<BridgeProcess>()?;
+    let Ok(mut guard) = process.0.try_lock() else {
+        return Some(BridgeState {
+            status: "unknown (locked)".into(),
+            pid: None,
+        });
+    };
+    Some(match guard.as_mut() {
+        Some(child) => BridgeState {
+            status: match child.try_wait() {
+                Ok(Some(status)) => format!("exited:{}", status),
+                Ok(None) => "running".into(),
+                Err(e) => format!("error checking status: {}", e),
+            },
+            pid: child.id(),
+        },
+        None => BridgeState {
+            status: "not_started".into(),
+            pid: None,
+        },
+    })
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.json", report.id));
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Tell a freshly loaded page about crash reports left by earlier runs
+pub fn notify_pending(webview: &Webview) {
+    let Some(reports) = webview.try_state::<CrashReports>() else {
+        return;
+    };
+    let unsent = reports.unsent();
+    if !unsent.is_empty() {
+        let _ = webview.emit_to(webview.label(), CRASH_REPORTS_EVENT, unsent);
+    }
+}
+
+/// List crash reports that have not been dismissed
+#[tauri::command]
+pub fn crash_reports_list(reports: State<'_, CrashReports>) -> Vec<CrashReport> {
+    reports.unsent()
+}
+
+/// Mark a crash report as handled so it is no longer listed
+#[tauri::command]
+pub fn crash_reports_dismiss(id: String, reports: State<'_, CrashReports>) -> Result<(), String> {
+    reports.dismiss(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(id: &str, timestamp: &str) -> CrashReport {
+        CrashReport {
+            id: id.to_string(),
+            timestamp: timestamp.to_string(),
+            message: "boom".into(),
+            location: None,
+            thread: "main".into(),
+            backtrace: String::new(),
+            app_version: "1.0.0".into(),
+            os: "linux".into(),
+            arch: "x86_64".into(),
+            bridge: None,
+        }
+    }
+
+    #[test]
+    fn reports_are_listed_oldest_first_until_dismissed() {
+        let dir = tempfile::tempdir().unwrap();
+        let reports = CrashReports {
+            dir: dir.path().to_path_buf(),
+        };
+        write_report(dir.path(), &report("crash-b", "2026-01-02T00:00:00Z")).unwrap();
+        write_report(dir.path(), &report("crash-a", "2026-01-01T00:00:00Z")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a report").unwrap();
+
+        let ids: Vec<String> = reports.unsent().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["crash-a", "crash-b"]);
+
+        reports.dismiss("crash-a").unwrap();
+        assert!(dir.path().join(SENT_DIR).join("crash-a.json").is_file());
+        assert_eq!(reports.unsent().len(), 1);
+        assert!(reports.dismiss("crash-a").is_err());
+    }
+
+    #[test]
+    fn dismissing_rejects_ids_that_leave_the_folder() {
+        let reports = CrashReports {
+            dir: std::env::temp_dir(),
+        };
+        for id in ["", "../crash", "sent/crash", "crash.json", "..\\crash"] {
+            assert!(reports.dismiss(id).unwrap_err().starts_with("invalid"), "{}", id);
+        }
+    }
+
+    #[test]
+    fn panic_messages_are_redacted_before_the_app_exists() {
+        let message = redact_message("failed to connect to postgres://admin:hunter2@db:5432/app");
+        assert!(!message.contains("hunter2"), "{}", message);
+    }
+}
@@ -0,0 +1,475 @@
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use url::Url;
+
+use crate::bridge;
+use crate::bridge::policy::MAIN_WINDOW;
+
+/// Scheme registered for RelWave links (see `plugins.deep-link` in `tauri.conf.json`)
+pub const SCHEME: &str = "relwave";
+
+/// Event emitted to the main window when a link arrives while the app is running
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+
+/// Links that are not confirmed within this time are dropped together with their credentials
+const PENDING_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// What a link asks for, with every credential removed
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DeepLinkAction {
+    /// `relwave://connect?url=...&name=...`
+    #[serde(rename_all = "camelCase")]
+    Connect {
+        name: String,
+        db_type: String,
+        host: Option<String>,
+        port: Option<u16>,
+        user: Option<String>,
+        database: String,
+        sslmode: Option<String>,
+        /// The link carried a password; it is held by the shell and passed to the bridge on confirm
+        has_password: bool,
+    },
+    /// `relwave://project/import?path=...[&databaseId=...]`
+    #[serde(rename_all = "camelCase")]
+    ProjectImport {
+        path: String,
+        database_id: Option<String>,
+    },
+}
+
+/// A link waiting for the user to confirm it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkRequest {
+    pub id: String,
+    /// The link as received, with credentials masked
+    pub url: String,
+    #[serde(flatten)]
+    pub action: DeepLinkAction,
+}
+
+struct PendingLink {
+    request: DeepLinkRequest,
+    password: Option<String>,
+    received_at: Instant,
+}
+
+/// Parsed links awaiting confirmation; passwords never leave the shell until the bridge stores them
+#[derive(Default)]
+pub struct DeepLinks(Mutex<HashMap<String, PendingLink>>);
+
+impl DeepLinks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&self, request: DeepLinkRequest, password: Option<String>) {
+        let mut links = self.0.lock().unwrap();
+        links.retain(|_, l| l.received_at.elapsed() < PENDING_TTL);
+        links.insert(
+            request.id.clone(),
+            PendingLink {
+                request,
+                password,
+                received_at: Instant::now(),
+            },
+        );
+    }
+
+    fn take(&self, id: &str) -> Option<PendingLink> {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(id)
+            .filter(|l| l.received_at.elapsed() < PENDING_TTL)
+    }
+
+    fn list(&self) -> Vec<DeepLinkRequest> {
+        let mut links = self.0.lock().unwrap();
+        links.retain(|_, l| l.received_at.elapsed() < PENDING_TTL);
+        let mut pending: Vec<_> = links.values().collect();
+        pending.sort_by_key(|l| l.received_at);
+        pending.iter().map(|l| l.request.clone()).collect()
+    }
+}
+
+fn decode(raw: &str) -> String {
+    percent_decode_str(raw).decode_utf8_lossy().into_owned()
+}
+
+fn query_param(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+        .filter(|v| !v.is_empty())
+}
+
+/// The link with any password in the nested connection URL masked
+fn masked(link: &Url) -> String {
+    let mut masked = link.clone();
+    let pairs: Vec<(String, String)> = link
+        .query_pairs()
+        .map(|(k, v)| {
+            let v = match Url::parse(&v) {
+                Ok(mut inner) if k == "url" && inner.password().is_some() => {
+                    let _ = inner.set_password(Some(bridge::REDACTED));
+                    inner.to_string()
+                }
+                _ => v.into_owned(),
+            };
+            (k.into_owned(), v)
+        })
+        .collect();
+    masked.query_pairs_mut().clear().extend_pairs(pairs);
+    masked.to_string()
+}
+
+/// Split a shared connection URL into `db.add` fields and the password
+fn parse_connection(raw: &str, name: Option<String>) -> Result<(DeepLinkAction, Option<String>), String> {
+    let url = Url::parse(raw).map_err(|e| format!("invalid connection URL: {}", e))?;
+    let db_type = match url.scheme() {
+        "postgres" | "postgresql" => "postgres",
+        "mysql" => "mysql",
+        "mariadb" => "mariadb",
+        "sqlite" | "file" => "sqlite",
+        other => return Err(format!("unsupported database scheme '{}'", other)),
+    };
+
+    if db_type == "sqlite" {
+        let path = decode(url.path());
+        if path.is_empty() {
+            return Err("sqlite URL has no file path".into());
+        }
+        let name = name.unwrap_or_else(|| {
+            std::path::Path::new(&path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "SQLite database".into())
+        });
+        let action = DeepLinkAction::Connect {
+            name,
+            db_type: db_type.into(),
+            host: None,
+            port: None,
+            user: None,
+            database: path,
+            sslmode: None,
+            has_password: false,
+        };
+        return Ok((action, None));
+    }
+
+    let host = url
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .ok_or("connection URL has no host")?
+        .to_string();
+    let database = decode(url.path().trim_start_matches('/'));
+    if database.is_empty() {
+        return Err("connection URL has no database name".into());
+    }
+    let user = Some(decode(url.username())).filter(|u| !u.is_empty());
+    let password = url.password().map(decode).filter(|p| !p.is_empty());
+    let port = url.port().or(match db_type {
+        "postgres" => Some(5432),
+        _ => Some(3306),
+    });
+
+    let action = DeepLinkAction::Connect {
+        name: name.unwrap_or_else(|| format!("{}@{}", database, host)),
+        db_type: db_type.into(),
+        host: Some(host),
+        port,
+        user,
+        database,
+        sslmode: query_param(&url, "sslmode"),
+        has_password: password.is_some(),
+    };
+    Ok((action, password))
+}
+
+/// Parse a `relwave://` link into the action it requests and any password it carried
+pub fn parse(link: &str) -> Result<(DeepLinkRequest, Option<String>), String> {
+    let url = Url::parse(link).map_err(|e| format!("invalid link: {}", e))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("not a {}:// link", SCHEME));
+    }
+    // `relwave://connect?..` parses with `connect` as the host; also accept `relwave:connect?..`
+    let route = format!("{}{}", url.host_str().unwrap_or_default(), url.path());
+    let route = route.trim_matches('/');
+
+    let (action, password) = match route {
+        "connect" => {
+            let raw = query_param(&url, "url").ok_or("connect link needs a url parameter")?;
+            parse_connection(&raw, query_param(&url, "name"))?
+        }
+        "project/import" => {
+            let path = query_param(&url, "path").ok_or("project import link needs a path parameter")?;
+            let action = DeepLinkAction::ProjectImport {
+                path,
+                database_id: query_param(&url, "databaseId"),
+            };
+            (action, None)
+        }
+        other => return Err(format!("unknown link '{}'", other)),
+    };
+
+    let request = DeepLinkRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        url: masked(&url),
+        action,
+    };
+    Ok((request, password))
+}
+
+/// The question put to the user before a link is carried out
+fn describe(action: &DeepLinkAction) -> String {
+    match action {
+        DeepLinkAction::Connect {
+            name,
+            db_type,
+            host,
+            port,
+            user,
+            database,
+            has_password,
+            ..
+        } => {
+            let mut target = String::new();
+            if let Some(user) = user {
+                target.push_str(&format!("{}@", user));
+            }
+            if let Some(host) = host {
+                target.push_str(host);
+                if let Some(port) = port {
+                    target.push_str(&format!(":{}", port));
+                }
+                target.push('/');
+            }
+            target.push_str(database);
+            let password = if *has_password { " The link includes a password, which will be saved." } else { "" };
+            format!("Add the {} connection \"{}\" to {}?{}", db_type, name, target, password)
+        }
+        DeepLinkAction::ProjectImport { path, .. } => format!("Import the project at {}?", path),
+    }
+}
+
+/// Parse incoming links and hold them for confirmation.
+/// Set `notify` when the main window is already loaded and should be told right away.
+pub fn receive<S: AsRef<str>>(app_handle: &AppHandle, links: &[S], notify: bool) {
+    let Some(state) = app_handle.try_state::<DeepLinks>() else {
+        return;
+    };
+    for link in links.iter().map(AsRef::as_ref) {
+        if !link.starts_with(&format!("{}:", SCHEME)) {
+            continue;
+        }
+        match parse(link) {
+            Ok((request, password)) => {
+                if let Some(redactor) = app_handle.try_state::<bridge::Redactor>() {
+                    if let Some(password) = &password {
+                        redactor.register_secret(password);
+                    }
+                }
+                state.insert(request.clone(), password);
+                if notify {
+                    let _ = app_handle.emit_to("main", DEEP_LINK_EVENT, request);
+                }
+            }
+            Err(e) => crate::diagnostics::shell_log(app_handle, format!("Ignoring deep link: {}", e)),
+        }
+    }
+}
+
+/// Links received at startup or while the app was busy, still waiting for confirmation
+#[tauri::command]
+pub fn deep_link_pending(links: State<'_, DeepLinks>) -> Vec<DeepLinkRequest> {
+    links.list()
+}
+
+/// Drop a link without acting on it
+#[tauri::command]
+pub fn deep_link_dismiss(id: String, links: State<'_, DeepLinks>) {
+    links.take(&id);
+}
+
+/// Carry out a link once the user confirms it in a native dialog: add the connection (password goes
+/// straight to the bridge credential store) or import the project. `database_id` overrides the link's
+/// database for imports. Only the main window may act on links, as it alone manages connections and projects.
+#[tauri::command]
+pub async fn deep_link_confirm(
+    id: String,
+    database_id: Option<String>,
+    window: WebviewWindow,
+    app_handle: AppHandle,
+    links: State<'_, DeepLinks>,
+) -> Result<Value, String> {
+    if window.label() != MAIN_WINDOW {
+        return Err("deep links can only be confirmed from the main window".into());
+    }
+    let link = links
+        .take(&id)
+        .ok_or("deep link not found or expired")?;
+    let question = format!("{}\n\nFrom link: {}", describe(&link.request.action), link.request.url);
+
+    let (method, params) = match link.request.action {
+        DeepLinkAction::Connect {
+            name,
+            db_type,
+            host,
+            port,
+            user,
+            database,
+            sslmode,
+            ..
+        } => {
+            let mut params = json!({
+                "name": name,
+                "type": db_type,
+                "host": host,
+                "port": port,
+                "user": user,
+                "database": database,
+                "password": link.password,
+            });
+            if let Some(sslmode) = sslmode {
+                params["ssl"] = json!(sslmode != "disable");
+                params["sslmode"] = json!(sslmode);
+            }
+            if let Some(fields) = params.as_object_mut() {
+                fields.retain(|_, v| !v.is_null());
+            }
+            ("db.add", params)
+        }
+        DeepLinkAction::ProjectImport { path, database_id: linked } => {
+            let database_id = database_id
+                .or(linked)
+                .ok_or("choose a database connection for the imported project")?;
+            ("project.import", json!({ "sourcePath": path, "databaseId": database_id }))
+        }
+    };
+
+    let dialog = window
+        .dialog()
+        .message(question)
+        .title("Open RelWave link")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            if method == "db.add" { "Add connection" } else { "Import project" }.into(),
+            "Cancel".into(),
+        ))
+        .parent(&window);
+    let confirmed = tauri::async_runtime::spawn_blocking(move || dialog.blocking_show())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !confirmed {
+        return Err("link was not confirmed".into());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || bridge::request(&app_handle, method, params))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connect_links_keep_the_password_out_of_the_request() {
+        let (request, password) =
+            parse("relwave://connect?url=postgres%3A%2F%2Fadmin%3Ap%2540ss%40db.example.com%2Fshop%3Fsslmode%3Drequire")
+                .unwrap();
+        assert_eq!(password.as_deref(), Some("p@ss"));
+        assert!(!request.url.contains("p%40ss") && !request.url.contains("p@ss"), "{}", request.url);
+        match request.action {
+            DeepLinkAction::Connect {
+                name,
+                db_type,
+                host,
+                port,
+                user,
+                database,
+                sslmode,
+                has_password,
+            } => {
+                assert_eq!(name, "shop@db.example.com");
+                assert_eq!(db_type, "postgres");
+                assert_eq!(host.as_deref(), Some("db.example.com"));
+                assert_eq!(port, Some(5432));
+                assert_eq!(user.as_deref(), Some("admin"));
+                assert_eq!(database, "shop");
+                assert_eq!(sslmode.as_deref(), Some("require"));
+                assert!(has_password);
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sqlite_and_project_links_are_understood() {
+        let (request, password) = parse("relwave:connect?url=sqlite%3A%2F%2F%2Fdata%2Fmy%2520app.db").unwrap();
+        assert!(password.is_none());
+        assert!(matches!(
+            request.action,
+            DeepLinkAction::Connect { ref name, ref database, .. } if name == "my app" && database == "/data/my app.db"
+        ));
+
+        let (request, _) = parse("relwave://project/import?path=%2Frepos%2Fshop&databaseId=db1").unwrap();
+        assert!(matches!(
+            request.action,
+            DeepLinkAction::ProjectImport { ref path, ref database_id } if path == "/repos/shop" && database_id.as_deref() == Some("db1")
+        ));
+    }
+
+    #[test]
+    fn confirmations_describe_the_link_without_secrets() {
+        let (request, _) =
+            parse("relwave://connect?url=postgres%3A%2F%2Fadmin%3Asecret%40db.example.com%3A5433%2Fshop&name=Shop").unwrap();
+        let question = describe(&request.action);
+        assert_eq!(
+            question,
+            "Add the postgres connection \"Shop\" to admin@db.example.com:5433/shop? \
+             The link includes a password, which will be saved."
+        );
+        assert!(!question.contains("secret"));
+
+        let (request, _) = parse("relwave://project/import?path=%2Frepos%2Fshop").unwrap();
+        assert_eq!(describe(&request.action), "Import the project at /repos/shop?");
+    }
+
+    #[test]
+    fn bad_links_are_rejected() {
+        for link in [
+            "https://connect?url=postgres://h/db",
+            "relwave://delete?id=1",
+            "relwave://connect",
+            "relwave://connect?url=redis%3A%2F%2Fhost%2F0",
+            "relwave://connect?url=postgres%3A%2F%2Fhost",
+            "relwave://project/import",
+        ] {
+            assert!(parse(link).is_err(), "{}", link);
+        }
+    }
+
+    #[test]
+    fn pending_links_are_taken_once() {
+        let links = DeepLinks::new();
+        let (request, password) = parse("relwave://project/import?path=%2Frepos%2Fshop").unwrap();
+        let id = request.id.clone();
+        links.insert(request, password);
+
+        assert_eq!(links.list().len(), 1);
+        assert!(links.take(&id).is_some());
+        assert!(links.take(&id).is_none());
+        assert!(links.list().is_empty());
+    }
+}
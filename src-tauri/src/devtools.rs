@@ -1,40 +1,44 @@
-use tauri::{AppHandle, Manager, WebviewWindow};
+use tauri::{AppHandle, State, WebviewWindow};
+use tauri_plugin_dialog::DialogExt;
 
-/// Open the devtools for the main window
+use crate::bridge::{to_har, TrafficEntry, TrafficFilter, TrafficInspector};
+
+/// Open the devtools for the calling window
 #[tauri::command]
-pub fn open_devtools(app_handle: AppHandle) -> Result<(), String> {
-    if let Some(window) = app_handle.get_webview_window("main") {
-        window.open_devtools();
-        Ok(())
-    } else {
-        Err("Main window not found".into())
-    }
+pub fn open_devtools(window: WebviewWindow) -> Result<(), String> {
+    window.open_devtools();
+    Ok(())
 }
 
-/// Close the devtools for the main window
+/// Close the devtools for the calling window
 #[tauri::command]
-pub fn close_devtools(app_handle: AppHandle) -> Result<(), String> {
-    if let Some(window) = app_handle.get_webview_window("main") {
-        window.close_devtools();
-        Ok(())
-    } else {
-        Err("Main window not found".into())
-    }
+pub fn close_devtools(window: WebviewWindow) -> Result<(), String> {
+    window.close_devtools();
+    Ok(())
 }
 
-/// Check if devtools are open
+/// Check if devtools are open for the calling window
 #[tauri::command]
-pub fn is_devtools_open(app_handle: AppHandle) -> Result<bool, String> {
-    if let Some(window) = app_handle.get_webview_window("main") {
-        Ok(window.is_devtools_open())
+pub fn is_devtools_open(window: WebviewWindow) -> Result<bool, String> {
+    Ok(window.is_devtools_open())
+}
+
+/// Open the devtools if they are closed, close them if they are open
+pub fn toggle_devtools(window: &WebviewWindow) {
+    if window.is_devtools_open() {
+        window.close_devtools();
     } else {
-        Err("Main window not found".into())
+        window.open_devtools();
     }
 }
 
 /// Reload the webview
 #[tauri::command]
 pub fn reload_webview(window: WebviewWindow) -> Result<(), String> {
+    reload(&window)
+}
+
+pub fn reload(window: &WebviewWindow) -> Result<(), String> {
     window
         .eval("window.location.reload()")
         .map_err(|e| e.to_string())
@@ -43,6 +47,10 @@ pub fn reload_webview(window: WebviewWindow) -> Result<(), String> {
 /// Navigate back in history
 #[tauri::command]
 pub fn navigate_back(window: WebviewWindow) -> Result<(), String> {
+    back(&window)
+}
+
+pub fn back(window: &WebviewWindow) -> Result<(), String> {
     window
         .eval("window.history.back()")
         .map_err(|e| e.to_string())
@@ -51,7 +59,61 @@ pub fn navigate_back(window: WebviewWindow) -> Result<(), String> {
 /// Navigate forward in history
 #[tauri::command]
 pub fn navigate_forward(window: WebviewWindow) -> Result<(), String> {
+    forward(&window)
+}
+
+pub fn forward(window: &WebviewWindow) -> Result<(), String> {
     window
         .eval("window.history.forward()")
         .map_err(|e| e.to_string())
 }
+
+/// Recent bridge frames matching `filter`, oldest first
+#[tauri::command]
+pub fn devtools_traffic_query(filter: Option<TrafficFilter>, inspector: State<'_, TrafficInspector>) -> Vec<TrafficEntry> {
+    inspector.query(&filter.unwrap_or_default())
+}
+
+/// Forget the recorded bridge traffic
+#[tauri::command]
+pub fn devtools_traffic_clear(inspector: State<'_, TrafficInspector>) {
+    inspector.clear();
+}
+
+/// Turn capture of (redacted) frame payloads on or off; metadata is always recorded
+#[tauri::command]
+pub fn devtools_traffic_set_capture(enabled: bool, inspector: State<'_, TrafficInspector>) {
+    inspector.set_capture_payloads(enabled);
+}
+
+/// Write the recorded traffic matching `filter` as a HAR-style JSON log where the user picks in a save dialog.
+/// Returns the path written, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn devtools_traffic_export(
+    filter: Option<TrafficFilter>,
+    window: WebviewWindow,
+    app_handle: AppHandle,
+    inspector: State<'_, TrafficInspector>,
+) -> Result<Option<String>, String> {
+    let entries = inspector.query(&filter.unwrap_or_default());
+    let json = serde_json::to_string_pretty(&to_har(&app_handle, &entries)).map_err(|e| e.to_string())?;
+
+    let dialog = window
+        .dialog()
+        .file()
+        .add_filter("HAR log", &["har", "json"])
+        .set_file_name(format!(
+            "relwave-traffic-{}.har",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ))
+        .set_parent(&window);
+    let chosen = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file())
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(path) = chosen else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("failed to write traffic log: {}", e))?;
+    Ok(Some(path.display().to_string()))
+}
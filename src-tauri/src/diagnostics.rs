@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+use tauri_plugin_dialog::DialogExt;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::bridge::{BridgeHistory, Redactor};
+use crate::crash::CrashReports;
+
+/// Number of log lines kept per source
+const LOG_BUFFER_LEN: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    Bridge,
+    Shell,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    pub timestamp: String,
+    pub source: LogSource,
+    pub line: String,
+}
+
+/// Recent bridge stderr and shell log lines, kept in memory for diagnostic bundles
+#[derive(Default)]
+pub struct LogBuffer(Mutex<LogQueues>);
+
+#[derive(Default)]
+struct LogQueues {
+    bridge: VecDeque<LogLine>,
+    shell: VecDeque<LogLine>,
+}
+
+impl LogQueues {
+    fn of(&mut self, source: LogSource) -> &mut VecDeque<LogLine> {
+        match source {
+            LogSource::Bridge => &mut self.bridge,
+            LogSource::Shell => &mut self.shell,
+        }
+    }
+}
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, source: LogSource, line: &str) {
+        Self::append(&mut self.0.lock().unwrap(), source, line);
+    }
+
+    /// `push` unless another thread holds the buffer; false if the line was dropped
+    pub fn try_push(&self, source: LogSource, line: &str) -> bool {
+        match self.0.try_lock() {
+            Ok(mut queues) => {
+                Self::append(&mut queues, source, line);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn append(queues: &mut LogQueues, source: LogSource, line: &str) {
+        let lines = queues.of(source);
+        if lines.len() >= LOG_BUFFER_LEN {
+            lines.pop_front();
+        }
+        lines.push_back(LogLine {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            source,
+            line: line.to_string(),
+        });
+    }
+
+    pub fn lines(&self, source: LogSource) -> Vec<LogLine> {
+        self.0.lock().unwrap().of(source).iter().cloned().collect()
+    }
+}
+
+/// Keep a (already redacted) log line for diagnostics
+pub fn record_log(app_handle: &AppHandle, source: LogSource, line: &str) {
+    if let Some(buffer) = app_handle.try_state::<LogBuffer>() {
+        buffer.push(source, line);
+    }
+}
+
+/// Log a shell message to stderr, redacted, and keep it for diagnostics
+pub fn shell_log(app_handle: &AppHandle, message: impl AsRef<str>) {
+    let message = message.as_ref();
+    let message = match app_handle.try_state::<Redactor>() {
+        Some(redactor) => redactor.redact(message),
+        None => message.to_string(),
+    };
+    eprintln!("{}", message);
+    record_log(app_handle, LogSource::Shell, &message);
+}
+
+/// `shell_log` for the panic hook, which must not wait for a lock the panicking thread may hold
+pub fn try_shell_log(app_handle: &AppHandle, message: impl AsRef<str>) {
+    let message = message.as_ref();
+    let message = match app_handle.try_state::<Redactor>() {
+        Some(redactor) => redactor.try_redact(message),
+        None => message.to_string(),
+    };
+    eprintln!("{}", message);
+    if let Some(buffer) = app_handle.try_state::<LogBuffer>() {
+        buffer.try_push(LogSource::Shell, &message);
+    }
+}
+
+/// One file of a diagnostic bundle, as shown to the user before saving
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticFile {
+    pub name: String,
+    pub size: usize,
+    pub content: String,
+}
+
+impl DiagnosticFile {
+    fn new(name: &str, content: String) -> Self {
+        Self {
+            name: name.to_string(),
+            size: content.len(),
+            content,
+        }
+    }
+
+    fn json(name: &str, value: &Value) -> Self {
+        Self::new(name, serde_json::to_string_pretty(value).unwrap_or_default())
+    }
+}
+
+/// The files the last preview showed, which are exactly what an export writes
+#[derive(Default)]
+pub struct PreviewedBundle(Mutex<Option<Vec<DiagnosticFile>>>);
+
+impl PreviewedBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// RelWave config folder shared with the bridge (`RELWAVE_HOME` or the per-user default)
+pub fn relwave_home(app_handle: &AppHandle) -> Option<PathBuf> {
+    relwave_home_under(app_handle.path().home_dir().ok())
+}
+
+/// `RELWAVE_HOME`, or the bridge's default config folder inside the user's `home`
+pub fn relwave_home_under(home: Option<PathBuf>) -> Option<PathBuf> {
+    if let Ok(home) = std::env::var("RELWAVE_HOME") {
+        return Some(PathBuf::from(home));
+    }
+    let home = home?;
+    if cfg!(target_os = "windows") {
+        Some(home.join("AppData").join("Roaming").join("relwave"))
+    } else {
+        Some(home.join(".relwave"))
+    }
+}
+
+/// Read a JSON config file with every secret masked
+fn sanitized_config(app_handle: &AppHandle, path: PathBuf) -> String {
+    let Ok(raw) = std::fs::read_to_string(&path) else {
+        return format!("(not found: {})", path.display());
+    };
+    let redactor = app_handle.try_state::<Redactor>();
+    match serde_json::from_str::<Value>(&raw) {
+        Ok(mut value) => {
+            if let Some(redactor) = &redactor {
+                redactor.redact_json(&mut value);
+            }
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        }
+        Err(_) => match &redactor {
+            Some(redactor) => redactor.redact(&raw),
+            None => "(unparseable, omitted)".into(),
+        },
+    }
+}
+
+fn log_text(lines: &[LogLine]) -> String {
+    lines
+        .iter()
+        .map(|l| format!("{} {}\n", l.timestamp, l.line))
+        .collect()
+}
+
+/// Write the buffered bridge and shell logs to the app log folder and return the folder
+pub fn write_logs(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle.path().app_log_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create log dir: {}", e))?;
+    let logs = app_handle.try_state::<LogBuffer>().ok_or("logs not available")?;
+    for (name, source) in [("bridge.log", LogSource::Bridge), ("shell.log", LogSource::Shell)] {
+        std::fs::write(dir.join(name), log_text(&logs.lines(source)))
+            .map_err(|e| format!("failed to write {}: {}", name, e))?;
+    }
+    Ok(dir)
+}
+
+/// Gather every file that goes into a diagnostic bundle
+fn collect(app_handle: &AppHandle) -> Vec<DiagnosticFile> {
+    let package = app_handle.package_info();
+    let mut files = vec![DiagnosticFile::json(
+        "manifest.json",
+        &json!({
+            "app": package.name,
+            "version": package.version.to_string(),
+            "tauriVersion": tauri::VERSION,
+            "os": std::env::consts::OS,
+            "osFamily": std::env::consts::FAMILY,
+            "arch": std::env::consts::ARCH,
+            "debugBuild": cfg!(debug_assertions),
+            "generatedAt": chrono::Utc::now().to_rfc3339(),
+        }),
+    )];
+
+    if let Some(history) = app_handle.try_state::<BridgeHistory>() {
+        files.push(DiagnosticFile::json(
+            "bridge/spawn-report.json",
+            &json!(history.spawn_report()),
+        ));
+        files.push(DiagnosticFile::json(
+            "bridge/status-history.json",
+            &json!(history.statuses()),
+        ));
+    }
+
+    if let Some(logs) = app_handle.try_state::<LogBuffer>() {
+        files.push(DiagnosticFile::new(
+            "logs/bridge.log",
+            log_text(&logs.lines(LogSource::Bridge)),
+        ));
+        files.push(DiagnosticFile::new(
+            "logs/shell.log",
+            log_text(&logs.lines(LogSource::Shell)),
+        ));
+    }
+
+    if let Some(reports) = app_handle.try_state::<CrashReports>() {
+        files.push(DiagnosticFile::json("crash-reports.json", &json!(reports.unsent())));
+    }
+
+    if let Some(home) = relwave_home(app_handle) {
+        files.push(DiagnosticFile::new(
+            "config/databases.json",
+            sanitized_config(app_handle, home.join("databases.json")),
+        ));
+        files.push(DiagnosticFile::new(
+            "config/projects-index.json",
+            sanitized_config(app_handle, home.join("projects").join("index.json")),
+        ));
+    }
+
+    files
+}
+
+fn write_zip(path: &Path, files: &[DiagnosticFile]) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("failed to create bundle: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+    for f in files {
+        zip.start_file(f.name.as_str(), options)
+            .map_err(|e| format!("failed to add {}: {}", f.name, e))?;
+        zip.write_all(f.content.as_bytes())
+            .map_err(|e| format!("failed to write {}: {}", f.name, e))?;
+    }
+    zip.finish().map_err(|e| format!("failed to finish bundle: {}", e))?;
+    Ok(())
+}
+
+/// List the files (with contents) that `diagnostics_export` will write, for review
+#[tauri::command]
+pub fn diagnostics_preview(app_handle: AppHandle, previewed: State<'_, PreviewedBundle>) -> Vec<DiagnosticFile> {
+    let files = collect(&app_handle);
+    *previewed.0.lock().unwrap() = Some(files.clone());
+    files
+}
+
+/// Write the files of the last preview to a diagnostic bundle (zip archive) where the user picks in a save dialog.
+/// Returns the path written, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn diagnostics_export(
+    window: WebviewWindow,
+    previewed: State<'_, PreviewedBundle>,
+) -> Result<Option<String>, String> {
+    let files = previewed
+        .0
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("preview the diagnostic bundle before exporting it")?;
+
+    let dialog = window
+        .dialog()
+        .file()
+        .add_filter("Zip archive", &["zip"])
+        .set_file_name(format!(
+            "relwave-diagnostics-{}.zip",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ))
+        .set_parent(&window);
+    let chosen = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file())
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(path) = chosen else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+    write_zip(&path, &files)?;
+    Ok(Some(path.display().to_string()))
+}
+
+/// Recent log lines for a source, oldest first
+#[tauri::command]
+pub fn diagnostics_logs(source: LogSource, logs: State<'_, LogBuffer>) -> Vec<LogLine> {
+    logs.lines(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn each_source_keeps_its_own_recent_lines() {
+        let logs = LogBuffer::new();
+        for i in 0..LOG_BUFFER_LEN + 5 {
+            logs.push(LogSource::Bridge, &format!("bridge {}", i));
+        }
+        logs.push(LogSource::Shell, "shell");
+
+        let bridge = logs.lines(LogSource::Bridge);
+        assert_eq!(bridge.len(), LOG_BUFFER_LEN);
+        assert_eq!(bridge[0].line, "bridge 5");
+        assert_eq!(logs.lines(LogSource::Shell).len(), 1);
+        assert!(log_text(&bridge).ends_with(&format!(" bridge {}\n", LOG_BUFFER_LEN + 4)));
+    }
+
+    #[test]
+    fn the_bundle_holds_exactly_the_given_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.zip");
+        let files = vec![
+            DiagnosticFile::json("manifest.json", &json!({ "app": "relwave" })),
+            DiagnosticFile::new("logs/shell.log", "started\n".into()),
+        ];
+        assert_eq!(files[1].size, 8);
+        write_zip(&path, &files).unwrap();
+
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let names: Vec<String> = zip.file_names().map(str::to_string).collect();
+        assert_eq!(names.len(), 2);
+        for file in &files {
+            let mut content = String::new();
+            zip.by_name(&file.name).unwrap().read_to_string(&mut content).unwrap();
+            assert_eq!(content, file.content);
+        }
+    }
+
+    #[test]
+    fn relwave_home_defaults_to_the_bridge_folder() {
+        if std::env::var_os("RELWAVE_HOME").is_some() {
+            return;
+        }
+        let home = relwave_home_under(Some(PathBuf::from("home"))).unwrap();
+        assert!(home.starts_with("home"));
+        assert!(home.ends_with(if cfg!(target_os = "windows") { "relwave" } else { ".relwave" }));
+        assert!(relwave_home_under(None).is_none());
+    }
+}
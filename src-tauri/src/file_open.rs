@@ -0,0 +1,168 @@
+use serde::Serialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, State};
+
+/// Event emitted to the main window for SQLite files passed to a running instance
+pub const OPEN_SQLITE_FILE_EVENT: &str = "open-sqlite-file";
+
+/// Extensions registered as file associations in `tauri.conf.json`
+pub const SQLITE_EXTENSIONS: &[&str] = &["db", "sqlite", "sqlite3", "s3db"];
+
+/// First 16 bytes of every SQLite 3 database
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileAction {
+    /// An existing SQLite database: open (or reuse) a connection for it
+    Open,
+    /// Missing or empty file with a SQLite extension: create a new database there
+    Create,
+    /// Not a SQLite database; `error` says why
+    Invalid,
+}
+
+/// A SQLite file the frontend should connect to
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteFileRequest {
+    pub path: String,
+    pub name: String,
+    pub action: FileAction,
+    pub error: Option<String>,
+}
+
+/// Files passed on the command line at startup, until the frontend asks for them
+#[derive(Default)]
+pub struct PendingFileOpens(Mutex<Vec<SqliteFileRequest>>);
+
+impl PendingFileOpens {
+    /// Validate the file arguments of this launch and keep them for the frontend
+    pub fn from_args(args: &[String]) -> Self {
+        let cwd = std::env::current_dir().ok();
+        Self(Mutex::new(sqlite_requests(args, cwd.as_deref())))
+    }
+}
+
+fn has_sqlite_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SQLITE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Check the SQLite header of `path` and decide what the frontend should do with it
+fn inspect(path: &Path) -> (FileAction, Option<String>) {
+    let mut file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (FileAction::Create, None),
+        Err(e) => return (FileAction::Invalid, Some(format!("cannot read file: {}", e))),
+    };
+    if path.is_dir() {
+        return (FileAction::Invalid, Some("is a directory".into()));
+    }
+
+    let mut header = [0u8; 16];
+    let mut read = 0;
+    while read < header.len() {
+        match file.read(&mut header[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) => return (FileAction::Invalid, Some(format!("cannot read file: {}", e))),
+        }
+    }
+
+    match read {
+        0 => (FileAction::Create, None),
+        16 if &header == SQLITE_HEADER => (FileAction::Open, None),
+        _ => (FileAction::Invalid, Some("not a SQLite 3 database (bad header)".into())),
+    }
+}
+
+/// Turn launch arguments into SQLite file requests.
+/// Flags are skipped; relative paths resolve against `cwd` (the caller's directory for forwarded launches).
+pub fn sqlite_requests(args: &[String], cwd: Option<&Path>) -> Vec<SqliteFileRequest> {
+    args.iter()
+        .filter(|a| !a.starts_with('-') && !a.contains("://"))
+        .filter_map(|arg| {
+            let path = PathBuf::from(arg);
+            let path = match cwd {
+                Some(cwd) if path.is_relative() => cwd.join(path),
+                _ => path,
+            };
+            let (action, error) = inspect(&path);
+            // Only claim extensionless or unknown files if they really are SQLite databases
+            if !has_sqlite_extension(&path) && action != FileAction::Open {
+                return None;
+            }
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            Some(SqliteFileRequest {
+                name: path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "SQLite database".into()),
+                path: path.display().to_string(),
+                action,
+                error,
+            })
+        })
+        .collect()
+}
+
+/// Tell the main window about SQLite files passed to an already running instance
+pub fn emit_forwarded(app_handle: &AppHandle, args: &[String], cwd: Option<&Path>) {
+    for request in sqlite_requests(args, cwd) {
+        let _ = app_handle.emit_to("main", OPEN_SQLITE_FILE_EVENT, request);
+    }
+}
+
+/// Take the SQLite files passed on the command line at startup (each is returned once)
+#[tauri::command]
+pub fn file_open_pending(pending: State<'_, PendingFileOpens>) -> Vec<SqliteFileRequest> {
+    std::mem::take(&mut *pending.0.lock().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_inspected_by_their_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("app.db");
+        let mut content = SQLITE_HEADER.to_vec();
+        content.extend_from_slice(&[0; 84]);
+        std::fs::write(&db, content).unwrap();
+        let empty = dir.path().join("empty.sqlite");
+        std::fs::write(&empty, "").unwrap();
+        let text = dir.path().join("notes.db");
+        std::fs::write(&text, "hello").unwrap();
+
+        assert_eq!(inspect(&db), (FileAction::Open, None));
+        assert_eq!(inspect(&empty), (FileAction::Create, None));
+        assert_eq!(inspect(&dir.path().join("new.sqlite3")), (FileAction::Create, None));
+        assert_eq!(inspect(&text).0, FileAction::Invalid);
+        assert_eq!(inspect(dir.path()), (FileAction::Invalid, Some("is a directory".into())));
+    }
+
+    #[test]
+    fn only_sqlite_files_are_taken_from_the_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Chinook.DB"), SQLITE_HEADER).unwrap();
+        std::fs::write(dir.path().join("data"), SQLITE_HEADER).unwrap();
+        std::fs::write(dir.path().join("readme.txt"), "hello").unwrap();
+        let args: Vec<String> = ["--verbose", "relwave://open", "Chinook.DB", "data", "readme.txt", "new.sqlite"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+
+        let requests = sqlite_requests(&args, Some(dir.path()));
+        let summary: Vec<(&str, FileAction)> = requests.iter().map(|r| (r.name.as_str(), r.action)).collect();
+        assert_eq!(
+            summary,
+            vec![("Chinook", FileAction::Open), ("data", FileAction::Open), ("new", FileAction::Create)]
+        );
+        assert!(Path::new(&requests[0].path).is_absolute());
+    }
+}
@@ -1,2 +1,4 @@
-// This file is intentionally left as an empty module.
 // The real application entry point is main.rs — do not add commands here.
+// The library only exposes a typed client for driving the bridge from Rust tools and tests.
+
+pub mod api;
@@ -1,47 +1,209 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod audit;
 mod bridge;
+mod cli;
+mod crash;
+mod deep_link;
 mod devtools;
+mod diagnostics;
+mod file_open;
+mod notifications;
+mod shortcuts;
+mod single_instance;
+mod tray;
+mod window_state;
+mod windows;
 
-use bridge::{bridge_restart, bridge_kill, bridge_status, bridge_write, BridgeProcess};
-use devtools::{close_devtools, is_devtools_open, navigate_back, navigate_forward, open_devtools, reload_webview};
+use audit::{audit_query, audit_verify, AuditLog};
+use bridge::{bridge_cancel, bridge_late_responses, bridge_metrics, bridge_metrics_report, bridge_policy, bridge_record_start, bridge_record_stop, bridge_recording_status, bridge_replay_start, bridge_replay_stop, bridge_restart, bridge_kill, bridge_set_raw_output, bridge_status, bridge_timeouts_get, bridge_timeouts_set, bridge_write, safety_confirm, safety_list_modes, safety_set_mode, BridgeProcess, BridgeHistory, BridgeMetrics, BridgeRecorder, BridgeRouter, PendingRequests, Redactor, RequestTimeouts, SafetyGuard, TrafficInspector};
+use crash::{crash_reports_dismiss, crash_reports_list};
+use deep_link::{deep_link_confirm, deep_link_dismiss, deep_link_pending, DeepLinks};
+use devtools::{close_devtools, devtools_traffic_clear, devtools_traffic_export, devtools_traffic_query, devtools_traffic_set_capture, is_devtools_open, navigate_back, navigate_forward, open_devtools, reload_webview};
+use diagnostics::{diagnostics_export, diagnostics_logs, diagnostics_preview, LogBuffer, PreviewedBundle};
+use file_open::{file_open_pending, PendingFileOpens};
+use notifications::{notifications_settings_get, notifications_settings_set, Notifier};
+use tauri::webview::PageLoadEvent;
+use tauri_plugin_deep_link::DeepLinkExt;
+use shortcuts::{shortcuts_list, shortcuts_reload, shortcuts_run, Shortcuts};
 use tauri::Manager;
+use tray::{tray_settings_get, tray_settings_set, Tray};
+use window_state::{workspace_session_get, workspace_session_save, WindowState};
+use windows::{window_open_database, window_open_project};
 
 fn main() {
+    let context = tauri::generate_context!();
+    // Installed first so a panic anywhere (including app startup) leaves a crash report
+    let crash_reports = crash::install(&context);
+
+    // `relwave <subcommand> ...` runs headless and never opens a window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args, &context) {
+        std::process::exit(code);
+    }
+
+    // A second launch hands its arguments to the running instance instead of starting another bridge
+    let mut instance = match single_instance::acquire(&context, &args) {
+        single_instance::Instance::Primary(guard) => guard,
+        single_instance::Instance::Forwarded => return,
+    };
+
     tauri::Builder::default()
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
-        .setup(|app| {
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(shortcuts::handle)
+                .build(),
+        )
+        .setup(move |app| {
+            // Managed first so startup problems and the bridge's first lines are filtered and kept
+            app.manage(Redactor::new());
+            app.manage(LogBuffer::new());
+            crash::attach(app.handle());
+            app.manage(crash_reports);
+            instance.listen(app.handle().clone());
+            app.manage(instance);
+            app.manage(PendingFileOpens::from_args(&args));
+            app.manage(DeepLinks::new());
+            // Linux and Windows deliver links as launch arguments; macOS sends them as events
+            deep_link::receive(app.handle(), &args, false);
+            let link_handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                let urls: Vec<String> = event.urls().iter().map(|u| u.to_string()).collect();
+                deep_link::receive(&link_handle, &urls, true);
+            });
+            // Registers the xdg handler for AppImages and development builds
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            if let Err(e) = app.deep_link().register_all() {
+                diagnostics::shell_log(app.handle(), format!("Failed to register relwave:// links: {}", e));
+            }
+            let window_state_path = app.path().app_config_dir().ok().map(|d| d.join("window-state.json"));
+            app.manage(WindowState::load(window_state_path));
+            if let Some(window) = app.get_webview_window("main") {
+                app.state::<WindowState>().restore(&window);
+            }
+            app.manage(PreviewedBundle::new());
+            app.manage(BridgeHistory::new());
+            let safety_path = app.path().app_config_dir().ok().map(|d| d.join("safety.json"));
+            let projects_index = diagnostics::relwave_home(app.handle()).map(|d| d.join("projects").join("index.json"));
+            app.manage(SafetyGuard::load(safety_path, projects_index));
+            app.manage(PendingRequests::new());
+            let timeouts_path = app.path().app_config_dir().ok().map(|d| d.join("timeouts.json"));
+            app.manage(RequestTimeouts::load(timeouts_path));
+            bridge::start_watchdog(app.handle());
+            let notifications_path = app.path().app_config_dir().ok().map(|d| d.join("notifications.json"));
+            app.manage(Notifier::load(notifications_path));
+            app.manage(BridgeRouter::new());
+            app.manage(TrafficInspector::new());
+            app.manage(BridgeMetrics::new());
+            app.manage(BridgeRecorder::from_env(app.handle(), &app.package_info().version.to_string()));
+            if let (Ok(data_dir), Ok(config_dir)) = (app.path().app_data_dir(), app.path().app_config_dir()) {
+                app.manage(AuditLog::open(data_dir.join("audit.log"), config_dir.join("audit-head.json")));
+            }
+            let launch_config = match bridge::LaunchConfig::discover(app.path().app_config_dir().ok()) {
+                Some(Ok(config)) => Some(config),
+                Some(Err(e)) => {
+                    diagnostics::shell_log(app.handle(), format!("Ignoring {}", e));
+                    None
+                }
+                None => None,
+            };
+            let launcher = bridge::ProcessLauncher::new(app.path().resource_dir().ok(), launch_config);
+            app.manage(bridge::Launcher(Box::new(launcher)));
             let handle = app.handle().clone();
             let child = bridge::init(handle);
             app.manage(BridgeProcess::new(child));
+            let keymap_path = app.path().app_config_dir().ok().map(|d| d.join("keymap.json"));
+            app.manage(Shortcuts::new(keymap_path));
+            app.state::<Shortcuts>().apply(app.handle());
+            let tray_path = app.path().app_config_dir().ok().map(|d| d.join("tray.json"));
+            app.manage(Tray::load(tray_path));
+            if let Err(e) = tray::init(app.handle()) {
+                diagnostics::shell_log(app.handle(), format!("Failed to create tray icon: {}", e));
+            }
             Ok(())
         })
+        .on_page_load(|webview, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                crash::notify_pending(webview);
+            }
+        })
+        .on_window_event(|window, event| {
+            window_state::on_window_event(window, event);
+            tray::on_window_event(window, event);
+            // Sessions opened by a closed window would otherwise stay alive in the bridge
+            if let tauri::WindowEvent::Destroyed = event {
+                bridge::window_closed(window.app_handle(), window.label());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             bridge_write,
+            bridge_cancel,
+            bridge_timeouts_get,
+            bridge_timeouts_set,
+            bridge_late_responses,
             bridge_kill,
             bridge_restart,
             bridge_status,
+            bridge_set_raw_output,
+            bridge_policy,
+            bridge_metrics,
+            bridge_metrics_report,
+            bridge_record_start,
+            bridge_record_stop,
+            bridge_recording_status,
+            bridge_replay_start,
+            bridge_replay_stop,
+            safety_list_modes,
+            safety_set_mode,
+            safety_confirm,
+            audit_query,
+            audit_verify,
+            diagnostics_preview,
+            diagnostics_export,
+            diagnostics_logs,
+            crash_reports_list,
+            crash_reports_dismiss,
+            file_open_pending,
+            deep_link_pending,
+            deep_link_confirm,
+            deep_link_dismiss,
+            workspace_session_get,
+            workspace_session_save,
+            tray_settings_get,
+            tray_settings_set,
+            notifications_settings_get,
+            notifications_settings_set,
+            shortcuts_list,
+            shortcuts_reload,
+            shortcuts_run,
+            window_open_database,
+            window_open_project,
             open_devtools,
             close_devtools,
             is_devtools_open,
+            devtools_traffic_query,
+            devtools_traffic_clear,
+            devtools_traffic_set_capture,
+            devtools_traffic_export,
             reload_webview,
             navigate_back,
             navigate_forward
         ])
-        .build(tauri::generate_context!())
+        .build(context)
         .expect("error while building tauri application")
         .run(|app_handle, event| {
             if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app_handle.try_state::<WindowState>() {
+                    state.flush(app_handle);
+                }
                 // Kill bridge process on app exit to prevent orphaned processes
                 if let Some(state) = app_handle.try_state::<BridgeProcess>() {
-                    let mut guard = state.0.lock().unwrap();
-                    if let Some(mut child) = guard.take() {
-                        let _ = child.kill();
-                        let _ = child.wait();
-                    }
+                    let _ = state.shutdown();
                 }
             }
         });
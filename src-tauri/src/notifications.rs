@@ -0,0 +1,337 @@
+use notify_rust::{Notification, NotificationResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::bridge::PendingRequest;
+
+/// Methods whose response only arrives once the work is done.
+/// `query.run` answers right away and finishes with a `query.done` or `query.error` notification instead.
+const LONG_RUNNING_METHODS: &[&str] = &["migration.applyMigrations", "git.push", "git.pull"];
+
+/// Longest error message shown in a notification
+const MAX_MESSAGE_LEN: usize = 200;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// Operations that finish faster than this never notify
+    pub threshold_secs: u64,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_secs: 10,
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Whether notifications are on and an operation of this length is long enough to notify about
+    fn allows(&self, elapsed: Duration) -> bool {
+        self.enabled && elapsed >= Duration::from_secs(self.threshold_secs)
+    }
+}
+
+/// A query started from a window, waiting for its `query.done` or `query.error`
+struct RunningQuery {
+    window: String,
+    started_at: Instant,
+}
+
+/// Watches long-running bridge work and raises a desktop notification when it ends while its window is in the background.
+/// Settings are persisted to `notifications.json`.
+pub struct Notifier {
+    queries: Mutex<HashMap<String, RunningQuery>>,
+    settings: Mutex<NotificationSettings>,
+    path: Option<PathBuf>,
+}
+
+impl Notifier {
+    /// Load settings from `path`, starting with the defaults if it is missing or unreadable
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let settings = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str::<NotificationSettings>(&s).ok())
+            .unwrap_or_default();
+
+        Self {
+            queries: Mutex::new(HashMap::new()),
+            settings: Mutex::new(settings),
+            path,
+        }
+    }
+
+    fn set_settings(&self, settings: NotificationSettings) -> Result<(), String> {
+        *self.settings.lock().unwrap() = settings;
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create config dir: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("failed to save notification settings: {}", e))
+    }
+
+    /// Forget the queries a closed window was waiting on
+    pub fn drop_window(&self, window: &str) {
+        self.queries.lock().unwrap().retain(|_, query| query.window != window);
+    }
+
+    /// Forget every running query, as when the bridge that ran them has gone
+    pub fn clear(&self) {
+        self.queries.lock().unwrap().clear();
+    }
+
+    /// Whether an operation of this length, run from `window`, deserves a notification
+    fn should_notify(&self, app_handle: &AppHandle, window: &str, elapsed: Duration) -> bool {
+        if !self.settings.lock().unwrap().allows(elapsed) {
+            return false;
+        }
+        // Closed windows have nothing to focus; visible, focused ones don't need telling
+        app_handle
+            .get_webview_window(window)
+            .is_some_and(|w| !w.is_focused().unwrap_or(false) || !w.is_visible().unwrap_or(true))
+    }
+}
+
+fn format_duration(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    if secs < 60.0 {
+        format!("{:.1} s", secs)
+    } else {
+        format!("{}m {:02}s", elapsed.as_secs() / 60, elapsed.as_secs() % 60)
+    }
+}
+
+fn plural(count: u64, word: &str) -> String {
+    if count == 1 {
+        format!("1 {}", word)
+    } else {
+        format!("{} {}s", count, word)
+    }
+}
+
+fn error_message(error: &Value) -> String {
+    let message = error
+        .get("message")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| error.to_string());
+    match message.char_indices().nth(MAX_MESSAGE_LEN) {
+        Some((end, _)) => format!("{}…", &message[..end]),
+        None => message,
+    }
+}
+
+/// Show a native notification; clicking it focuses `window`
+fn show(app_handle: &AppHandle, window: &str, summary: &str, body: &str) {
+    let mut notification = Notification::new();
+    notification.summary(summary).body(body).appname(&app_handle.package_info().name);
+    #[cfg(target_os = "windows")]
+    notification.app_id(&app_handle.config().identifier);
+    // XDG servers only report body clicks for notifications that offer the "default" action
+    #[cfg(all(unix, not(target_os = "macos")))]
+    notification.action("default", "Show");
+
+    let app_handle = app_handle.clone();
+    let window = window.to_string();
+    std::thread::spawn(move || {
+        let handle = match notification.show() {
+            Ok(handle) => handle,
+            Err(e) => {
+                crate::diagnostics::shell_log(&app_handle, format!("Failed to show notification: {}", e));
+                return;
+            }
+        };
+        let _ = handle.wait_for_response(|response: &NotificationResponse| {
+            let clicked = match response {
+                NotificationResponse::Default => true,
+                NotificationResponse::Action(action) => action == "default",
+                _ => false,
+            };
+            if clicked {
+                crate::windows::focus(&app_handle, &window);
+            }
+        });
+    });
+}
+
+/// Follow query sessions through `query.started` and the notification that ends them
+pub fn observe_notification(app_handle: &AppHandle, frame: &Value, window: Option<&str>) {
+    let Some(notifier) = app_handle.try_state::<Notifier>() else {
+        return;
+    };
+    let method = frame.get("method").and_then(Value::as_str).unwrap_or_default();
+    let params = frame.get("params").cloned().unwrap_or(Value::Null);
+    let Some(session) = params.get("sessionId").and_then(Value::as_str) else {
+        return;
+    };
+
+    match method {
+        "query.started" => {
+            if let Some(window) = window {
+                notifier.queries.lock().unwrap().insert(
+                    session.to_string(),
+                    RunningQuery {
+                        window: window.to_string(),
+                        started_at: Instant::now(),
+                    },
+                );
+            }
+        }
+        "query.done" | "query.error" => {
+            let Some(query) = notifier.queries.lock().unwrap().remove(session) else {
+                return;
+            };
+            let elapsed = params
+                .get("timeMs")
+                .and_then(Value::as_u64)
+                .map(Duration::from_millis)
+                .unwrap_or_else(|| query.started_at.elapsed());
+            if !notifier.should_notify(app_handle, &query.window, elapsed) {
+                return;
+            }
+
+            let (summary, body) = match (method, params.get("status").and_then(Value::as_str)) {
+                ("query.error", _) => (
+                    "Query failed",
+                    format!(
+                        "After {}: {}",
+                        format_duration(elapsed),
+                        error_message(params.get("error").unwrap_or(&Value::Null))
+                    ),
+                ),
+                (_, Some("cancelled")) => ("Query cancelled", format!("Cancelled after {}", format_duration(elapsed))),
+                _ => {
+                    let rows = params.get("rows").and_then(Value::as_u64).unwrap_or(0);
+                    (
+                        "Query finished",
+                        format!("{} in {}", plural(rows, "row"), format_duration(elapsed)),
+                    )
+                }
+            };
+            show(app_handle, &query.window, summary, &body);
+        }
+        _ => {}
+    }
+}
+
+/// Notify about a long-running request that has just been answered
+pub fn observe_response(app_handle: &AppHandle, pending: &PendingRequest, frame: &Value) {
+    let method = pending.request.method.as_str();
+    if !LONG_RUNNING_METHODS.contains(&method) {
+        return;
+    }
+    let Some(notifier) = app_handle.try_state::<Notifier>() else {
+        return;
+    };
+    let elapsed = pending.started_at.elapsed();
+    if !notifier.should_notify(app_handle, &pending.window, elapsed) {
+        return;
+    }
+
+    let result = frame.get("result");
+    let error = frame
+        .get("error")
+        .or_else(|| result.filter(|r| r.get("ok") == Some(&Value::Bool(false))).and_then(|r| r.get("error")));
+    let duration = format_duration(elapsed);
+    let (summary, body) = match (method, error) {
+        ("migration.applyMigrations", None) => {
+            let count = result.and_then(|r| r.get("count")).and_then(Value::as_u64).unwrap_or(0);
+            ("Migrations applied", format!("{} in {}", plural(count, "migration"), duration))
+        }
+        ("migration.applyMigrations", Some(error)) => ("Migration failed", format!("After {}: {}", duration, error_message(error))),
+        ("git.push", None) => ("Push finished", format!("Pushed in {}", duration)),
+        ("git.push", Some(error)) => ("Push failed", format!("After {}: {}", duration, error_message(error))),
+        (_, None) => ("Pull finished", format!("Pulled in {}", duration)),
+        (_, Some(error)) => ("Pull failed", format!("After {}: {}", duration, error_message(error))),
+    };
+    show(app_handle, &pending.window, summary, &body);
+}
+
+/// Current notification settings
+#[tauri::command]
+pub fn notifications_settings_get(notifier: State<'_, Notifier>) -> NotificationSettings {
+    *notifier.settings.lock().unwrap()
+}
+
+/// Change and persist the notification settings
+#[tauri::command]
+pub fn notifications_settings_set(settings: NotificationSettings, notifier: State<'_, Notifier>) -> Result<(), String> {
+    notifier.set_settings(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn durations_are_shown_in_seconds_then_minutes() {
+        assert_eq!(format_duration(Duration::from_millis(12_345)), "12.3 s");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59.0 s");
+        assert_eq!(format_duration(Duration::from_secs(60)), "1m 00s");
+        assert_eq!(format_duration(Duration::from_secs(754)), "12m 34s");
+    }
+
+    #[test]
+    fn counts_are_pluralized() {
+        assert_eq!(plural(0, "row"), "0 rows");
+        assert_eq!(plural(1, "row"), "1 row");
+        assert_eq!(plural(2, "migration"), "2 migrations");
+    }
+
+    #[test]
+    fn long_error_messages_are_cut_on_a_character_boundary() {
+        let short = json!({ "message": "relation \"users\" does not exist" });
+        assert_eq!(error_message(&short), "relation \"users\" does not exist");
+        assert_eq!(error_message(&json!("timeout")), "\"timeout\"");
+
+        // Every character is two bytes, so a byte-based cut would panic
+        let long = json!({ "message": "é".repeat(MAX_MESSAGE_LEN + 10) });
+        let message = error_message(&long);
+        assert_eq!(message, format!("{}…", "é".repeat(MAX_MESSAGE_LEN)));
+    }
+
+    #[test]
+    fn only_enabled_settings_notify_past_the_threshold() {
+        let settings = NotificationSettings { enabled: true, threshold_secs: 10 };
+        assert!(!settings.allows(Duration::from_millis(9_999)));
+        assert!(settings.allows(Duration::from_secs(10)));
+
+        let disabled = NotificationSettings { enabled: false, ..settings };
+        assert!(!disabled.allows(Duration::from_secs(3600)));
+
+        let always = NotificationSettings { enabled: true, threshold_secs: 0 };
+        assert!(always.allows(Duration::ZERO));
+    }
+
+    #[test]
+    fn queries_are_forgotten_with_their_window_or_the_bridge() {
+        let notifier = Notifier::load(None);
+        for (session, window) in [("s1", "main"), ("s2", "query-1"), ("s3", "main")] {
+            notifier.queries.lock().unwrap().insert(
+                session.to_string(),
+                RunningQuery { window: window.to_string(), started_at: Instant::now() },
+            );
+        }
+
+        notifier.drop_window("main");
+        let mut left: Vec<String> = notifier.queries.lock().unwrap().keys().cloned().collect();
+        left.sort();
+        assert_eq!(left, vec!["s2"]);
+
+        notifier.clear();
+        assert!(notifier.queries.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,507 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::bridge::{self, BridgeRouter};
+
+/// Event emitted to the focused window for actions the frontend carries out itself
+pub const SHORTCUT_EVENT: &str = "shortcut";
+
+/// Emitted to every window when the keymap has been reloaded, so they fetch the new bindings
+pub const SHORTCUTS_CHANGED_EVENT: &str = "shortcuts-changed";
+
+/// Editing keys the webview needs; binding them would break copy, paste and undo everywhere
+const RESERVED: &[&str] = &[
+    "CmdOrCtrl+A",
+    "CmdOrCtrl+C",
+    "CmdOrCtrl+V",
+    "CmdOrCtrl+X",
+    "CmdOrCtrl+Z",
+    "CmdOrCtrl+Shift+Z",
+    "CmdOrCtrl+Y",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutAction {
+    RunQuery,
+    CancelQuery,
+    RestartBridge,
+    ToggleDevtools,
+    Reload,
+    NavigateBack,
+    NavigateForward,
+    ShowApp,
+}
+
+const ACTIONS: &[ShortcutAction] = &[
+    ShortcutAction::RunQuery,
+    ShortcutAction::CancelQuery,
+    ShortcutAction::RestartBridge,
+    ShortcutAction::ToggleDevtools,
+    ShortcutAction::Reload,
+    ShortcutAction::NavigateBack,
+    ShortcutAction::NavigateForward,
+    ShortcutAction::ShowApp,
+];
+
+/// Where a shortcut works
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Only while a RelWave window has focus; the window matches its own key presses
+    App,
+    /// System-wide, even when RelWave is in the background
+    Global,
+}
+
+impl ShortcutAction {
+    fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::RunQuery => "Run query",
+            ShortcutAction::CancelQuery => "Cancel running queries",
+            ShortcutAction::RestartBridge => "Restart bridge",
+            ShortcutAction::ToggleDevtools => "Toggle developer tools",
+            ShortcutAction::Reload => "Reload window",
+            ShortcutAction::NavigateBack => "Go back",
+            ShortcutAction::NavigateForward => "Go forward",
+            ShortcutAction::ShowApp => "Show RelWave",
+        }
+    }
+
+    fn scope(self) -> Scope {
+        match self {
+            ShortcutAction::ShowApp => Scope::Global,
+            _ => Scope::App,
+        }
+    }
+
+    /// Binding used when the keymap doesn't mention the action; global shortcuts are opt-in
+    fn default_accelerator(self) -> Option<&'static str> {
+        match self {
+            ShortcutAction::RunQuery => Some("CmdOrCtrl+Enter"),
+            ShortcutAction::CancelQuery => Some("CmdOrCtrl+Period"),
+            ShortcutAction::RestartBridge => Some("CmdOrCtrl+Shift+R"),
+            ShortcutAction::ToggleDevtools => Some("CmdOrCtrl+Shift+I"),
+            ShortcutAction::Reload => Some("CmdOrCtrl+R"),
+            ShortcutAction::NavigateBack => Some("Alt+ArrowLeft"),
+            ShortcutAction::NavigateForward => Some("Alt+ArrowRight"),
+            ShortcutAction::ShowApp => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShortcutStatus {
+    /// Registered with the OS (global) or handled by the windows (app)
+    Ready,
+    Unbound,
+    /// The accelerator could not be parsed
+    Invalid,
+    /// Clashes with another binding, a reserved key or another application
+    Conflict,
+}
+
+/// One row of the shortcut settings page
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutInfo {
+    pub action: ShortcutAction,
+    pub label: &'static str,
+    pub scope: Scope,
+    pub accelerator: Option<String>,
+    pub default_accelerator: Option<&'static str>,
+    /// The parsed accelerator, for windows to match `keydown` events against
+    pub keys: Option<Keys>,
+    pub status: ShortcutStatus,
+    /// Why the shortcut is invalid or conflicting
+    pub problem: Option<String>,
+}
+
+/// A key press as the webview reports it: `KeyboardEvent.code` and its modifiers.
+/// `CmdOrCtrl` is already resolved for this platform.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Keys {
+    pub code: String,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+}
+
+impl Keys {
+    fn of(shortcut: &Shortcut) -> Self {
+        Self {
+            code: shortcut.key.to_string(),
+            ctrl: shortcut.mods.contains(Modifiers::CONTROL),
+            alt: shortcut.mods.contains(Modifiers::ALT),
+            shift: shortcut.mods.contains(Modifiers::SHIFT),
+            meta: shortcut.mods.contains(Modifiers::SUPER),
+        }
+    }
+}
+
+struct Binding {
+    info: ShortcutInfo,
+    shortcut: Option<Shortcut>,
+}
+
+/// Keyboard shortcuts from `keymap.json`, checked for conflicts.
+/// Only global shortcuts are registered with the OS; app shortcuts are matched by each window's
+/// `keydown` listener and run through `shortcuts_run`, so they never take keys from the webview or other programs.
+pub struct Shortcuts {
+    path: Option<PathBuf>,
+    bindings: Mutex<Vec<Binding>>,
+    /// Registered global shortcut ids and the actions they trigger
+    registered: Mutex<HashMap<u32, ShortcutAction>>,
+}
+
+impl Shortcuts {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            bindings: Mutex::new(Vec::new()),
+            registered: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Read the keymap, writing one with the defaults if there is none yet so it can be edited.
+    /// `null` unbinds an action; actions left out keep their default.
+    fn read_keymap(&self, app_handle: &AppHandle) -> BTreeMap<ShortcutAction, Option<String>> {
+        let mut keymap: BTreeMap<ShortcutAction, Option<String>> = ACTIONS
+            .iter()
+            .map(|a| (*a, a.default_accelerator().map(str::to_string)))
+            .collect();
+        let Some(path) = &self.path else {
+            return keymap;
+        };
+
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let written = path
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .map_err(|e| e.to_string())
+                    .and_then(|_| serde_json::to_string_pretty(&keymap).map_err(|e| e.to_string()))
+                    .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()));
+                if let Err(e) = written {
+                    crate::diagnostics::shell_log(app_handle, format!("Failed to write default keymap: {}", e));
+                }
+                return keymap;
+            }
+            Err(e) => {
+                crate::diagnostics::shell_log(app_handle, format!("Failed to read keymap: {}", e));
+                return keymap;
+            }
+        };
+        let entries = match serde_json::from_str::<BTreeMap<String, Option<String>>>(&raw) {
+            Ok(entries) => entries,
+            Err(e) => {
+                crate::diagnostics::shell_log(app_handle, format!("Ignoring malformed keymap: {}", e));
+                return keymap;
+            }
+        };
+        for (name, accelerator) in entries {
+            match serde_json::from_value::<ShortcutAction>(Value::String(name.clone())) {
+                Ok(action) => {
+                    keymap.insert(action, accelerator.filter(|a| !a.trim().is_empty()));
+                }
+                Err(_) => crate::diagnostics::shell_log(app_handle, format!("Ignoring unknown shortcut action '{}'", name)),
+            }
+        }
+        keymap
+    }
+
+    fn is_registered(&self, shortcut: &Shortcut) -> bool {
+        self.registered.lock().unwrap().contains_key(&shortcut.id())
+    }
+
+    /// Register or unregister every ready global shortcut
+    fn register_global(&self, app_handle: &AppHandle, enable: bool) {
+        let manager = app_handle.global_shortcut();
+        let mut bindings = self.bindings.lock().unwrap();
+        for binding in bindings.iter_mut().filter(|b| b.info.scope == Scope::Global) {
+            let Some(shortcut) = binding.shortcut else {
+                continue;
+            };
+            if binding.info.status != ShortcutStatus::Ready || enable == self.is_registered(&shortcut) {
+                continue;
+            }
+            if enable {
+                match manager.register(shortcut) {
+                    Ok(()) => {
+                        self.registered.lock().unwrap().insert(shortcut.id(), binding.info.action);
+                    }
+                    Err(e) => {
+                        binding.info.status = ShortcutStatus::Conflict;
+                        binding.info.problem = Some(format!("in use by another application ({})", e));
+                    }
+                }
+            } else {
+                let _ = manager.unregister(shortcut);
+                self.registered.lock().unwrap().remove(&shortcut.id());
+            }
+        }
+    }
+
+    /// (Re)load the keymap and register the global shortcuts it defines
+    pub fn apply(&self, app_handle: &AppHandle) {
+        self.register_global(app_handle, false);
+        *self.bindings.lock().unwrap() = resolve(self.read_keymap(app_handle));
+        self.register_global(app_handle, true);
+    }
+
+    /// Whether `action` is bound, free of conflicts and handled by the windows
+    fn is_ready_in_app(&self, action: ShortcutAction) -> bool {
+        self.bindings
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|b| b.info.action == action && b.info.scope == Scope::App && b.info.status == ShortcutStatus::Ready)
+    }
+
+    pub fn list(&self) -> Vec<ShortcutInfo> {
+        self.bindings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|b| b.info.clone())
+            .collect()
+    }
+}
+
+/// Parse every binding and mark the ones that can't be registered
+fn resolve(keymap: BTreeMap<ShortcutAction, Option<String>>) -> Vec<Binding> {
+    let reserved: Vec<u32> = RESERVED
+        .iter()
+        .filter_map(|r| Shortcut::from_str(r).ok())
+        .map(|s| s.id())
+        .collect();
+
+    let mut bindings: Vec<Binding> = ACTIONS
+        .iter()
+        .map(|action| {
+            let accelerator = keymap.get(action).cloned().flatten();
+            let mut info = ShortcutInfo {
+                action: *action,
+                label: action.label(),
+                scope: action.scope(),
+                accelerator: accelerator.clone(),
+                default_accelerator: action.default_accelerator(),
+                keys: None,
+                status: ShortcutStatus::Unbound,
+                problem: None,
+            };
+            let shortcut = match accelerator.as_deref().map(Shortcut::from_str) {
+                None => None,
+                Some(Ok(shortcut)) if reserved.contains(&shortcut.id()) => {
+                    info.status = ShortcutStatus::Conflict;
+                    info.problem = Some("reserved for text editing".into());
+                    Some(shortcut)
+                }
+                Some(Ok(shortcut)) => {
+                    info.status = ShortcutStatus::Ready;
+                    Some(shortcut)
+                }
+                Some(Err(e)) => {
+                    info.status = ShortcutStatus::Invalid;
+                    info.problem = Some(e.to_string());
+                    None
+                }
+            };
+            info.keys = shortcut.as_ref().map(Keys::of);
+            Binding { info, shortcut }
+        })
+        .collect();
+
+    // Two actions on the same keys: neither can be trusted to win, so both are disabled
+    for i in 0..bindings.len() {
+        let Some(shortcut) = bindings[i].shortcut else {
+            continue;
+        };
+        let clashes: Vec<&'static str> = bindings
+            .iter()
+            .enumerate()
+            .filter(|(j, b)| *j != i && b.shortcut.is_some_and(|s| s.id() == shortcut.id()))
+            .map(|(_, b)| b.info.label)
+            .collect();
+        if !clashes.is_empty() {
+            bindings[i].info.status = ShortcutStatus::Conflict;
+            bindings[i].info.problem = Some(format!("also bound to {}", clashes.join(", ")));
+        }
+    }
+    bindings
+}
+
+/// Carry out an app action for the window the key was pressed in
+fn run(app_handle: &AppHandle, action: ShortcutAction, window: &WebviewWindow) -> Result<(), String> {
+    let label = window.label().to_string();
+    match action {
+        ShortcutAction::RunQuery => app_handle
+            .emit_to(label.as_str(), SHORTCUT_EVENT, json!({ "action": action }))
+            .map_err(|e| e.to_string()),
+        ShortcutAction::CancelQuery => {
+            let sessions = app_handle
+                .try_state::<BridgeRouter>()
+                .map(|r| r.sessions_of(&label))
+                .unwrap_or_default();
+            let app_handle = app_handle.clone();
+            std::thread::spawn(move || {
+                for session in sessions {
+                    if let Err(e) = bridge::request(&app_handle, "query.cancel", json!({ "sessionId": session })) {
+                        crate::diagnostics::shell_log(&app_handle, format!("Failed to cancel session {}: {}", session, e));
+                    }
+                }
+            });
+            Ok(())
+        }
+        ShortcutAction::RestartBridge => {
+            let app_handle = app_handle.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = bridge::restart(&app_handle) {
+                    crate::diagnostics::shell_log(&app_handle, format!("Failed to restart bridge: {}", e));
+                }
+            });
+            Ok(())
+        }
+        ShortcutAction::ToggleDevtools => {
+            crate::devtools::toggle_devtools(window);
+            Ok(())
+        }
+        ShortcutAction::Reload => crate::devtools::reload(window),
+        ShortcutAction::NavigateBack => crate::devtools::back(window),
+        ShortcutAction::NavigateForward => crate::devtools::forward(window),
+        ShortcutAction::ShowApp => {
+            crate::windows::focus(app_handle, "main");
+            Ok(())
+        }
+    }
+}
+
+/// Called by the global shortcut plugin for every registered shortcut
+pub fn handle(app_handle: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let Some(state) = app_handle.try_state::<Shortcuts>() else {
+        return;
+    };
+    // Only `ShowApp` is global, and it needs no window
+    if state.registered.lock().unwrap().get(&shortcut.id()) == Some(&ShortcutAction::ShowApp) {
+        crate::windows::focus(app_handle, "main");
+    }
+}
+
+/// Every shortcut action with its binding and whether it could be registered
+#[tauri::command]
+pub fn shortcuts_list(shortcuts: State<'_, Shortcuts>) -> Vec<ShortcutInfo> {
+    shortcuts.list()
+}
+
+/// Re-read `keymap.json` after it has been edited
+#[tauri::command]
+pub fn shortcuts_reload(app_handle: AppHandle, shortcuts: State<'_, Shortcuts>) -> Vec<ShortcutInfo> {
+    shortcuts.apply(&app_handle);
+    let _ = app_handle.emit(SHORTCUTS_CHANGED_EVENT, ());
+    shortcuts.list()
+}
+
+/// Run an app shortcut the calling window matched in its `keydown` listener
+#[tauri::command]
+pub fn shortcuts_run(
+    action: ShortcutAction,
+    window: WebviewWindow,
+    app_handle: AppHandle,
+    shortcuts: State<'_, Shortcuts>,
+) -> Result<(), String> {
+    if !shortcuts.is_ready_in_app(action) {
+        return Err(format!("{} is not an app shortcut", action.label()));
+    }
+    run(&app_handle, action, &window).inspect_err(|e| {
+        crate::diagnostics::shell_log(&app_handle, format!("Shortcut {:?} failed: {}", action, e));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> BTreeMap<ShortcutAction, Option<String>> {
+        ACTIONS
+            .iter()
+            .map(|a| (*a, a.default_accelerator().map(str::to_string)))
+            .collect()
+    }
+
+    fn info(bindings: &[Binding], action: ShortcutAction) -> &ShortcutInfo {
+        &bindings.iter().find(|b| b.info.action == action).unwrap().info
+    }
+
+    #[test]
+    fn the_defaults_do_not_conflict() {
+        let bindings = resolve(defaults());
+        assert_eq!(bindings.len(), ACTIONS.len());
+        for binding in &bindings {
+            let expected = match binding.info.action {
+                ShortcutAction::ShowApp => ShortcutStatus::Unbound,
+                _ => ShortcutStatus::Ready,
+            };
+            assert_eq!(binding.info.status, expected, "{:?}", binding.info);
+        }
+        assert_eq!(info(&bindings, ShortcutAction::ShowApp).scope, Scope::Global);
+    }
+
+    #[test]
+    fn windows_get_the_keys_to_match() {
+        let bindings = resolve(defaults());
+        let back = info(&bindings, ShortcutAction::NavigateBack).keys.clone().unwrap();
+        assert_eq!(
+            back,
+            Keys { code: "ArrowLeft".into(), ctrl: false, alt: true, shift: false, meta: false }
+        );
+
+        let restart = info(&bindings, ShortcutAction::RestartBridge).keys.clone().unwrap();
+        assert_eq!(restart.code, "KeyR");
+        assert!(restart.shift);
+        // CmdOrCtrl is Cmd on macOS and Ctrl elsewhere
+        assert_eq!((restart.ctrl, restart.meta), (!cfg!(target_os = "macos"), cfg!(target_os = "macos")));
+
+        assert_eq!(info(&bindings, ShortcutAction::CancelQuery).keys.as_ref().unwrap().code, "Period");
+        assert!(info(&bindings, ShortcutAction::ShowApp).keys.is_none());
+    }
+
+    #[test]
+    fn shared_reserved_and_invalid_bindings_are_flagged() {
+        let mut keymap = defaults();
+        keymap.insert(ShortcutAction::Reload, Some("CmdOrCtrl+Enter".into()));
+        keymap.insert(ShortcutAction::CancelQuery, Some("CmdOrCtrl+C".into()));
+        keymap.insert(ShortcutAction::NavigateBack, Some("Alt+Nowhere".into()));
+        keymap.insert(ShortcutAction::NavigateForward, None);
+        let bindings = resolve(keymap);
+
+        for (action, other) in [
+            (ShortcutAction::RunQuery, "Reload window"),
+            (ShortcutAction::Reload, "Run query"),
+        ] {
+            let info = info(&bindings, action);
+            assert_eq!(info.status, ShortcutStatus::Conflict);
+            assert_eq!(info.problem.as_deref(), Some(format!("also bound to {}", other).as_str()));
+        }
+        let cancel = info(&bindings, ShortcutAction::CancelQuery);
+        assert_eq!(cancel.status, ShortcutStatus::Conflict);
+        assert_eq!(cancel.problem.as_deref(), Some("reserved for text editing"));
+        let back = info(&bindings, ShortcutAction::NavigateBack);
+        assert_eq!(back.status, ShortcutStatus::Invalid);
+        assert!(back.problem.is_some());
+        assert_eq!(info(&bindings, ShortcutAction::NavigateForward).status, ShortcutStatus::Unbound);
+        assert_eq!(info(&bindings, ShortcutAction::RestartBridge).status, ShortcutStatus::Ready);
+    }
+}
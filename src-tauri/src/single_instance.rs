@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::diagnostics::{self, LogSource};
+
+/// Event emitted to the main window with the arguments of a second launch
+pub const INSTANCE_ARGS_EVENT: &str = "instance-args";
+
+/// Held with an exclusive lock for as long as the primary instance runs
+const LOCK_FILE: &str = "instance.lock";
+
+/// Port and token of the primary instance's socket
+const ENDPOINT_FILE: &str = "instance.json";
+
+/// How long a second launch keeps trying to reach a primary that is still starting
+const CONNECT_ATTEMPTS: u32 = 20;
+const CONNECT_RETRY: Duration = Duration::from_millis(100);
+
+/// Pause after a failed accept, so a persistent error does not spin the listener thread
+const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Endpoint {
+    port: u16,
+    token: String,
+}
+
+/// Arguments of a later launch, forwarded to the running instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardedArgs {
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Message {
+    token: String,
+    #[serde(flatten)]
+    forwarded: ForwardedArgs,
+}
+
+/// Proof that this process is the primary instance; keeps the lock and the socket
+pub struct InstanceGuard {
+    _lock: Option<File>,
+    listener: Option<(TcpListener, String)>,
+    /// Problems setting up the lock or socket, printed at once and kept for the shell log
+    problems: Vec<String>,
+}
+
+pub enum Instance {
+    Primary(InstanceGuard),
+    /// Another instance is running and has received our arguments
+    Forwarded,
+}
+
+/// Folder for the lock and endpoint files, the same one Tauri resolves for `app_data_dir`
+fn instance_dir(context: &tauri::Context) -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join(&context.config().identifier))
+}
+
+/// Become the primary instance, or hand `args` to the one already running.
+/// Any failure to set up the lock falls back to running unguarded rather than refusing to start.
+pub fn acquire(context: &tauri::Context, args: &[String]) -> Instance {
+    let unguarded = |problem: String| {
+        eprintln!("{}", problem);
+        Instance::Primary(InstanceGuard {
+            _lock: None,
+            listener: None,
+            problems: vec![problem],
+        })
+    };
+    let Some(dir) = instance_dir(context) else {
+        return unguarded("Single-instance check disabled: no data folder".into());
+    };
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        return unguarded(format!(
+            "Single-instance check disabled: failed to create {}: {}",
+            dir.display(),
+            e
+        ));
+    }
+
+    let lock = match OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))
+    {
+        Ok(f) => f,
+        Err(e) => return unguarded(format!("Single-instance check disabled: failed to open lock file: {}", e)),
+    };
+
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let forwarded = ForwardedArgs {
+                args: args.to_vec(),
+                cwd: std::env::current_dir().ok().map(|d| d.display().to_string()),
+            };
+            // This process exits right away and never gets a shell log, so stderr is all there is
+            if let Err(e) = forward(&dir, forwarded) {
+                eprintln!("RelWave is already running but could not be reached: {}", e);
+            }
+            return Instance::Forwarded;
+        }
+        Err(TryLockError::Error(e)) => {
+            return unguarded(format!("Single-instance check disabled: failed to lock: {}", e));
+        }
+    }
+
+    let mut problems = Vec::new();
+    let listener = match bind(&dir) {
+        Ok(listener) => Some(listener),
+        Err(e) => {
+            let problem = format!("Failed to open single-instance socket: {}", e);
+            eprintln!("{}", problem);
+            problems.push(problem);
+            None
+        }
+    };
+    Instance::Primary(InstanceGuard {
+        _lock: Some(lock),
+        listener,
+        problems,
+    })
+}
+
+/// Listen on loopback and publish the port with a fresh token readable only by the current user
+fn bind(dir: &Path) -> Result<(TcpListener, String), String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).map_err(|e| e.to_string())?;
+    let endpoint = Endpoint {
+        port: listener.local_addr().map_err(|e| e.to_string())?.port(),
+        token: uuid::Uuid::new_v4().to_string(),
+    };
+    let json = serde_json::to_string(&endpoint).map_err(|e| e.to_string())?;
+    let path = dir.join(ENDPOINT_FILE);
+    // Start from a new file, so it never has the permissions of one left by an earlier run
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.to_string()),
+        _ => {}
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path).map_err(|e| e.to_string())?;
+    file.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+    Ok((listener, endpoint.token))
+}
+
+fn forward(dir: &Path, forwarded: ForwardedArgs) -> Result<(), String> {
+    let mut last_error = String::from("endpoint not published");
+    for _ in 0..CONNECT_ATTEMPTS {
+        match try_forward(dir, &forwarded) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = e,
+        }
+        std::thread::sleep(CONNECT_RETRY);
+    }
+    Err(last_error)
+}
+
+fn try_forward(dir: &Path, forwarded: &ForwardedArgs) -> Result<(), String> {
+    let raw = std::fs::read_to_string(dir.join(ENDPOINT_FILE)).map_err(|e| e.to_string())?;
+    let endpoint: Endpoint = serde_json::from_str(&raw).map_err(|e| e.to_string())?;
+    let mut stream =
+        TcpStream::connect((Ipv4Addr::LOCALHOST, endpoint.port)).map_err(|e| e.to_string())?;
+    let message = Message {
+        token: endpoint.token,
+        forwarded: forwarded.clone(),
+    };
+    let line = serde_json::to_string(&message).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", line).map_err(|e| e.to_string())?;
+
+    // Wait for the acknowledgement so we don't exit before the message is read
+    let mut ack = String::new();
+    BufReader::new(stream).read_line(&mut ack).map_err(|e| e.to_string())?;
+    if ack.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(format!("running instance rejected the request: {}", ack.trim()))
+    }
+}
+
+impl InstanceGuard {
+    /// Accept forwarded launches for the rest of the process lifetime
+    pub fn listen(&mut self, app_handle: AppHandle) {
+        for problem in self.problems.drain(..) {
+            diagnostics::record_log(&app_handle, LogSource::Shell, &problem);
+        }
+        let Some((listener, token)) = self.listener.take() else {
+            return;
+        };
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        diagnostics::shell_log(&app_handle, format!("Failed to accept a single-instance connection: {}", e));
+                        std::thread::sleep(ACCEPT_RETRY);
+                        continue;
+                    }
+                };
+                if let Some(forwarded) = receive(stream, &token) {
+                    deliver(&app_handle, forwarded);
+                }
+            }
+        });
+    }
+}
+
+fn receive(stream: TcpStream, token: &str) -> Option<ForwardedArgs> {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut writer = &stream;
+    match serde_json::from_str::<Message>(&line) {
+        Ok(message) if message.token == token => {
+            let _ = writeln!(writer, "ok");
+            Some(message.forwarded)
+        }
+        _ => {
+            let _ = writeln!(writer, "denied");
+            None
+        }
+    }
+}
+
+/// Bring the main window forward and hand it the forwarded arguments
+fn deliver(app_handle: &AppHandle, forwarded: ForwardedArgs) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    crate::deep_link::receive(app_handle, &forwarded.args, true);
+    crate::file_open::emit_forwarded(
+        app_handle,
+        &forwarded.args,
+        forwarded.cwd.as_deref().map(Path::new),
+    );
+    let _ = app_handle.emit_to("main", INSTANCE_ARGS_EVENT, forwarded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwarded_arguments_need_the_published_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let (listener, token) = bind(dir.path()).unwrap();
+        let forwarded = ForwardedArgs {
+            args: vec!["relwave://open".into()],
+            cwd: None,
+        };
+
+        let server = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            for stream in listener.incoming().take(2).map_while(Result::ok) {
+                received.push(receive(stream, &token));
+            }
+            received
+        });
+        try_forward(dir.path(), &forwarded).unwrap();
+        let mut endpoint: Endpoint =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join(ENDPOINT_FILE)).unwrap()).unwrap();
+        endpoint.token = "guessed".into();
+        std::fs::write(dir.path().join(ENDPOINT_FILE), serde_json::to_string(&endpoint).unwrap()).unwrap();
+        assert!(try_forward(dir.path(), &forwarded).unwrap_err().contains("denied"));
+
+        let received = server.join().unwrap();
+        assert_eq!(received[0].as_ref().unwrap().args, forwarded.args);
+        assert!(received[1].is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_endpoint_is_created_readable_only_by_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(ENDPOINT_FILE);
+        std::fs::write(&path, "left by an earlier run").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        bind(dir.path()).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(serde_json::from_str::<Endpoint>(&std::fs::read_to_string(&path).unwrap()).is_ok());
+    }
+}
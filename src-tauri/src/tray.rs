@@ -0,0 +1,440 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, State, Window, WindowEvent};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::bridge::{self, BridgeHistory, BridgeProcess};
+
+const TRAY_ID: &str = "main";
+
+/// How often the tray checks whether the bridge is still alive
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long the recent connections list is reused before `db.list` is asked again
+const CONNECTIONS_TTL: Duration = Duration::from_secs(60);
+
+/// Connections listed under "Open connection"
+const RECENT_CONNECTIONS: usize = 8;
+
+const CONNECTION_PREFIX: &str = "connection:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BridgeState {
+    Running,
+    Restarting,
+    Failed,
+}
+
+impl BridgeState {
+    fn label(self) -> &'static str {
+        match self {
+            BridgeState::Running => "running",
+            BridgeState::Restarting => "restarting",
+            BridgeState::Failed => "stopped",
+        }
+    }
+
+    /// Color of the status dot drawn over the app icon
+    fn color(self) -> [u8; 3] {
+        match self {
+            BridgeState::Running => [46, 160, 67],
+            BridgeState::Restarting => [219, 154, 4],
+            BridgeState::Failed => [207, 34, 46],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraySettings {
+    /// Hide the main window to the tray instead of quitting when it is closed
+    #[serde(default)]
+    pub close_to_tray: bool,
+}
+
+struct Connection {
+    id: String,
+    name: String,
+}
+
+/// Tray icon state: last bridge state shown, recent connections and settings (persisted to `tray.json`)
+pub struct Tray {
+    state: Mutex<Option<BridgeState>>,
+    connections: Mutex<Vec<Connection>>,
+    connections_loaded: Mutex<Option<Instant>>,
+    settings: Mutex<TraySettings>,
+    path: Option<PathBuf>,
+}
+
+impl Tray {
+    /// Load settings from `path`, starting with the defaults if it is missing or unreadable
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let settings = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str::<TraySettings>(&s).ok())
+            .unwrap_or_default();
+
+        Self {
+            state: Mutex::new(None),
+            connections: Mutex::new(Vec::new()),
+            connections_loaded: Mutex::new(None),
+            settings: Mutex::new(settings),
+            path,
+        }
+    }
+
+    fn set_settings(&self, settings: TraySettings) -> Result<(), String> {
+        *self.settings.lock().unwrap() = settings;
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create config dir: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("failed to save tray settings: {}", e))
+    }
+
+    fn connections_stale(&self) -> bool {
+        self.connections_loaded
+            .lock()
+            .unwrap()
+            .is_none_or(|t| t.elapsed() >= CONNECTIONS_TTL)
+    }
+}
+
+/// Work out the bridge state from the process and the last recorded status.
+/// A restart holds the process lock while spawning, so a busy lock means "keep showing the last status".
+fn bridge_state(app_handle: &AppHandle) -> BridgeState {
+    let last = app_handle
+        .try_state::<BridgeHistory>()
+        .and_then(|h| h.last_status());
+    if last.as_deref() == Some("restarting") {
+        return BridgeState::Restarting;
+    }
+    let Some(process) = app_handle.try_state::<BridgeProcess>() else {
+        return BridgeState::Restarting;
+    };
+    let Ok(mut guard) = process.0.try_lock() else {
+        return match last.as_deref() {
+            Some("running") => BridgeState::Running,
+            _ => BridgeState::Restarting,
+        };
+    };
+    match guard.as_mut().map(|child| child.try_wait()) {
+        Some(Ok(None)) => BridgeState::Running,
+        _ => BridgeState::Failed,
+    }
+}
+
+/// The app icon with a status dot in the bottom-right corner
+fn status_icon(base: &Image<'_>, state: BridgeState) -> Image<'static> {
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    let radius = width.min(height) as f32 * 0.2;
+    let ring = radius * 1.3;
+    let (cx, cy) = (width as f32 - ring, height as f32 - ring);
+    let [r, g, b] = state.color();
+
+    for y in 0..height {
+        for x in 0..width {
+            let distance = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+            let pixel = if distance <= radius {
+                [r, g, b, 255]
+            } else if distance <= ring {
+                [255, 255, 255, 255]
+            } else {
+                continue;
+            };
+            let i = ((y * width + x) * 4) as usize;
+            rgba[i..i + 4].copy_from_slice(&pixel);
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
+
+fn build_menu(app_handle: &AppHandle, state: BridgeState) -> tauri::Result<Menu<tauri::Wry>> {
+    let status = MenuItem::with_id(
+        app_handle,
+        "status",
+        format!("Bridge: {}", state.label()),
+        false,
+        None::<&str>,
+    )?;
+    let show = MenuItem::with_id(app_handle, "show", "Show RelWave", true, None::<&str>)?;
+    let restart = MenuItem::with_id(
+        app_handle,
+        "restart",
+        "Restart bridge",
+        state != BridgeState::Restarting,
+        None::<&str>,
+    )?;
+
+    let connections = Submenu::with_id(app_handle, "connections", "Open connection", true)?;
+    {
+        let tray = app_handle.state::<Tray>();
+        let recent = tray.connections.lock().unwrap();
+        if recent.is_empty() {
+            let none = MenuItem::with_id(app_handle, "connections-empty", "No connections", false, None::<&str>)?;
+            connections.append(&none)?;
+        }
+        for connection in recent.iter() {
+            let item = MenuItem::with_id(
+                app_handle,
+                format!("{}{}", CONNECTION_PREFIX, connection.id),
+                &connection.name,
+                state == BridgeState::Running,
+                None::<&str>,
+            )?;
+            connections.append(&item)?;
+        }
+    }
+
+    let logs = MenuItem::with_id(app_handle, "logs", "Open logs", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app_handle, "quit", "Quit RelWave", true, None::<&str>)?;
+    Menu::with_items(
+        app_handle,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &show,
+            &connections,
+            &restart,
+            &logs,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &quit,
+        ],
+    )
+}
+
+/// Redraw the icon, tooltip and menu for the current bridge state
+fn render(app_handle: &AppHandle, tray: &TrayIcon, state: BridgeState) {
+    if let Some(base) = app_handle.default_window_icon() {
+        let _ = tray.set_icon(Some(status_icon(base, state)));
+    }
+    let _ = tray.set_tooltip(Some(format!("RelWave - bridge {}", state.label())));
+    match build_menu(app_handle, state) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => crate::diagnostics::shell_log(app_handle, format!("Failed to build tray menu: {}", e)),
+    }
+}
+
+/// Ask the bridge for the saved connections, outside the user's traffic
+fn load_connections(app_handle: &AppHandle) {
+    let Some(tray) = app_handle.try_state::<Tray>() else {
+        return;
+    };
+    let result = match bridge::background_request(app_handle, "db.list", json!({})) {
+        Ok(result) => result,
+        Err(e) => {
+            crate::diagnostics::shell_log(app_handle, format!("Failed to list connections for the tray: {}", e));
+            return;
+        }
+    };
+    *tray.connections.lock().unwrap() = recent_connections(&result);
+
+    if let Some(icon) = app_handle.tray_by_id(TRAY_ID) {
+        render(app_handle, &icon, bridge_state(app_handle));
+    }
+}
+
+/// The connections of a `db.list` result, most recently updated first
+fn recent_connections(result: &Value) -> Vec<Connection> {
+    let mut entries: Vec<&Value> = result
+        .get("data")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().collect())
+        .unwrap_or_default();
+    entries.sort_by(|a, b| {
+        let updated = |v: &Value| v.get("updatedAt").and_then(Value::as_str).unwrap_or_default().to_string();
+        updated(b).cmp(&updated(a))
+    });
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let id = entry.get("id").and_then(Value::as_str)?;
+            let name = entry.get("name").and_then(Value::as_str).unwrap_or(id);
+            Some(Connection {
+                id: id.to_string(),
+                name: name.to_string(),
+            })
+        })
+        .take(RECENT_CONNECTIONS)
+        .collect()
+}
+
+/// Update the tray if the bridge state has changed since it was last drawn
+pub fn refresh(app_handle: &AppHandle) {
+    let (Some(tray), Some(icon)) = (app_handle.try_state::<Tray>(), app_handle.tray_by_id(TRAY_ID)) else {
+        return;
+    };
+    let state = bridge_state(app_handle);
+    let changed = tray.state.lock().unwrap().replace(state) != Some(state);
+    if changed {
+        render(app_handle, &icon, state);
+    }
+    // `db.list` blocks on the bridge, so it never runs on the caller's thread
+    if state == BridgeState::Running && (changed || tray.connections_stale()) {
+        *tray.connections_loaded.lock().unwrap() = Some(Instant::now());
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || load_connections(&app_handle));
+    }
+}
+
+fn on_menu_event(app_handle: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "show" => {
+            crate::windows::focus(app_handle, "main");
+        }
+        "restart" => {
+            let app_handle = app_handle.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = bridge::restart(&app_handle) {
+                    crate::diagnostics::shell_log(&app_handle, format!("Failed to restart bridge: {}", e));
+                }
+            });
+        }
+        "logs" => {
+            let opened = crate::diagnostics::write_logs(app_handle).and_then(|dir| {
+                app_handle
+                    .opener()
+                    .open_path(dir.display().to_string(), None::<&str>)
+                    .map_err(|e| e.to_string())
+            });
+            if let Err(e) = opened {
+                crate::diagnostics::shell_log(app_handle, format!("Failed to open logs: {}", e));
+            }
+        }
+        "quit" => app_handle.exit(0),
+        id => {
+            if let Some(db_id) = id.strip_prefix(CONNECTION_PREFIX) {
+                if let Err(e) = crate::windows::open_database(app_handle, db_id) {
+                    crate::diagnostics::shell_log(app_handle, format!("Failed to open connection: {}", e));
+                }
+            }
+        }
+    }
+}
+
+/// Create the tray icon and keep it in step with the bridge
+pub fn init(app_handle: &AppHandle) -> tauri::Result<()> {
+    let state = bridge_state(app_handle);
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("RelWave")
+        .menu(&build_menu(app_handle, state)?)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                crate::windows::focus(tray.app_handle(), "main");
+            }
+        });
+    if let Some(base) = app_handle.default_window_icon() {
+        builder = builder.icon(status_icon(base, state));
+    }
+    builder.build(app_handle)?;
+
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || loop {
+        refresh(&app_handle);
+        std::thread::sleep(POLL_INTERVAL);
+    });
+    Ok(())
+}
+
+/// Hide the main window instead of closing it when close-to-tray is on
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    let WindowEvent::CloseRequested { api, .. } = event else {
+        return;
+    };
+    if window.label() != "main" || window.app_handle().tray_by_id(TRAY_ID).is_none() {
+        return;
+    }
+    let close_to_tray = window
+        .try_state::<Tray>()
+        .is_some_and(|t| t.settings.lock().unwrap().close_to_tray);
+    if close_to_tray {
+        api.prevent_close();
+        let _ = window.hide();
+    }
+}
+
+/// Current tray settings
+#[tauri::command]
+pub fn tray_settings_get(tray: State<'_, Tray>) -> TraySettings {
+    *tray.settings.lock().unwrap()
+}
+
+/// Change and persist the tray settings
+#[tauri::command]
+pub fn tray_settings_set(settings: TraySettings, tray: State<'_, Tray>) -> Result<(), String> {
+    tray.set_settings(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(connections: &[Connection]) -> Vec<(&str, &str)> {
+        connections.iter().map(|c| (c.id.as_str(), c.name.as_str())).collect()
+    }
+
+    #[test]
+    fn bridge_states_have_a_label_and_a_distinct_color() {
+        assert_eq!(BridgeState::Running.label(), "running");
+        assert_eq!(BridgeState::Restarting.label(), "restarting");
+        assert_eq!(BridgeState::Failed.label(), "stopped");
+        assert_ne!(BridgeState::Running.color(), BridgeState::Failed.color());
+        assert_ne!(BridgeState::Running.color(), BridgeState::Restarting.color());
+    }
+
+    #[test]
+    fn the_status_dot_is_drawn_in_the_bottom_right_corner() {
+        let base = Image::new_owned(vec![0; 32 * 32 * 4], 32, 32);
+        let icon = status_icon(&base, BridgeState::Failed);
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * 32 + x) * 4) as usize;
+            icon.rgba()[i..i + 4].to_vec()
+        };
+        let [r, g, b] = BridgeState::Failed.color();
+
+        assert_eq!((icon.width(), icon.height()), (32, 32));
+        assert_eq!(pixel(0, 0), vec![0, 0, 0, 0]);
+        assert_eq!(pixel(24, 24), vec![r, g, b, 255]);
+        // A white ring separates the dot from the icon
+        assert_eq!(pixel(24, 16), vec![255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn recent_connections_are_sorted_by_update_and_capped() {
+        let mut data: Vec<Value> = (0..10)
+            .map(|i| json!({ "id": format!("db{}", i), "name": format!("DB {}", i), "updatedAt": format!("2026-01-{:02}", i + 1) }))
+            .collect();
+        data.push(json!({ "name": "no id", "updatedAt": "2027-01-01" }));
+        data.push(json!({ "id": "unnamed", "updatedAt": "2026-12-01" }));
+
+        let connections = recent_connections(&json!({ "ok": true, "data": data }));
+        assert_eq!(connections.len(), RECENT_CONNECTIONS);
+        assert_eq!(names(&connections[..3]), vec![("unnamed", "unnamed"), ("db9", "DB 9"), ("db8", "DB 8")]);
+    }
+
+    #[test]
+    fn malformed_results_list_no_connections() {
+        assert!(recent_connections(&json!({ "ok": false })).is_empty());
+        assert!(recent_connections(&json!({ "data": "nope" })).is_empty());
+    }
+}
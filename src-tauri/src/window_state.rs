@@ -0,0 +1,336 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, State, WebviewWindow, Window, WindowEvent};
+
+use crate::diagnostics;
+
+/// Moves and resizes arrive in bursts; write the file at most this often while they do
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Smallest window we restore, so a bad entry can't leave an unusable sliver
+const MIN_WIDTH: u32 = 640;
+const MIN_HEIGHT: u32 = 480;
+
+/// Part of the title bar that has to be on a monitor for the window to count as reachable
+const GRAB_WIDTH: i32 = 120;
+const GRAB_HEIGHT: i32 = 32;
+
+/// Size and position of a window in physical pixels, as last seen before it was maximized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+/// What the user had open, so the frontend can pick up where it left off
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSession {
+    /// Connection ids with an open tab or window, in the order they were opened
+    #[serde(default)]
+    pub open_connections: Vec<String>,
+    #[serde(default)]
+    pub active_connection: Option<String>,
+    /// Active tab per connection id
+    #[serde(default)]
+    pub active_tabs: HashMap<String, String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StateFile {
+    #[serde(default)]
+    windows: HashMap<String, WindowGeometry>,
+    #[serde(default)]
+    workspace: WorkspaceSession,
+}
+
+/// Window geometry per label and the workspace session, persisted to `window-state.json`
+pub struct WindowState {
+    state: Mutex<StateFile>,
+    last_saved: Mutex<Option<Instant>>,
+    path: Option<PathBuf>,
+}
+
+impl WindowState {
+    /// Load saved state from `path`, starting empty if it is missing or unreadable
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let state = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str::<StateFile>(&s).ok())
+            .unwrap_or_default();
+
+        Self {
+            state: Mutex::new(state),
+            last_saved: Mutex::new(None),
+            path,
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("failed to create config dir: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&*self.state.lock().unwrap()).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("failed to save window state: {}", e))?;
+        *self.last_saved.lock().unwrap() = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Remember the current geometry of `window`; `flush` writes it out right away
+    fn record(&self, window: &Window, flush: bool) {
+        // A minimized window reports a meaningless position (and a zero size on Windows)
+        if window.is_minimized().unwrap_or(false) {
+            return;
+        }
+        let maximized = window.is_maximized().unwrap_or(false);
+        {
+            let mut state = self.state.lock().unwrap();
+            let previous = state.windows.get(window.label()).copied();
+            let geometry = match (maximized, previous) {
+                // Keep the normal geometry so un-maximizing after a restart lands in the right place
+                (true, Some(previous)) => WindowGeometry {
+                    maximized: true,
+                    ..previous
+                },
+                _ => {
+                    let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+                        return;
+                    };
+                    WindowGeometry {
+                        x: position.x,
+                        y: position.y,
+                        width: size.width,
+                        height: size.height,
+                        maximized,
+                    }
+                }
+            };
+            if previous == Some(geometry) {
+                return;
+            }
+            state.windows.insert(window.label().to_string(), geometry);
+        }
+
+        let due = self
+            .last_saved
+            .lock()
+            .unwrap()
+            .is_none_or(|t| t.elapsed() >= SAVE_INTERVAL);
+        if flush || due {
+            if let Err(e) = self.save() {
+                diagnostics::shell_log(window.app_handle(), e);
+            }
+        }
+    }
+
+    /// Apply the saved geometry of `window`, if any, moved back onto a monitor if it no longer fits
+    pub fn restore(&self, window: &WebviewWindow) {
+        let Some(saved) = self.state.lock().unwrap().windows.get(window.label()).copied() else {
+            return;
+        };
+        let monitors: Vec<Area> = window.available_monitors().unwrap_or_default().iter().map(Area::of).collect();
+        let primary = window.primary_monitor().ok().flatten().map(|m| Area::of(&m));
+        let geometry = clamp(saved, &monitors, primary);
+
+        let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+        let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+        if geometry.maximized {
+            let _ = window.maximize();
+        }
+    }
+
+    /// Write out whatever has not been saved yet
+    pub fn flush(&self, app_handle: &AppHandle) {
+        if let Err(e) = self.save() {
+            diagnostics::shell_log(app_handle, e);
+        }
+    }
+}
+
+/// Work area of a monitor in physical pixels (the screen minus taskbars and docks)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Area {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl Area {
+    fn of(monitor: &Monitor) -> Self {
+        let area = monitor.work_area();
+        Self {
+            x: area.position.x,
+            y: area.position.y,
+            width: area.size.width,
+            height: area.size.height,
+        }
+    }
+
+    fn overlaps(&self, x: i32, y: i32, width: i32, height: i32) -> bool {
+        let (right, bottom) = (self.x + self.width as i32, self.y + self.height as i32);
+        x < right && x + width > self.x && y < bottom && y + height > self.y
+    }
+}
+
+/// Fit saved geometry to the monitors connected now.
+/// The window keeps its position if its title bar is reachable on some monitor; otherwise it is centered on the primary one.
+fn clamp(saved: WindowGeometry, monitors: &[Area], primary: Option<Area>) -> WindowGeometry {
+    let mut geometry = saved;
+    geometry.width = geometry.width.max(MIN_WIDTH);
+    geometry.height = geometry.height.max(MIN_HEIGHT);
+
+    let grab_x = geometry.x + (geometry.width as i32 - GRAB_WIDTH) / 2;
+    let home = monitors
+        .iter()
+        .find(|m| m.overlaps(grab_x, geometry.y, GRAB_WIDTH, GRAB_HEIGHT));
+    let (area, reachable) = match home {
+        Some(m) => (*m, true),
+        None => match primary.or(monitors.first().copied()) {
+            Some(m) => (m, false),
+            // No monitor information; trust the saved geometry
+            None => return geometry,
+        },
+    };
+
+    geometry.width = geometry.width.min(area.width);
+    geometry.height = geometry.height.min(area.height);
+    if !reachable {
+        geometry.x = area.x + (area.width - geometry.width) as i32 / 2;
+        geometry.y = area.y + (area.height - geometry.height) as i32 / 2;
+    }
+    // Never let the title bar end up above the top of the screen
+    geometry.y = geometry.y.max(area.y);
+    geometry
+}
+
+/// Track geometry changes; registered with `on_window_event`
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    let Some(state) = window.try_state::<WindowState>() else {
+        return;
+    };
+    match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => state.record(window, false),
+        WindowEvent::CloseRequested { .. } => state.record(window, true),
+        _ => {}
+    }
+}
+
+/// The workspace session saved by the last run (empty on first launch)
+#[tauri::command]
+pub fn workspace_session_get(state: State<'_, WindowState>) -> WorkspaceSession {
+    state.state.lock().unwrap().workspace.clone()
+}
+
+/// Replace the saved workspace session
+#[tauri::command]
+pub fn workspace_session_save(session: WorkspaceSession, state: State<'_, WindowState>) -> Result<(), String> {
+    state.state.lock().unwrap().workspace = session;
+    state.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: Area = Area {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1040,
+    };
+    const RIGHT: Area = Area {
+        x: 1920,
+        y: 0,
+        width: 2560,
+        height: 1400,
+    };
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32) -> WindowGeometry {
+        WindowGeometry {
+            x,
+            y,
+            width,
+            height,
+            maximized: false,
+        }
+    }
+
+    #[test]
+    fn windows_on_a_disconnected_monitor_move_to_the_primary_one() {
+        // Saved on the right-hand monitor, which is gone now
+        let restored = clamp(geometry(2400, 200, 1200, 800), &[LEFT], Some(LEFT));
+        assert_eq!(restored, geometry(360, 120, 1200, 800));
+
+        // Too big for the remaining monitor as well
+        let restored = clamp(geometry(2000, 0, 2560, 1400), &[LEFT], Some(LEFT));
+        assert_eq!(restored, geometry(0, 0, 1920, 1040));
+    }
+
+    #[test]
+    fn partly_offscreen_windows_stay_where_they_were() {
+        // Hanging off the left edge, but the title bar is still reachable
+        let restored = clamp(geometry(-400, 100, 1000, 700), &[LEFT, RIGHT], Some(LEFT));
+        assert_eq!(restored, geometry(-400, 100, 1000, 700));
+
+        // Spanning both monitors
+        let restored = clamp(geometry(1500, 300, 1000, 700), &[LEFT, RIGHT], Some(LEFT));
+        assert_eq!(restored, geometry(1500, 300, 1000, 700));
+
+        // Title bar above the top of the screen is pulled down
+        let restored = clamp(geometry(100, -20, 1000, 700), &[LEFT, RIGHT], Some(LEFT));
+        assert_eq!(restored, geometry(100, 0, 1000, 700));
+    }
+
+    #[test]
+    fn unreachable_windows_use_the_primary_monitor_then_the_first() {
+        let lost = geometry(-5000, -5000, 800, 600);
+        assert_eq!(clamp(lost, &[LEFT, RIGHT], Some(RIGHT)), geometry(2800, 400, 800, 600));
+        assert_eq!(clamp(lost, &[LEFT, RIGHT], None), geometry(560, 220, 800, 600));
+        // Nothing known about the monitors: only the minimum size is enforced
+        assert_eq!(clamp(geometry(10, 10, 200, 100), &[], None), geometry(10, 10, MIN_WIDTH, MIN_HEIGHT));
+    }
+
+    #[test]
+    fn state_survives_a_round_trip_through_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join("window-state.json");
+        let state = WindowState::load(Some(path.clone()));
+        {
+            let mut file = state.state.lock().unwrap();
+            file.windows.insert(
+                "main".into(),
+                WindowGeometry {
+                    maximized: true,
+                    ..geometry(10, 20, 1280, 800)
+                },
+            );
+            file.workspace.open_connections = vec!["db1".into(), "db2".into()];
+            file.workspace.active_connection = Some("db2".into());
+            file.workspace.active_tabs.insert("db2".into(), "sql".into());
+        }
+        state.save().unwrap();
+
+        let reloaded = WindowState::load(Some(path.clone()));
+        let file = reloaded.state.lock().unwrap();
+        assert_eq!(file.windows["main"].width, 1280);
+        assert!(file.windows["main"].maximized);
+        assert_eq!(file.workspace.open_connections, ["db1", "db2"]);
+        assert_eq!(file.workspace.active_tabs["db2"], "sql");
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(WindowState::load(Some(path)).state.lock().unwrap().windows.is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::bridge;
+use crate::window_state::WindowState;
+
+/// Keep labels within what Tauri accepts (alphanumerics, `-`, `/`, `:` and `_`)
+fn label_for(prefix: &str, id: &str) -> String {
+    let id: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}-{}", prefix, id)
+}
+
+/// Bring the window with `label` to the front, restoring it if it is minimized or hidden
+pub fn focus(app_handle: &AppHandle, label: &str) -> bool {
+    let Some(window) = app_handle.get_webview_window(label) else {
+        return false;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    true
+}
+
+/// Focus the window with `label`, or open it on `route` if it doesn't exist yet
+fn open_or_focus(app_handle: &AppHandle, label: &str, route: &str, title: &str) -> Result<String, String> {
+    if focus(app_handle, label) {
+        return Ok(label.to_string());
+    }
+
+    let window = WebviewWindowBuilder::new(app_handle, label, WebviewUrl::App(route.trim_start_matches('/').into()))
+        .title(title)
+        .inner_size(1200.0, 800.0)
+        .min_inner_size(800.0, 600.0)
+        .decorations(false)
+        .build()
+        .map_err(|e| format!("failed to open window: {}", e))?;
+    if let Some(state) = app_handle.try_state::<WindowState>() {
+        state.restore(&window);
+    }
+    Ok(label.to_string())
+}
+
+/// Open a database in its own window (or focus the window that already shows it).
+/// Returns the window label.
+#[tauri::command]
+pub async fn window_open_database(db_id: String, app_handle: AppHandle) -> Result<String, String> {
+    open_database(&app_handle, &db_id)
+}
+
+pub fn open_database(app_handle: &AppHandle, db_id: &str) -> Result<String, String> {
+    if db_id.is_empty() {
+        return Err("database id is required".into());
+    }
+    let label = label_for("db", db_id);
+    open_or_focus(app_handle, &label, &format!("/{}", db_id), "RelWave")
+}
+
+/// Open a project's database in its own window.
+/// Returns the window label.
+#[tauri::command]
+pub async fn window_open_project(project_id: String, app_handle: AppHandle) -> Result<String, String> {
+    let label = label_for("project", &project_id);
+    if app_handle.get_webview_window(&label).is_none() {
+        let handle = app_handle.clone();
+        let id = project_id.clone();
+        let project = tauri::async_runtime::spawn_blocking(move || {
+            bridge::request(&handle, "project.get", json!({ "id": id }))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        let database_id = project
+            .get("data")
+            .and_then(|p| p.get("databaseId"))
+            .and_then(Value::as_str)
+            .ok_or("project has no database connection")?
+            .to_string();
+        let name = project
+            .get("data")
+            .and_then(|p| p.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or("Project");
+        return open_or_focus(&app_handle, &label, &format!("/{}", database_id), &format!("{} - RelWave", name));
+    }
+    open_or_focus(&app_handle, &label, "/", "RelWave")
+}
@@ -0,0 +1,67 @@
+import { useCallback, useEffect } from "react";
+import { toast } from "sonner";
+import { useQueryClient } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { hasTauriInvoke } from "@/services/bridge/bridgeClient";
+import { useBridgeQuery } from "@/services/bridge/useBridgeQuery";
+import { queryKeys } from "@/features/project/hooks/useDbQueries";
+import { projectKeys } from "@/features/project/hooks/useProjectQueries";
+
+/** A `relwave://` link held by the shell until the user confirms it; credentials never reach the webview */
+interface DeepLinkRequest {
+    id: string;
+    url: string;
+    kind: "connect" | "projectImport";
+    name?: string;
+}
+
+/**
+ * Hands `relwave://` links to the shell, which asks the user in a native dialog
+ * before adding the connection or importing the project. Only the main window receives them.
+ */
+export function useDeepLinks() {
+    const queryClient = useQueryClient();
+    const { data: bridgeReady } = useBridgeQuery();
+
+    const confirmLink = useCallback(async (link: DeepLinkRequest) => {
+        try {
+            await invoke("deep_link_confirm", { id: link.id });
+            if (link.kind === "connect") {
+                queryClient.invalidateQueries({ queryKey: queryKeys.databases });
+                toast.success("Database connection added", { description: link.name });
+            } else {
+                queryClient.invalidateQueries({ queryKey: projectKeys.all });
+                toast.success("Project imported");
+            }
+        } catch (err: any) {
+            const message = typeof err === "string" ? err : err?.message;
+            if (message !== "link was not confirmed") {
+                toast.error("Failed to open link", { description: message });
+            }
+        }
+    }, [queryClient]);
+
+    // Links this launch was started with, once the bridge can act on them
+    useEffect(() => {
+        if (!hasTauriInvoke() || !bridgeReady || getCurrentWebviewWindow().label !== "main") return;
+        invoke<DeepLinkRequest[]>("deep_link_pending")
+            .then(async (links) => {
+                for (const link of links) {
+                    await confirmLink(link);
+                }
+            })
+            .catch((err) => console.error("Failed to read pending deep links:", err));
+    }, [bridgeReady, confirmLink]);
+
+    // Links opened while the app is running
+    useEffect(() => {
+        if (!hasTauriInvoke()) return;
+        const unlisten = getCurrentWebviewWindow().listen<DeepLinkRequest>("deep-link", (event) => {
+            confirmLink(event.payload);
+        });
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [confirmLink]);
+}
@@ -0,0 +1,86 @@
+import { useCallback, useEffect } from "react";
+import { toast } from "sonner";
+import { useNavigate } from "react-router-dom";
+import { useQueryClient } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { hasTauriInvoke } from "@/services/bridge/bridgeClient";
+import { databaseService } from "@/services/bridge/database";
+import { useBridgeQuery } from "@/services/bridge/useBridgeQuery";
+import { queryKeys } from "@/features/project/hooks/useDbQueries";
+
+/** A SQLite file passed on the command line or through a file association, as checked by the shell */
+interface SqliteFileRequest {
+    path: string;
+    name: string;
+    action: "open" | "create" | "invalid";
+    error: string | null;
+}
+
+/**
+ * Opens SQLite files handed to the app (`relwave mydb.sqlite`, file associations):
+ * reuses or adds a connection, creates the database if the file is new, and shows it.
+ * Only the main window receives them.
+ */
+export function useSqliteFileOpen() {
+    const navigate = useNavigate();
+    const queryClient = useQueryClient();
+    const { data: bridgeReady } = useBridgeQuery();
+
+    const openFile = useCallback(async (file: SqliteFileRequest) => {
+        if (file.action === "invalid") {
+            toast.error(`Cannot open ${file.name}`, { description: file.error ?? file.path });
+            return;
+        }
+
+        try {
+            const existing = (await databaseService.listDatabases()).find(
+                (db) => db.type === "sqlite" && db.database === file.path
+            );
+            const db = existing ?? await databaseService.addDatabase({
+                name: file.name,
+                type: "sqlite",
+                database: file.path,
+                port: 0,
+                sslmode: "disable",
+            });
+
+            if (file.action === "create") {
+                // Connecting creates the database file
+                const result = await databaseService.testConnection(db.id);
+                if (!result.ok) {
+                    throw new Error(result.message || "Could not create the database");
+                }
+            }
+
+            queryClient.invalidateQueries({ queryKey: queryKeys.databases });
+            toast.success(file.action === "create" ? "Database created" : "Database opened", { description: file.path });
+            navigate(`/${db.id}`);
+        } catch (err: any) {
+            toast.error(`Failed to open ${file.name}`, { description: err.message });
+        }
+    }, [navigate, queryClient]);
+
+    // Files from this launch, once the bridge can add connections
+    useEffect(() => {
+        if (!hasTauriInvoke() || !bridgeReady || getCurrentWebviewWindow().label !== "main") return;
+        invoke<SqliteFileRequest[]>("file_open_pending")
+            .then(async (files) => {
+                for (const file of files) {
+                    await openFile(file);
+                }
+            })
+            .catch((err) => console.error("Failed to read pending SQLite files:", err));
+    }, [bridgeReady, openFile]);
+
+    // Files passed to a second launch, forwarded by the running instance
+    useEffect(() => {
+        if (!hasTauriInvoke()) return;
+        const unlisten = getCurrentWebviewWindow().listen<SqliteFileRequest>("open-sqlite-file", (event) => {
+            openFile(event.payload);
+        });
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [openFile]);
+}
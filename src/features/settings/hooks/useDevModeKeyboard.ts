@@ -3,10 +3,9 @@ import { useEffect } from "react";
 export const useDevModeKeyboard = (devMode: boolean) => {
     useEffect(() => {
         const handleKeyDown = (e: KeyboardEvent) => {
-            // Always block hard reload inside the app
+            // Always block the webview's own hard reload; the keymap's Reload shortcut still sees the key
             if ((e.ctrlKey || e.metaKey) && (e.key === "r" || e.key === "R")) {
                 e.preventDefault();
-                e.stopPropagation();
                 return;
             }
 
@@ -1,4 +1,6 @@
 import { useState, useEffect, useCallback } from "react";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { hasTauriInvoke } from "@/services/bridge/bridgeClient";
 import { useBridgeQuery } from "@/services/bridge/useBridgeQuery";
 import { useDatabaseDetails } from "@/features/database/hooks/useDatabaseDetails";
 import { Spinner } from "@/components/ui/spinner";
@@ -64,6 +66,19 @@ const SQLWorkspacePanel = ({ dbId }: SQLWorkspacePanelProps) => {
         }
     }, [activeTabId]);
 
+    // The run-query shortcut from the keymap, matched by useAppShortcuts and sent back by the shell
+    useEffect(() => {
+        if (!hasTauriInvoke()) return;
+        const unlisten = getCurrentWebviewWindow().listen<{ action: string }>("shortcut", (event) => {
+            if (event.payload.action === "runQuery") {
+                handleExecuteQuery();
+            }
+        });
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [handleExecuteQuery]);
+
     // Update tab when query changes
     const updateActiveTabQuery = useCallback((newQuery: string) => {
         setTabs(prev => prev.map(tab =>
@@ -0,0 +1,75 @@
+import { useEffect, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { hasTauriInvoke } from "@/services/bridge/bridgeClient";
+
+/** A key press as the shell resolved it from `keymap.json`; `CmdOrCtrl` is already Cmd or Ctrl */
+interface ShortcutKeys {
+    code: string;
+    ctrl: boolean;
+    alt: boolean;
+    shift: boolean;
+    meta: boolean;
+}
+
+interface ShortcutInfo {
+    action: string;
+    scope: "app" | "global";
+    keys: ShortcutKeys | null;
+    status: "ready" | "unbound" | "invalid" | "conflict";
+}
+
+function matches(keys: ShortcutKeys, e: KeyboardEvent) {
+    return e.code === keys.code
+        && e.ctrlKey === keys.ctrl
+        && e.altKey === keys.alt
+        && e.shiftKey === keys.shift
+        && e.metaKey === keys.meta;
+}
+
+function isEditable(target: EventTarget | null) {
+    const el = target as HTMLElement | null;
+    return el?.tagName === "INPUT" || el?.tagName === "TEXTAREA" || !!el?.isContentEditable;
+}
+
+/**
+ * Runs the app-scope shortcuts from `keymap.json` while this window has focus.
+ * The shell only registers global shortcuts with the OS, so app keys are matched here and handed to `shortcuts_run`.
+ */
+export function useAppShortcuts() {
+    const shortcuts = useRef<ShortcutInfo[]>([]);
+
+    useEffect(() => {
+        if (!hasTauriInvoke()) return;
+        const load = () => {
+            invoke<ShortcutInfo[]>("shortcuts_list")
+                .then((list) => {
+                    shortcuts.current = list.filter((s) => s.scope === "app" && s.status === "ready" && s.keys);
+                })
+                .catch((err) => console.error("Failed to load shortcuts:", err));
+        };
+        load();
+        const unlisten = getCurrentWebviewWindow().listen("shortcuts-changed", load);
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, []);
+
+    useEffect(() => {
+        if (!hasTauriInvoke()) return;
+        const handleKeyDown = (e: KeyboardEvent) => {
+            if (e.repeat || e.isComposing) return;
+            const shortcut = shortcuts.current.find((s) => matches(s.keys!, e));
+            if (!shortcut) return;
+            // Keys without Ctrl or Cmd (Alt+Arrow) move the caret while typing
+            if (!shortcut.keys!.ctrl && !shortcut.keys!.meta && isEditable(e.target)) return;
+
+            e.preventDefault();
+            invoke("shortcuts_run", { action: shortcut.action })
+                .catch((err) => console.error(`Shortcut ${shortcut.action} failed:`, err));
+        };
+
+        window.addEventListener("keydown", handleKeyDown);
+        return () => window.removeEventListener("keydown", handleKeyDown);
+    }, []);
+}
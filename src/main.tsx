@@ -8,6 +8,9 @@ import DatabaseDetail from './pages/DatabaseDetails';
 import NotFound from './pages/NotFound';
 import { ThemeProvider } from './components/providers/ThemeProvider';
 import { useBridgeInit } from "@/services/bridge/useBridgeInit";
+import { useSqliteFileOpen } from "@/features/home/hooks/useSqliteFileOpen";
+import { useDeepLinks } from "@/features/home/hooks/useDeepLinks";
+import { useAppShortcuts } from "@/hooks/useAppShortcuts";
 import { useEffect, useState } from 'react';
 import { DeveloperContextMenu } from './components/dev/DeveloperContextMenu';
 import { UpdateNotification } from './components/shared/UpdateNotification';
@@ -22,6 +25,21 @@ function BridgeInitializer() {
   return null;
 }
 
+function SqliteFileOpener() {
+  useSqliteFileOpen();
+  return null;
+}
+
+function DeepLinkHandler() {
+  useDeepLinks();
+  return null;
+}
+
+function AppShortcuts() {
+  useAppShortcuts();
+  return null;
+}
+
 function ThemeVariantInitializer() {
   useEffect(() => {
     // Initialize theme variant from localStorage on mount
@@ -86,6 +104,7 @@ function AppRoot() {
     <ThemeProvider defaultTheme="dark" storageKey="relwave-ui-theme">
       <QueryClientProvider client={queryClient}>
         <BridgeInitializer />
+        <AppShortcuts />
         <ThemeVariantInitializer />
         <UpdateNotification />
         <WhatsNewDialog />
@@ -96,6 +115,8 @@ function AppRoot() {
             <div className="pt-8">
               <BrowserRouter>
                 <CommandPalette />
+                <SqliteFileOpener />
+                <DeepLinkHandler />
                 <AnimatedRoutes />
               </BrowserRouter>
             </div>
@@ -7,7 +7,7 @@ class BridgeService{
        */
       async ping(): Promise<boolean> {
         try {
-          const result = await bridgeRequest("ping", {});
+          const result = await bridgeRequest("health.ping", {});
           return result?.ok === true;
         } catch (error) {
           return false;
@@ -1,6 +1,7 @@
 // src/renderer/src/services/bridgeClient.ts
 import { invoke } from "@tauri-apps/api/core";
-import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { type UnlistenFn } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 
 type Pending = { resolve: (v: any) => void; reject: (e: any) => void };
 const pending = new Map<number, Pending>();
@@ -74,8 +75,11 @@ export async function startBridgeListeners(): Promise<void> {
   }
 
   try {
+    // Listen on this window so responses routed to other windows are not delivered here
+    const appWindow = getCurrentWebviewWindow();
+
     // Listen to bridge stdout forwarded by Rust
-    unlistenStdout = await listen<string>("bridge-stdout", (event) => {
+    unlistenStdout = await appWindow.listen<string>("bridge-stdout", (event) => {
       try {
         const payload = JSON.parse(event.payload);
         if (payload && typeof payload === "object") {
@@ -120,7 +124,7 @@ export async function startBridgeListeners(): Promise<void> {
     });
 
     // Listen to bridge stderr for logs
-    unlistenStderr = await listen<string>("bridge-stderr", (event) => {
+    unlistenStderr = await appWindow.listen<string>("bridge-stderr", (event) => {
       console.debug("bridge-log:", event.payload);
       // Even logs are a sign of life
       lastSuccessfulRequest = Date.now();
@@ -332,27 +336,11 @@ export function stopBridgeListeners(): void {
 }
 
 /**
- * Get appropriate timeout for different operations
+ * Extra wait beyond the shell's timeout before giving up locally.
+ * The shell enforces per-method timeouts and answers with a TIMEOUT error;
+ * this only covers requests it drops, e.g. when the bridge restarts.
  */
-function getTimeoutForMethod(method: string): number {
-  // Ping should be fast
-  if (method === "ping" || method === HEALTH_PING_METHOD) return 5000; // 5 seconds
-  
-  // Schema operations can be very slow on large databases
-  if (method === "db.getSchema") return 180000; // 3 minutes
-
-  // Table listing can be slow on MySQL
-  if (method === "db.listTables") return 120000; // 2 minutes
-
-  // Stats queries are usually fast but can be slow on large DBs
-  if (method === "db.getStats") return 60000; // 1 minute
-
-  // Query operations need longer timeouts
-  if (method.startsWith("query.")) return 300000; // 5 minutes
-
-  // Default for other operations
-  return 30000; // 30 seconds
-}
+const SHELL_TIMEOUT_GRACE_MS = 5000;
 
 /**
  * Check if an error indicates a broken pipe/connection
@@ -376,8 +364,6 @@ async function bridgeRequestInternal(
   params?: any,
   timeoutMs?: number
 ): Promise<any> {
-  const timeout = timeoutMs ?? getTimeoutForMethod(method);
-  
   if (!hasTauriInvoke()) {
     throw new Error(
       "Tauri runtime not available. Run inside the Tauri app (pnpm tauri dev) or provide a browser fallback."
@@ -397,18 +383,8 @@ async function bridgeRequestInternal(
   const startTime = performance.now();
   console.debug(`[Bridge Request ${id}] ${method}`, params);
 
+  let timeoutHandle: ReturnType<typeof setTimeout> | undefined;
   const promise = new Promise((resolve, reject) => {
-    const timeoutHandle = setTimeout(() => {
-      if (pending.has(id)) {
-        pending.delete(id);
-        const elapsed = performance.now() - startTime;
-        console.error(
-          `[Bridge Timeout ${id}] ${method} after ${elapsed.toFixed(0)}ms (limit: ${timeout}ms)`
-        );
-        reject(new Error(`Bridge request timeout after ${timeout}ms: ${method}`));
-      }
-    }, timeout);
-
     const wrappedResolve = (v: any) => {
       clearTimeout(timeoutHandle);
       const elapsed = performance.now() - startTime;
@@ -426,13 +402,40 @@ async function bridgeRequestInternal(
     pending.set(id, { resolve: wrappedResolve, reject: wrappedReject });
   });
 
+  // The shell enforces the timeout (its own per-method table unless one is given) and returns it
+  const write = (confirmation?: string) =>
+    invoke<number>("bridge_write", { data: payload, confirmation, timeoutMs });
+
+  let timeout: number;
   try {
-    await invoke("bridge_write", { data: payload });
+    timeout = await write();
   } catch (error) {
-    pending.delete(id);
-    throw new Error(`Failed to send bridge request: ${error}`);
+    // Production connections: the shell asks the user to confirm, then accepts the retried write
+    let sendError = error;
+    if (String(error).startsWith("confirmation required")) {
+      try {
+        const confirmation = await invoke<string>("safety_confirm", { data: payload });
+        timeout = await write(confirmation);
+        sendError = undefined;
+      } catch (confirmError) {
+        sendError = confirmError;
+      }
+    }
+    if (sendError !== undefined) {
+      pending.delete(id);
+      throw new Error(`Failed to send bridge request: ${sendError}`);
+    }
   }
 
+  timeoutHandle = setTimeout(() => {
+    const entry = pending.get(id);
+    if (entry) {
+      pending.delete(id);
+      console.error(`[Bridge Timeout ${id}] ${method}: no answer from the shell (limit: ${timeout}ms)`);
+      entry.reject(new Error(`Bridge request timeout after ${timeout}ms: ${method}`));
+    }
+  }, timeout + SHELL_TIMEOUT_GRACE_MS);
+
   return promise;
 }
 
@@ -440,7 +443,7 @@ async function bridgeRequestInternal(
  * bridgeRequest - Send a JSON-RPC request to the bridge process with automatic retry
  * @param method - The JSON-RPC method name
  * @param params - The parameters for the method
- * @param timeoutMs - Request timeout in milliseconds (auto-determined if not provided)
+ * @param timeoutMs - Request timeout in milliseconds (the shell's per-method timeout if not provided)
  */
 export async function bridgeRequest(
   method: string,